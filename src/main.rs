@@ -1,11 +1,33 @@
 mod ui {
+    pub mod animation;
     pub mod common;
+    pub mod component_state;
+    pub mod context_menu;
+    pub mod edit_state;
+    pub mod font;
+    pub mod grid;
+    pub mod icon;
+    pub mod image;
     pub mod layout;
+    pub mod markdown;
+    pub mod overlay;
+    pub mod paint;
+    pub mod raster;
     pub mod raw_text;
     pub mod root;
+    pub mod select;
+    pub mod shaping;
+    pub mod span;
+    pub mod style;
+    pub mod svg;
+    pub mod text_area;
     // pub mod text_input;
     pub mod text_input;
     pub mod text_layout;
+    pub mod text_wrap;
+    pub mod theme;
+    pub mod tooltip;
+    pub mod window;
 }
 
 use lazy_static::lazy_static;
@@ -13,12 +35,24 @@ use raylib::prelude::*;
 use std::sync::{Arc, Mutex};
 use std::vec;
 use ui::common::{Length, MouseEvent};
-use ui::root::UIRoot;
-
-use crate::ui::common::{Alignment, Component, def_key_handler};
+use ui::root::{UIRoot, breakpoint};
+
+use crate::ui::animation::Animation;
+use crate::ui::common::{Alignment, Breakpoint, Component, TextAlign, TextOverflow};
+use crate::ui::context_menu::{ContextMenu, MenuItem};
+use crate::ui::edit_state;
+use crate::ui::grid::{Grid, Track};
+use crate::ui::icon::{Icon, icons};
 use crate::ui::layout::Layout;
-use crate::ui::text_input::TextInput;
+use crate::ui::markdown::{Markdown, MarkdownStyle};
+use crate::ui::overlay::OverlayProps;
+use crate::ui::paint::Shadow;
+use crate::ui::select::Select;
+use crate::ui::style::Style;
+use crate::ui::text_area::TextArea;
 use crate::ui::text_layout::TextLayout;
+use crate::ui::theme::{self, ColorToken, Radius, Space, TextSize, Theme};
+use crate::ui::window::WindowConfig;
 
 lazy_static! {
     static ref CHAT_STATE: Arc<Mutex<ChatState>> = Arc::new(Mutex::new(ChatState::new()));
@@ -31,7 +65,20 @@ fn main() {
     {
         CHAT_STATE.lock().unwrap().seed_messages();
     }
-    UIRoot::start(Box::new(||{chat_layout()}), (1000,1000), "HI!");
+    let config = WindowConfig::new()
+        .dim((1000, 1000))
+        .title("HI!")
+        .min_size((400, 300))
+        .on_close_requested(Box::new(|| {
+            let mut chat_state = CHAT_STATE.lock().unwrap();
+            // Ask once before throwing away an unsent draft, close on the second request
+            if chat_state.draft_message.trim().is_empty() || chat_state.close_warning {
+                return true;
+            }
+            chat_state.close_warning = true;
+            false
+        }));
+    UIRoot::start_with_config(Box::new(|| chat_layout()), config);
 }
 
 #[derive(Clone)]
@@ -54,6 +101,7 @@ struct ChatState {
     current_user_id: String,
     draft_message: String,
     show_delete_user_popup: Option<String>,
+    close_warning: bool,
 }
 
 impl ChatState {
@@ -65,6 +113,7 @@ impl ChatState {
             my_id: "0".to_string(),
             draft_message: String::from("Hi!"),
             show_delete_user_popup: None,
+            close_warning: false,
         }
     }
 
@@ -90,6 +139,7 @@ impl ChatState {
         self.add_user("3", "Charlie");
         self.add_user("4", "David");
         self.add_user("5", "Eve");
+        self.add_user("6", "Maximilian Alexander Featherstonehaugh");
         self.current_user_id = "1".to_string(); // Assume Alice is the active chat
     }
 
@@ -118,6 +168,12 @@ impl ChatState {
         self.add_message("I've been good, just busy with work. You?", "2", "0");
         self.add_message("Same here. We should grab coffee sometime.", "0", "2");
         self.add_message("Sounds good! Let's do it.", "2", "0");
+        self.add_message("How about this place? https://www.raylib.com", "0", "2");
+        self.add_message(
+            "**Sure!** Here's the plan:\n\n1. Pick a *quiet* spot\n2. Bring the `laptop`\n\n> Coffee first, code later",
+            "2",
+            "0",
+        );
     }
 
     fn delete_user(&mut self, id: &str) {
@@ -160,24 +216,61 @@ impl ChatState {
     }
 }
 
+/// Shared by the app's buttons, variants override parts of it with `merge`
+fn button_style() -> Style {
+    Style::new()
+        .bg_color(ColorToken::Primary)
+        .text_color(ColorToken::OnPrimary)
+        .border_radius(Radius::Md)
+        .padding(Space::xy(Space::Md, Space::Sm))
+        .main_align(Alignment::Center)
+        .cross_align(Alignment::Center)
+}
+
+fn theme_select_component() -> Component {
+    let dark = theme::current().dark;
+    Select::get_builder("THEME_SELECT")
+        .options(&["Light", "Dark"])
+        .selected(Some(dark as usize))
+        .font_size(TextSize::Sm)
+        .dim((Length::FIXED(110), Length::FIT))
+        .on_change(Box::new(|index| {
+            theme::set_theme(if index == 1 { Theme::dark() } else { Theme::light() });
+        }))
+        .build()
+}
+
+/// Right click menu of a message
+fn message_menu_items(content: String) -> Vec<MenuItem> {
+    vec![
+        MenuItem::new("Copy").on_select(Box::new(move || edit_state::set_clipboard_text(&content))),
+        MenuItem::separator(),
+        MenuItem::new("Theme").submenu(vec![
+            MenuItem::new("Light").on_select(Box::new(|| theme::set_theme(Theme::light()))),
+            MenuItem::new("Dark").on_select(Box::new(|| theme::set_theme(Theme::dark()))),
+        ]),
+    ]
+}
+
 fn users_header() -> Component {
     Layout::get_row_builder()
         .children(vec![
             TextLayout::get_builder()
                 .content("Users:")
-                .font_size(24)
-                .bg_color(Color {
-                    r: 100,
-                    g: 100,
-                    b: 255,
-                    a: 255,
-                })
+                .style(&button_style().merge(&Style::new().padding(Space::Md.all())))
+                .font_size(TextSize::Lg)
                 .dim((Length::FIT, Length::FIT))
-                .padding((10, 10, 10, 10))
                 .build() as Component,
+            theme_select_component(),
         ])
         .dim((Length::FILL, Length::FIT))
-        .main_align(Alignment::Center)
+        // Title on the left, theme picker on the right
+        .main_align(Alignment::SpaceBetween)
+        .cross_align(Alignment::Center)
+        .gap(Space::Md)
+        // The picker moves under the title when the sidebar gets narrow
+        .wrap(true)
+        .cross_gap(Space::Sm)
         .bg_color(Color {
             r: 0,
             g: 0,
@@ -187,7 +280,7 @@ fn users_header() -> Component {
         .build() as Component
 }
 
-fn users_component() -> Vec<Component> {
+fn users_component() -> Component {
     let (users_to_display, my_id, current_user_id) = {
         let chat_state = CHAT_STATE.lock().unwrap();
         let users = chat_state.users.clone();
@@ -196,86 +289,111 @@ fn users_component() -> Vec<Component> {
         (users, my_id, current_user_id)
     };
 
-    users_to_display
+    let cells = users_to_display
         .iter()
         .filter(|user| user.id != my_id)
-        .map(|user| {
+        .flat_map(|user| {
             let user = user.clone();
             let user_id = user.id.clone();
-
-            Layout::get_row_builder()
-                .dim((Length::FILL, Length::FIXED(40)))
-                .gap(10)
-                .on_click(Box::new(move |_mouse_event: MouseEvent| {
-                    CHAT_STATE.lock().unwrap().current_user_id = user_id.clone();
-                    true
-                }))
-                .children(vec![
-                    TextLayout::get_builder()
-                        .content(&user.name)
-                        .dim((Length::FILL, Length::FILL))
-                        .bg_color(if current_user_id == user.id {
-                            Color::LIGHTGREEN
-                        } else {
-                            Color::LIGHTGRAY
+            let is_current = current_user_id == user.id;
+
+            [
+                TextLayout::get_builder()
+                    .content(&user.name)
+                    .wrap(false)
+                    .max_lines(1)
+                    .text_overflow(TextOverflow::Ellipsis)
+                    // Long names are cut off, the tooltip shows them in full
+                    .tooltip(user.name.as_str())
+                    .dim((Length::FILL, Length::FILL))
+                    .bg_color(if is_current {
+                        ColorToken::Secondary
+                    } else {
+                        ColorToken::Surface
+                    })
+                    .text_color(if is_current {
+                        ColorToken::OnSecondary
+                    } else {
+                        ColorToken::Text
+                    })
+                    .cross_align(Alignment::Start)
+                    .main_align(Alignment::Center)
+                    .on_click(Box::new(move |_mouse_event: MouseEvent| {
+                        CHAT_STATE.lock().unwrap().current_user_id = user_id.clone();
+                        true
+                    }))
+                    .build() as Component,
+                Layout::get_col_builder()
+                    .children(vec![
+                        Icon::get_builder()
+                            .svg(icons::TRASH)
+                            .size(20)
+                            .color(ColorToken::OnDanger)
+                            .build(),
+                    ])
+                    .cross_align(Alignment::Center)
+                    .main_align(Alignment::Center)
+                    .tooltip(format!("Delete conversation with {}", user.name))
+                    .on_click({
+                        Box::new(move |_| {
+                            let mut state = CHAT_STATE.lock().unwrap();
+                            state.set_user_to_delete(&user.id);
+                            false
                         })
-                        // .padding((10, 0, 10, 0))
-                        .cross_align(Alignment::Start)
-                        .main_align(Alignment::Center)
-                        .flex(7.0)
-                        .build(),
-                    Layout::get_col_builder()
-                        .children(vec![
-                            TextLayout::get_builder()
-                                .cross_align(Alignment::Center)
-                                .main_align(Alignment::Center)
-                                .content("\\/")
-                                .font_size(10)
-                                .build(),
-                            TextLayout::get_builder()
-                                .cross_align(Alignment::Center)
-                                .main_align(Alignment::Center)
-                                .content("/\\")
-                                .font_size(10)
-                                .build(),
-                        ])
-                        .cross_align(Alignment::Center)
-                        .main_align(Alignment::Center)
-                        .on_click({
-                            Box::new(move |_| {
-                                let mut state = CHAT_STATE.lock().unwrap();
-                                state.set_user_to_delete(&user.id);
-                                false
-                            })
-                        })
-                        .flex(3.0)
-                        .dim((Length::FILL, Length::FILL))
-                        .bg_color(Color::RED)
-                        .build(),
-                ])
-                .build() as Component
+                    })
+                    .dim((Length::FILL, Length::FILL))
+                    .bg_color(ColorToken::Danger)
+                    .build(),
+            ]
         })
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+
+    // One row per user, the name and delete columns line up across rows
+    Grid::get_builder()
+        .columns(vec![Track::Fr(7.0), Track::Fr(3.0)])
+        .auto_rows(Track::Fixed(40))
+        .row_gap(Space::Sm)
+        .column_gap(Space::Md)
+        .children(cells)
+        .dim((Length::FILL, Length::FIT))
+        .dbg_name("USERS")
+        .build()
 }
 
 fn message_component(content: String, is_current_user: bool, idx: usize) -> Component {
+    let bubble = Markdown::get_builder()
+        .content(&content)
+        .hard_breaks(true)
+        .on_link_click(Box::new(|url| raylib::open_url(&url)))
+        .style(MarkdownStyle {
+            text_color: Some(if is_current_user {
+                ColorToken::OnSecondary.into()
+            } else {
+                ColorToken::OnPrimary.into()
+            }),
+            ..MarkdownStyle::new()
+        })
+        .bg_color(if is_current_user {
+            ColorToken::Secondary
+        } else {
+            ColorToken::Primary
+        })
+        // The corner nearest the sender stays sharp like a speech bubble tail
+        .border_radii(if is_current_user {
+            (2, 12, 12, 12)
+        } else {
+            (12, 2, 12, 12)
+        })
+        .shadow(Shadow::new((0, 2), 6, Color::new(0, 0, 0, 70)))
+        .dim((Length::FIT, Length::FIT))
+        .dbg_name(&format!("MSG {}", idx))
+        .padding(Space::xy(Space::Md, Space::Sm))
+        .build();
     Layout::get_col_builder()
         .children(vec![
-            TextLayout::get_builder()
-                .content(&content)
-                .font_size(20)
-                .bg_color(if is_current_user {
-                    Color::LIGHTGREEN
-                } else {
-                    Color::SLATEBLUE
-                })
-                .cross_align(Alignment::Start)
-                .border_width(2)
-                .main_align(Alignment::Center)
-                .dim((Length::FIT, Length::FIT))
-                .dbg_name(&format!("MSG {}", idx))
-                .padding((5, 2, 5, 2))
-                .font_size(20)
+            ContextMenu::get_builder(&format!("MSG_MENU {}", idx))
+                .child(bubble)
+                .items(message_menu_items(content))
                 .build(),
         ])
         .overflow_y(false)
@@ -299,44 +417,37 @@ fn input_box_component() -> Component {
         let chat_state = CHAT_STATE.lock().unwrap();
         chat_state.draft_message.clone()
     };
-    let builder = TextInput::get_builder();
-    let builder = builder
+    TextArea::get_builder("TEXT_INPUT")
         .content(&draft_message)
-        .dbg_name("TEXT_INPUT")
-        .font_size(20)
-        .padding((10, 0, 10, 0))
-        .main_align(Alignment::Center)
-        .on_key(Box::new(move |key_event| {
-            if key_event.key.is_some_and(|v| v == KeyboardKey::KEY_ENTER) {
-                let mut chat_state = CHAT_STATE.lock().unwrap();
-                let content = chat_state.draft_message.clone();
-                if content.trim().is_empty() {
-                    return true;
-                }
-                let current_user_id = chat_state.current_user_id.clone();
-                let my_id = chat_state.my_id.clone();
-                chat_state.add_message(&content, &my_id, &current_user_id);
-                chat_state.draft_message.clear();
+        .font_size(TextSize::Md)
+        .padding(Space::xy(Space::Md, Space::Sm))
+        .submit_on_enter(true)
+        .on_change(Box::new(|content| {
+            CHAT_STATE.lock().unwrap().draft_message = content;
+        }))
+        .on_submit(Box::new(|content| {
+            if content.trim().is_empty() {
+                return;
             }
             let mut chat_state = CHAT_STATE.lock().unwrap();
-            def_key_handler(key_event, &mut chat_state.draft_message);
-            true
+            let current_user_id = chat_state.current_user_id.clone();
+            let my_id = chat_state.my_id.clone();
+            chat_state.add_message(&content, &my_id, &current_user_id);
+            chat_state.draft_message.clear();
         }))
-        .bg_color(Color::LIGHTGRAY)
+        .bg_color(ColorToken::Surface)
         .dim((Length::FILL, Length::FILL))
         .flex(8.0)
-        .build();
-    builder
+        .build()
 }
 
 fn send_button_component() -> Component {
     TextLayout::get_builder()
         .content("Send")
-        .font_size(20)
-        .bg_color(Color::DARKGRAY)
+        .style(&button_style().merge(&Style::new().border_radius(0)))
+        .font_size(TextSize::Md)
         .dim((Length::FILL, Length::FILL))
-        .main_align(Alignment::Center)
-        .cross_align(Alignment::Center)
+        .text_align(TextAlign::Center)
         .flex(2.0)
         .on_click(Box::new(move |_mouse_event| {
             let mut chat_state = CHAT_STATE.lock().unwrap();
@@ -386,20 +497,75 @@ fn input_row_component() -> Component {
         .build() as Component
 }
 
+fn close_warning_component() -> Component {
+    TextLayout::get_builder()
+        .content("You have an unsent draft. Close the window again to discard it.")
+        .font_size(TextSize::Sm)
+        .text_color(ColorToken::OnWarning)
+        .bg_color(ColorToken::Warning)
+        .dim((Length::FILL, Length::FIT))
+        .padding(Space::xy(Space::Md, Space::Sm))
+        .dbg_name("CLOSE_WARNING")
+        .flex(0.0)
+        .build()
+}
+
+fn collapsed_users_component() -> Vec<Component> {
+    let (users_to_display, my_id, current_user_id) = {
+        let chat_state = CHAT_STATE.lock().unwrap();
+        let users = chat_state.users.clone();
+        let my_id = chat_state.my_id.clone();
+        let current_user_id = chat_state.current_user_id.clone();
+        (users, my_id, current_user_id)
+    };
+
+    users_to_display
+        .iter()
+        .filter(|user| user.id != my_id)
+        .map(|user| {
+            let user_id = user.id.clone();
+            let initial = user.name.chars().next().unwrap_or(' ').to_string();
+            TextLayout::get_builder()
+                .content(&initial)
+                .dim((Length::FILL, Length::FIXED(40)))
+                .text_color(if current_user_id == user.id {
+                    ColorToken::OnSecondary
+                } else {
+                    ColorToken::Text
+                })
+                .bg_color(if current_user_id == user.id {
+                    ColorToken::Secondary
+                } else {
+                    ColorToken::Surface
+                })
+                .cross_align(Alignment::Center)
+                .main_align(Alignment::Center)
+                .on_click(Box::new(move |_mouse_event: MouseEvent| {
+                    CHAT_STATE.lock().unwrap().current_user_id = user_id.clone();
+                    true
+                }))
+                .build() as Component
+        })
+        .collect::<Vec<_>>()
+}
+
 fn left_sidebar_component() -> Component {
-    let header = users_header();
-    let mut children = vec![header];
-    let users = users_component();
-    children.extend(users);
+    // Narrow windows only get the users' initials so the chat area keeps its room
+    let collapsed = breakpoint() == Breakpoint::Compact;
+    let children = if collapsed {
+        collapsed_users_component()
+    } else {
+        vec![users_header(), users_component()]
+    };
     Layout::get_col_builder()
         .children(children)
         .dim((Length::FILL, Length::FILL))
         .cross_align(Alignment::Center)
-        .bg_color(Color::BISQUE)
-        .padding((10, 5, 10, 5))
+        .bg_color(ColorToken::SurfaceAlt)
+        .padding(if collapsed { Space::Sm.all() } else { Space::xy(Space::Md, Space::Sm) })
         .dbg_name("LEFT_SIDEBAR")
-        .gap(5)
-        .flex(1.0)
+        .gap(Space::Sm)
+        .flex(if collapsed { 0.25 } else { 1.0 })
         .build()
 }
 
@@ -411,23 +577,30 @@ fn chat_area_component() -> Component {
             Layout::get_col_builder()
                 // .bg_color(Color::BEIGE)
                 .children(children)
-                .gap(2)
+                .gap(Space::Xs)
                 .build(),
         ])
         .flex(19f32)
         .build();
     let input_row = input_row_component();
 
+    let mut children: Vec<Component> = vec![messages];
+    let close_warning = { CHAT_STATE.lock().unwrap().close_warning };
+    if close_warning {
+        children.push(close_warning_component());
+    }
+    children.push(input_row);
+
     Layout::get_col_builder()
         .dim((Length::FILL, Length::FILL))
         .main_align(Alignment::Center)
         .overflow_y(false)
         .flex(2.0)
-        .children(vec![messages, input_row])
+        .children(children)
         .build()
 }
 
-fn delete_user_popup() -> Component {
+fn delete_user_popup() {
     let user_to_delete = {
         let mut state = CHAT_STATE.lock().unwrap();
         let del_user_id = &state.show_delete_user_popup;
@@ -444,7 +617,7 @@ fn delete_user_popup() -> Component {
     };
 
     if user_to_delete.is_none() {
-        return Layout::get_row_builder().flex(0.0).build();
+        return;
     }
 
     let user_to_delete = user_to_delete.unwrap();
@@ -459,18 +632,20 @@ fn delete_user_popup() -> Component {
         .build();
 
     let button_builder = TextLayout::get_builder()
-        .cross_align(Alignment::Center)
-        .main_align(Alignment::Center)
-        .dim((Length::FIT_PER(120), Length::FIT_PER(120)))
-        .padding((0, 0, 0, 0));
+        .style(&button_style())
+        .dim((Length::FIT_PER(120), Length::FIT_PER(120)));
 
     let buttons = Layout::get_row_builder()
-        .gap(20)
+        .gap(Space::Xl)
         .children(vec![
             button_builder
                 .clone()
                 .content("YES")
-                .bg_color(Color::GREEN)
+                .style(
+                    &Style::new()
+                        .bg_color(ColorToken::Secondary)
+                        .text_color(ColorToken::OnSecondary),
+                )
                 .on_click(Box::new(move |_| {
                     let mut state = CHAT_STATE.lock().unwrap();
                     state.delete_user(&user_to_delete.id);
@@ -480,7 +655,11 @@ fn delete_user_popup() -> Component {
             button_builder
                 .clone()
                 .content("NO")
-                .bg_color(Color::RED)
+                .style(
+                    &Style::new()
+                        .bg_color(ColorToken::Danger)
+                        .text_color(ColorToken::OnDanger),
+                )
                 .on_click(Box::new(|_| {
                     let mut state = CHAT_STATE.lock().unwrap();
                     state.clear_user_to_delete();
@@ -494,36 +673,35 @@ fn delete_user_popup() -> Component {
     let container = Layout::get_col_builder()
         .dim((Length::FIT_PER(105), Length::FIT_PER(120)))
         // .padding((0, 12, 0, 10))
-        .gap(10)
+        .gap(Space::Md)
         .children(vec![header, buttons])
-        .bg_color(Color::ROYALBLUE)
+        .bg_color(ColorToken::Primary)
+        // The header inherits this, the buttons set their own
+        .text_color(ColorToken::OnPrimary)
         .main_align(Alignment::Start)
         .cross_align(Alignment::Center)
-        .on_click(Box::new(|_| false))
         .dbg_name("OVERLAY_HEADER_CONT")
+        .enter(Animation::slide_in((0, 24), 200))
         .build();
 
-    Layout::get_col_builder()
-        .set_position(ui::common::Position::Sticky(0, 0))
-        .dim((Length::FILL, Length::FILL))
+    OverlayProps::modal()
+        .content(container)
         .dbg_name("OVERLAY")
+        .enter(Animation::fade_in(150))
+        .exit(Animation::slide_out((0, 24), 150))
         .padding((50, 0, 50, 0))
-        .cross_align(Alignment::Center)
-        .main_align(Alignment::Center)
-        .on_click(Box::new(|_| {
+        .on_dismiss(Box::new(|| {
             let mut state = CHAT_STATE.lock().unwrap();
             state.clear_user_to_delete();
-            false
         }))
-        .children(vec![container])
-        .build()
+        .show();
 }
 
 fn chat_layout() -> Component {
     let left_sidebar = left_sidebar_component();
     let chat_area = chat_area_component();
 
-    let mut children = vec![left_sidebar, chat_area];
+    let children = vec![left_sidebar, chat_area];
 
     let show_popup = {
         let chat_state = CHAT_STATE.lock().unwrap();
@@ -534,13 +712,13 @@ fn chat_layout() -> Component {
         }
     };
     if show_popup {
-        children.push(delete_user_popup());
+        delete_user_popup();
     }
 
     Layout::get_row_builder()
         .dim((Length::FILL, Length::FILL))
         .children(children)
         .dbg_name("ROOT_LAYOUT")
-        .bg_color(Color::BEIGE)
+        .bg_color(ColorToken::Background)
         .build()
 }
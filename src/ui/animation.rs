@@ -0,0 +1,511 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+};
+
+use raylib::{color::Color, ffi};
+
+use crate::ui::{common::Component, paint};
+
+/// Timing curve mapping elapsed time to progress, both from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// CSS `cubic-bezier(x1, y1, x2, y2)`
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier((0.42, 0.0, 1.0, 1.0), t),
+            Easing::EaseOut => cubic_bezier((0.0, 0.0, 0.58, 1.0), t),
+            Easing::EaseInOut => cubic_bezier((0.42, 0.0, 0.58, 1.0), t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier((x1, y1, x2, y2), t),
+        }
+    }
+}
+
+fn cubic_bezier((x1, y1, x2, y2): (f32, f32, f32, f32), x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, s: f32| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+    };
+    // Finds the curve parameter for `x` by bisection, x is monotonic for x1, x2 in 0..1
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..24 {
+        let mid = (low + high) / 2.0;
+        if bezier(x1, x2, mid) < x {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    bezier(y1, y2, (low + high) / 2.0)
+}
+
+/// Duration, easing and delay of a change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub duration: f32,
+    pub easing: Easing,
+    pub delay: f32,
+}
+
+impl Transition {
+    pub fn new(duration_ms: u32) -> Self {
+        Self {
+            duration: duration_ms as f32 / 1000.0,
+            easing: Easing::EaseInOut,
+            delay: 0.0,
+        }
+    }
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+    pub fn delay(mut self, delay_ms: u32) -> Self {
+        self.delay = delay_ms as f32 / 1000.0;
+        self
+    }
+    /// Eased progress `elapsed` seconds after the change
+    fn progress(&self, elapsed: f32) -> f32 {
+        let elapsed = elapsed - self.delay;
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        self.easing.apply(elapsed / self.duration)
+    }
+    fn is_done(&self, elapsed: f32) -> bool {
+        elapsed >= self.delay + self.duration
+    }
+}
+
+/// Layout properties that can transition between rebuilds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Property {
+    BgColor,
+    Opacity,
+    Offset,
+    Size,
+}
+
+/// Property values at one point of an animation, unset ones are left alone.
+/// Opacity multiplies the layout's own, offset adds to it and colour replaces it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keyframe {
+    pub opacity: Option<f32>,
+    pub offset: Option<(i32, i32)>,
+    pub bg_color: Option<Color>,
+}
+
+impl Keyframe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+    pub fn offset(mut self, offset: (i32, i32)) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.bg_color = Some(color.into());
+        self
+    }
+}
+
+/// Keyframe animation, frames are placed at 0.0 (start) to 1.0 (end) of each iteration
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub frames: Vec<(f32, Keyframe)>,
+    pub timing: Transition,
+    /// `None` repeats forever
+    pub iterations: Option<u32>,
+    /// Every other iteration runs backwards
+    pub alternate: bool,
+}
+
+impl Animation {
+    pub fn new(duration_ms: u32) -> Self {
+        Self {
+            frames: vec![],
+            timing: Transition::new(duration_ms),
+            iterations: Some(1),
+            alternate: false,
+        }
+    }
+    pub fn keyframe(mut self, at: f32, frame: Keyframe) -> Self {
+        self.frames.push((at.clamp(0.0, 1.0), frame));
+        self.frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.timing = self.timing.easing(easing);
+        self
+    }
+    pub fn delay(mut self, delay_ms: u32) -> Self {
+        self.timing = self.timing.delay(delay_ms);
+        self
+    }
+    pub fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = Some(iterations);
+        self
+    }
+    pub fn infinite(mut self) -> Self {
+        self.iterations = None;
+        self
+    }
+    pub fn alternate(mut self, alternate: bool) -> Self {
+        self.alternate = alternate;
+        self
+    }
+
+    pub fn fade_in(duration_ms: u32) -> Self {
+        Animation::new(duration_ms)
+            .keyframe(0.0, Keyframe::new().opacity(0.0))
+            .keyframe(1.0, Keyframe::new().opacity(1.0))
+    }
+    pub fn fade_out(duration_ms: u32) -> Self {
+        Animation::new(duration_ms)
+            .keyframe(0.0, Keyframe::new().opacity(1.0))
+            .keyframe(1.0, Keyframe::new().opacity(0.0))
+    }
+    /// Fades in while moving from `from` to the layout's place
+    pub fn slide_in(from: (i32, i32), duration_ms: u32) -> Self {
+        Animation::new(duration_ms)
+            .easing(Easing::EaseOut)
+            .keyframe(0.0, Keyframe::new().opacity(0.0).offset(from))
+            .keyframe(1.0, Keyframe::new().opacity(1.0).offset((0, 0)))
+    }
+    /// Fades out while moving from the layout's place by `to`
+    pub fn slide_out(to: (i32, i32), duration_ms: u32) -> Self {
+        Animation::new(duration_ms)
+            .easing(Easing::EaseIn)
+            .keyframe(0.0, Keyframe::new().opacity(1.0).offset((0, 0)))
+            .keyframe(1.0, Keyframe::new().opacity(0.0).offset(to))
+    }
+
+    fn is_done(&self, elapsed: f32) -> bool {
+        match self.iterations {
+            Some(iterations) => elapsed >= self.timing.delay + self.timing.duration * iterations as f32,
+            None => false,
+        }
+    }
+
+    /// Values `elapsed` seconds after the start, the last frame holds once finished
+    fn sample(&self, elapsed: f32) -> Keyframe {
+        let duration = self.timing.duration.max(f32::EPSILON);
+        let run = ((elapsed - self.timing.delay) / duration).max(0.0);
+        let run = match self.iterations {
+            Some(iterations) => run.min(iterations as f32),
+            None => run,
+        };
+        let mut iteration = run.floor();
+        let mut t = run - iteration;
+        // Finishing exactly on an iteration boundary shows the end of that iteration
+        if t == 0.0 && run > 0.0 {
+            iteration -= 1.0;
+            t = 1.0;
+        }
+        if self.alternate && iteration as u32 % 2 == 1 {
+            t = 1.0 - t;
+        }
+        let t = self.timing.easing.apply(t);
+        Keyframe {
+            opacity: sample_frames(&self.frames, t, |frame| frame.opacity.map(Value::Number))
+                .map(Value::number),
+            offset: sample_frames(&self.frames, t, |frame| {
+                frame.offset.map(|(x, y)| Value::Pair(x as f32, y as f32))
+            })
+            .map(|value| {
+                let (x, y) = value.pair();
+                (x.round() as i32, y.round() as i32)
+            }),
+            bg_color: sample_frames(&self.frames, t, |frame| frame.bg_color.map(Value::Color))
+                .map(Value::color),
+        }
+    }
+}
+
+/// Interpolates one property between the frames around `t` that set it
+fn sample_frames(frames: &[(f32, Keyframe)], t: f32, get: impl Fn(&Keyframe) -> Option<Value>) -> Option<Value> {
+    let mut before: Option<(f32, Value)> = None;
+    for (at, frame) in frames.iter() {
+        let Some(value) = get(frame) else {
+            continue;
+        };
+        if *at <= t {
+            before = Some((*at, value));
+            continue;
+        }
+        return Some(match before {
+            Some((before_at, before_value)) => {
+                before_value.lerp(value, (t - before_at) / (at - before_at).max(f32::EPSILON))
+            }
+            None => value,
+        });
+    }
+    before.map(|(_, value)| value)
+}
+
+/// Everything animated about one layout
+#[derive(Debug, Clone, Default)]
+pub struct Motion {
+    pub transitions: Vec<(Property, Transition)>,
+    pub animation: Option<Animation>,
+    pub enter: Option<Animation>,
+    pub exit: Option<Animation>,
+}
+
+impl Motion {
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty() && self.animation.is_none() && self.enter.is_none() && self.exit.is_none()
+    }
+}
+
+/// Values a layout draws with this frame
+#[derive(Debug, Clone, Copy)]
+pub struct Animated {
+    pub bg_color: Color,
+    pub opacity: f32,
+    pub offset: (i32, i32),
+    pub size: (i32, i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Number(f32),
+    Pair(f32, f32),
+    Color(Color),
+}
+
+impl Value {
+    fn same(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Pair(ax, ay), Value::Pair(bx, by)) => ax == bx && ay == by,
+            (Value::Color(a), Value::Color(b)) => (a.r, a.g, a.b, a.a) == (b.r, b.g, b.b, b.a),
+            _ => false,
+        }
+    }
+    fn lerp(self, to: Value, t: f32) -> Value {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        match (self, to) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(mix(a, b)),
+            (Value::Pair(ax, ay), Value::Pair(bx, by)) => Value::Pair(mix(ax, bx), mix(ay, by)),
+            (Value::Color(a), Value::Color(b)) => {
+                let channel = |a: u8, b: u8| mix(a as f32, b as f32).round() as u8;
+                Value::Color(Color::new(
+                    channel(a.r, b.r),
+                    channel(a.g, b.g),
+                    channel(a.b, b.b),
+                    channel(a.a, b.a),
+                ))
+            }
+            _ => to,
+        }
+    }
+    fn number(self) -> f32 {
+        match self {
+            Value::Number(n) => n,
+            _ => 0.0,
+        }
+    }
+    fn pair(self) -> (f32, f32) {
+        match self {
+            Value::Pair(x, y) => (x, y),
+            _ => (0.0, 0.0),
+        }
+    }
+    fn color(self) -> Color {
+        match self {
+            Value::Color(color) => color,
+            _ => Color::new(0, 0, 0, 0),
+        }
+    }
+}
+
+/// A property on its way from `from` to `to`
+struct Tween {
+    from: Value,
+    to: Value,
+    start: f64,
+}
+
+struct Entry {
+    first_seen: f64,
+    tweens: HashMap<Property, Tween>,
+    exit: Option<Animation>,
+}
+
+/// A layout removed in the last rebuild, drawn from the old tree until its exit finishes
+struct Ghost {
+    component: Component,
+    exit: Animation,
+    start: f64,
+}
+
+thread_local! {
+    static NOW: Cell<f64> = Cell::new(0.0);
+    static ENTRIES: RefCell<HashMap<String, Entry>> = RefCell::new(HashMap::new());
+    static SEEN: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    static GHOSTS: RefCell<Vec<Ghost>> = RefCell::new(vec![]);
+    static RUNNING: Cell<bool> = Cell::new(false);
+}
+
+fn now() -> f64 {
+    NOW.with(|now| now.get())
+}
+
+/// Whether something is still moving, so the next frame has to be rebuilt
+pub fn needs_frame() -> bool {
+    RUNNING.with(|running| running.get()) || GHOSTS.with(|ghosts| !ghosts.borrow().is_empty())
+}
+
+/// Starts a rebuild at `time` seconds
+pub fn begin_frame(time: f64) {
+    NOW.with(|now| now.set(time));
+    RUNNING.with(|running| running.set(false));
+    SEEN.with(|seen| seen.borrow_mut().clear());
+}
+
+/// Ends a rebuild. Layouts of the `previous` trees that are gone and have an exit animation
+/// become ghosts, and state of everything else that's gone is dropped.
+pub fn end_frame(previous: &[Component]) {
+    for root in previous {
+        collect_ghosts(root);
+    }
+    let seen = SEEN.with(|seen| seen.borrow().clone());
+    ENTRIES.with(|entries| entries.borrow_mut().retain(|key, _| seen.contains(key)));
+}
+
+fn collect_ghosts(component: &Component) {
+    let id = component.borrow().get_id();
+    let exit = if SEEN.with(|seen| seen.borrow().contains(&id)) {
+        None
+    } else {
+        ENTRIES.with(|entries| entries.borrow().get(&id).and_then(|entry| entry.exit.clone()))
+    };
+    if let Some(exit) = exit {
+        // Its children leave with it
+        GHOSTS.with(|ghosts| {
+            ghosts.borrow_mut().push(Ghost {
+                component: component.clone(),
+                exit,
+                start: now(),
+            })
+        });
+        return;
+    }
+    for child in component.borrow().get_children() {
+        collect_ghosts(&child);
+    }
+}
+
+/// Draws the layouts still running their exit animation where they were last laid out
+pub fn draw_ghosts(mut draw: impl FnMut(&Component)) {
+    let ghosts = GHOSTS.with(|ghosts| {
+        let mut ghosts = ghosts.borrow_mut();
+        ghosts.retain(|ghost| !ghost.exit.is_done((now() - ghost.start) as f32));
+        ghosts
+            .iter()
+            .map(|ghost| (ghost.component.clone(), ghost.exit.sample((now() - ghost.start) as f32)))
+            .collect::<Vec<_>>()
+    });
+    for (component, frame) in ghosts {
+        let (x, y) = frame.offset.unwrap_or((0, 0));
+        paint::with_opacity(frame.opacity.unwrap_or(1.0), || unsafe {
+            // Ghosts aren't laid out again, so they're moved with the transform instead
+            ffi::rlPushMatrix();
+            ffi::rlTranslatef(x as f32, y as f32, 0.0);
+            draw(&component);
+            ffi::rlPopMatrix();
+        });
+    }
+}
+
+/// Resolves the layout `key`'s animated values for this frame from its `target` ones
+pub fn animate(key: &str, motion: &Motion, target: Animated) -> Animated {
+    SEEN.with(|seen| seen.borrow_mut().insert(key.to_string()));
+    let now = now();
+    ENTRIES.with(|entries| {
+        let mut entries = entries.borrow_mut();
+        let entry = entries.entry(key.to_string()).or_insert_with(|| Entry {
+            first_seen: now,
+            tweens: HashMap::new(),
+            exit: None,
+        });
+        entry.exit = motion.exit.clone();
+
+        let mut animated = target;
+        let mut running = false;
+        for (property, transition) in motion.transitions.iter() {
+            let target_value = match property {
+                Property::BgColor => Value::Color(target.bg_color),
+                Property::Opacity => Value::Number(target.opacity),
+                Property::Offset => Value::Pair(target.offset.0 as f32, target.offset.1 as f32),
+                Property::Size => Value::Pair(target.size.0 as f32, target.size.1 as f32),
+            };
+            let tween = entry.tweens.entry(*property).or_insert(Tween {
+                from: target_value,
+                to: target_value,
+                start: now,
+            });
+            let elapsed = (now - tween.start) as f32;
+            let current = tween.from.lerp(tween.to, transition.progress(elapsed));
+            if !tween.to.same(&target_value) {
+                // Retargeting mid-way continues from wherever the value is now
+                *tween = Tween {
+                    from: current,
+                    to: target_value,
+                    start: now,
+                };
+            }
+            let elapsed = (now - tween.start) as f32;
+            running |= !transition.is_done(elapsed);
+            let value = tween.from.lerp(tween.to, transition.progress(elapsed));
+            match property {
+                Property::BgColor => animated.bg_color = value.color(),
+                Property::Opacity => animated.opacity = value.number(),
+                Property::Offset => {
+                    let (x, y) = value.pair();
+                    animated.offset = (x.round() as i32, y.round() as i32);
+                }
+                Property::Size => {
+                    let (w, h) = value.pair();
+                    animated.size = (w.round() as i32, h.round() as i32);
+                }
+            }
+        }
+
+        let elapsed = (now - entry.first_seen) as f32;
+        for animation in [&motion.enter, &motion.animation].into_iter().flatten() {
+            running |= !animation.is_done(elapsed);
+            let frame = animation.sample(elapsed);
+            if let Some(opacity) = frame.opacity {
+                animated.opacity *= opacity.clamp(0.0, 1.0);
+            }
+            if let Some((x, y)) = frame.offset {
+                animated.offset = (animated.offset.0 + x, animated.offset.1 + y);
+            }
+            if let Some(color) = frame.bg_color {
+                animated.bg_color = color;
+            }
+        }
+        if running {
+            RUNNING.with(|flag| flag.set(true));
+        }
+        animated
+    })
+}
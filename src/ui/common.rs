@@ -1,6 +1,9 @@
 use raylib::prelude::*;
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+use crate::ui::style::TextStyle;
+use crate::ui::tooltip::Tooltip;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Length {
     FILL,
@@ -21,6 +24,32 @@ pub enum Alignment {
     Start,
     Center,
     End,
+    /// Main axis only, free space between children, none at the ends
+    SpaceBetween,
+    /// Main axis only, free space around each child, halved at the ends
+    SpaceAround,
+    /// Main axis only, equal free space between children and at the ends
+    SpaceEvenly,
+    /// Cross axis only, children are sized to fill the line
+    Stretch,
+    /// Cross axis of rows only, children line up on their first text baseline
+    Baseline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    /// Stretches wrapped lines to the full width, the last line stays left aligned
+    Justify,
+}
+
+/// What happens to text cut off by `max_lines` or a width it can't wrap in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextOverflow {
+    Clip,
+    Ellipsis,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -29,11 +58,137 @@ pub enum ID {
     Manual(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Position {
     Auto,
-    Sticky(i32, i32),
-    Abs(i32, i32),
+    /// Laid out in the flow, then moved by (x, y) without moving its siblings.
+    /// Percentages are of the parent's size.
+    Relative(Offset, Offset),
+    /// Out of the flow, scrolls with the parent
+    Abs(Placement),
+    /// Out of the flow, stays put when the parent scrolls
+    Sticky(Placement),
+}
+
+impl Position {
+    /// `Abs` at (x, y) from the parent's top left corner
+    pub fn abs(x: i32, y: i32) -> Self {
+        Position::Abs(Placement::new().left(x).top(y))
+    }
+    /// `Sticky` at (x, y) from the parent's top left corner
+    pub fn sticky(x: i32, y: i32) -> Self {
+        Position::Sticky(Placement::new().left(x).top(y))
+    }
+    pub fn placement(&self) -> Option<&Placement> {
+        match self {
+            Position::Abs(placement) | Position::Sticky(placement) => Some(placement),
+            _ => None,
+        }
+    }
+    /// Placed against another component instead of the parent
+    pub fn is_anchored(&self) -> bool {
+        self.placement().is_some_and(|placement| placement.anchor.is_some())
+    }
+}
+
+/// Distance in pixels or in percent of a reference size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offset {
+    Px(i32),
+    Percent(f32),
+}
+
+impl Offset {
+    pub fn resolve(&self, reference: i32) -> i32 {
+        match self {
+            Offset::Px(px) => *px,
+            Offset::Percent(percent) => (reference as f32 * percent / 100.0).round() as i32,
+        }
+    }
+}
+
+impl From<i32> for Offset {
+    fn from(px: i32) -> Self {
+        Offset::Px(px)
+    }
+}
+
+/// Where an out of flow component goes. Edges are distances inward from the same edge of
+/// the reference box, the parent or the `anchor`, percentages are of its size. `left` wins
+/// over `right` and `top` over `bottom`, unset axes start at the top left.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Placement {
+    pub left: Option<Offset>,
+    pub top: Option<Offset>,
+    pub right: Option<Offset>,
+    pub bottom: Option<Offset>,
+    /// Id of the component to place against, e.g. the button a dropdown belongs to
+    pub anchor: Option<String>,
+}
+
+impl Placement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn left(mut self, left: impl Into<Offset>) -> Self {
+        self.left = Some(left.into());
+        self
+    }
+    pub fn top(mut self, top: impl Into<Offset>) -> Self {
+        self.top = Some(top.into());
+        self
+    }
+    pub fn right(mut self, right: impl Into<Offset>) -> Self {
+        self.right = Some(right.into());
+        self
+    }
+    pub fn bottom(mut self, bottom: impl Into<Offset>) -> Self {
+        self.bottom = Some(bottom.into());
+        self
+    }
+    pub fn anchor(mut self, id: &str) -> Self {
+        self.anchor = Some(id.to_string());
+        self
+    }
+
+    /// Top left corner of a box of `size` placed in `reference` (x, y, w, h)
+    pub fn resolve(&self, reference: (i32, i32, i32, i32), size: (i32, i32)) -> (i32, i32) {
+        let (x, y, w, h) = reference;
+        let left = match (self.left, self.right) {
+            (Some(left), _) => left.resolve(w),
+            (None, Some(right)) => w - size.0 - right.resolve(w),
+            (None, None) => 0,
+        };
+        let top = match (self.top, self.bottom) {
+            (Some(top), _) => top.resolve(h),
+            (None, Some(bottom)) => h - size.1 - bottom.resolve(h),
+            (None, None) => 0,
+        };
+        (x + left, y + top)
+    }
+}
+
+/// Coarse window width classes for responsive layouts.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Breakpoint {
+    /// Narrower than 600px
+    Compact,
+    /// 600px up to 1024px
+    Medium,
+    /// 1024px and wider
+    Expanded,
+}
+
+impl Breakpoint {
+    pub fn from_width(width: i32) -> Self {
+        if width < 600 {
+            Breakpoint::Compact
+        } else if width < 1024 {
+            Breakpoint::Medium
+        } else {
+            Breakpoint::Expanded
+        }
+    }
 }
 
 pub fn get_drawable_y_and_h(
@@ -138,6 +293,24 @@ pub trait Base {
     fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
         Vec::new()
     }
+    /// Hands text properties inherited from ancestors down the tree, runs before measuring
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        for child in self.get_children() {
+            child.borrow_mut().inherit_text_style(inherited);
+        }
+    }
+
+    /// Cross axis alignment overriding the parent's `cross_align`
+    fn get_align_self(&self) -> Option<Alignment> {
+        None
+    }
+    /// Distance from the top of the component to its first text baseline, valid after
+    /// its position is measured
+    fn get_baseline(&self) -> Option<i32> {
+        None
+    }
+    /// Space the parent forces on each axis for `Stretch` alignment, applied by `set_raw_dim`
+    fn set_stretch(&mut self, _stretch: (Option<i32>, Option<i32>)) {}
 
     fn get_id(&self) -> String;
     fn get_by_id(&self, id: &str) -> Option<Rc<RefCell<dyn Base>>>;
@@ -145,12 +318,27 @@ pub trait Base {
         false
     }
     fn get_position(&self) -> Position;
+    /// Stacking order among out of flow components, higher is drawn later and hit first
+    fn get_z_index(&self) -> i32 {
+        0
+    }
+    /// Shown after the pointer rests on the component for a moment
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        None
+    }
+}
+
+/// Whether a parent skips `component` when drawing, the root draws these afterwards in
+/// `z_index` order
+pub fn draws_later(component: &dyn Base) -> bool {
+    matches!(component.get_position(), Position::Abs(_) | Position::Sticky(_)) || component.get_z_index() != 0
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct MouseEvent {
     pub pos: (i32, i32),
     pub left_button_down: bool,
+    pub right_button_down: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -159,6 +347,12 @@ pub struct ScrollEvent {
     pub delta: i32,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct ResizeEvent {
+    pub dim: (i32, i32),
+    pub prev_dim: (i32, i32),
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct KeyEvent {
     pub key: Option<KeyboardKey>,
@@ -171,7 +365,7 @@ pub fn get_draw_dim(
     parent_dim: (i32, i32),
     children: &Vec<Rc<RefCell<dyn Base>>>,
     direction: Direction,
-    border_width: i32,
+    border_widths: (i32, i32, i32, i32),
 ) -> (i32, i32) {
     let (width, height) = dim;
     let draw_width = match width {
@@ -227,8 +421,8 @@ pub fn get_draw_dim(
     };
     // (draw_width,draw_height)
     (
-        draw_width - border_width * 2,
-        draw_height - border_width * 2,
+        draw_width - border_widths.0 - border_widths.2,
+        draw_height - border_widths.1 - border_widths.3,
     )
 }
 
@@ -0,0 +1,22 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+};
+
+thread_local! {
+    static STATES: RefCell<HashMap<(TypeId, String), Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Runs `f` on the `S` kept for the component with id `id`, starting from `S::default()`.
+/// Components are rebuilt on every event, so what they must remember lives here instead.
+/// Auto ids shift as rows come and go, stateful components should have a stable name.
+pub fn with_state<S: Default + 'static, R>(id: &str, f: impl FnOnce(&mut S) -> R) -> R {
+    STATES.with(|states| {
+        let mut states = states.borrow_mut();
+        let state = states
+            .entry((TypeId::of::<S>(), id.to_string()))
+            .or_insert_with(|| Box::new(S::default()));
+        f(state.downcast_mut().expect("state is keyed by its type"))
+    })
+}
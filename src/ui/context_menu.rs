@@ -0,0 +1,384 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use raylib::{color::Color, prelude::RaylibDrawHandle};
+
+use crate::ui::{
+    common::{
+        AbsoluteDraw, Alignment, Base, Component, KeyEvent, Length, MouseEvent, Offset, Placement,
+        Position, tabbed_print,
+    },
+    component_state,
+    layout::{Layout, LayoutProps},
+    overlay::OverlayProps,
+    style::Style,
+    text_layout::TextLayout,
+    theme::{ColorToken, Radius, Space},
+    tooltip::Tooltip,
+};
+
+/// Where a context menu was opened and which submenus are open in it
+#[derive(Debug, Clone, Default)]
+struct MenuState {
+    at: Option<(i32, i32)>,
+    // Index of the open submenu's item on each level
+    path: Vec<usize>,
+}
+
+fn with_state<R>(id: &str, f: impl FnOnce(&mut MenuState) -> R) -> R {
+    component_state::with_state(id, f)
+}
+
+/// Entry of a context menu, an action, a submenu or a separator line
+#[derive(Clone)]
+pub struct MenuItem {
+    pub label: String,
+    pub on_select: Rc<RefCell<dyn FnMut()>>,
+    pub submenu: Vec<MenuItem>,
+    pub separator: bool,
+    pub disabled: bool,
+}
+
+impl MenuItem {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            on_select: Rc::new(RefCell::new(|| {})),
+            submenu: vec![],
+            separator: false,
+            disabled: false,
+        }
+    }
+    pub fn separator() -> Self {
+        Self {
+            separator: true,
+            ..Self::new("")
+        }
+    }
+    pub fn on_select(mut self, f: Box<dyn FnMut()>) -> Self {
+        self.on_select = Rc::new(RefCell::new(f));
+        self
+    }
+    /// Items shown next to this one when it's clicked
+    pub fn submenu(mut self, items: Vec<MenuItem>) -> Self {
+        self.submenu = items;
+        self
+    }
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+pub struct ContextMenuProps {
+    pub layout: LayoutProps,
+    pub items: Vec<MenuItem>,
+}
+
+impl ContextMenuProps {
+    /// `id` keys the open menu across rebuilds, it must be unique and stable
+    pub fn new(id: &str) -> Self {
+        Self {
+            layout: Layout::get_col_builder().dim((Length::FIT, Length::FIT)).dbg_name(id),
+            items: vec![],
+        }
+    }
+
+    pub fn child(mut self, child: Component) -> Self {
+        self.layout = self.layout.children(vec![child]);
+        self
+    }
+    pub fn items(mut self, items: Vec<MenuItem>) -> Self {
+        self.items = items;
+        self
+    }
+    pub fn item(mut self, item: MenuItem) -> Self {
+        self.items.push(item);
+        self
+    }
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        self.layout = self.layout.dim(dim);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.layout = self.layout.padding(padding);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout = self.layout.bg_color(color);
+        self
+    }
+    pub fn main_align(mut self, align: Alignment) -> Self {
+        self.layout = self.layout.main_align(align);
+        self
+    }
+    pub fn cross_align(mut self, align: Alignment) -> Self {
+        self.layout = self.layout.cross_align(align);
+        self
+    }
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.layout = self.layout.flex(flex);
+        self
+    }
+    pub fn style(mut self, style: &Style) -> Self {
+        self.layout = self.layout.style(style);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<ContextMenu>> {
+        let menu = ContextMenu {
+            layout: self.layout.get_layout(),
+            items: self.items,
+        };
+        let state = with_state(&menu.get_id(), |state| state.clone());
+        if let Some(at) = state.at {
+            menu.show_menus(at, &state.path);
+        }
+        Rc::new(RefCell::new(menu))
+    }
+}
+
+/// Area that opens a menu of `items` at the pointer when right clicked
+pub struct ContextMenu {
+    pub layout: Layout,
+    pub items: Vec<MenuItem>,
+}
+
+impl ContextMenu {
+    pub fn get_builder(id: &str) -> ContextMenuProps {
+        ContextMenuProps::new(id)
+    }
+
+    /// Id of the row opening submenu `index` on `depth`, submenus are anchored to it
+    fn row_id(id: &str, depth: usize, index: usize) -> String {
+        format!("{}/{}/{}", id, depth, index)
+    }
+
+    /// Shows the menu and each open submenu as an overlay of its own. Dismissing one closes
+    /// it and the submenus opened from it.
+    fn show_menus(&self, at: (i32, i32), path: &[usize]) {
+        let id = self.get_id();
+        let mut items = &self.items;
+        let mut placement = Placement::new().left(at.0).top(at.1);
+        for depth in 0..=path.len() {
+            let open = path.get(depth).copied();
+            let close_id = id.clone();
+            OverlayProps::new()
+                .content(self.panel(items, depth, open))
+                .placement(placement)
+                .close_on_escape(true)
+                .close_on_backdrop(true)
+                .on_dismiss(Box::new(move || {
+                    with_state(&close_id, |state| match depth {
+                        0 => *state = MenuState::default(),
+                        _ => state.path.truncate(depth - 1),
+                    })
+                }))
+                .show();
+            let Some(index) = open else {
+                break;
+            };
+            let Some(item) = items.get(index) else {
+                break;
+            };
+            placement = Placement::new()
+                .left(Offset::Percent(100.0))
+                .anchor(&ContextMenu::row_id(&id, depth, index));
+            items = &item.submenu;
+        }
+    }
+
+    fn panel(&self, items: &[MenuItem], depth: usize, open: Option<usize>) -> Component {
+        let id = self.get_id();
+        let rows = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if item.separator {
+                    return Layout::get_row_builder()
+                        .dim((Length::FIT, Length::FIXED(1)))
+                        .bg_color(ColorToken::Border)
+                        .build() as Component;
+                }
+                let mut label = TextLayout::get_builder()
+                    .content(&item.label)
+                    .wrap(false)
+                    .dim((Length::FIT, Length::FIT))
+                    .padding((0, 0, 0, 0));
+                if item.disabled {
+                    label = label.text_color(ColorToken::TextMuted);
+                }
+                let mut children = vec![label.build() as Component];
+                if !item.submenu.is_empty() {
+                    children.push(
+                        TextLayout::get_builder()
+                            .content(">")
+                            .dim((Length::FIT, Length::FIT))
+                            .padding((0, 0, 0, 0))
+                            .build(),
+                    );
+                }
+                let item = item.clone();
+                let id = id.clone();
+                Layout::get_row_builder()
+                    .dim((Length::FIT, Length::FIT))
+                    .padding(Space::xy(Space::Md, Space::Sm))
+                    .gap(Space::Lg)
+                    .main_align(Alignment::SpaceBetween)
+                    .bg_color(if open == Some(index) {
+                        ColorToken::Selection
+                    } else {
+                        ColorToken::Surface
+                    })
+                    .dbg_name(&ContextMenu::row_id(&id, depth, index))
+                    .children(children)
+                    .on_click(Box::new(move |_| {
+                        if item.disabled {
+                            return false;
+                        }
+                        if item.submenu.is_empty() {
+                            with_state(&id, |state| *state = MenuState::default());
+                            let mut on_select = item.on_select.borrow_mut();
+                            on_select();
+                        } else {
+                            with_state(&id, |state| {
+                                state.path.truncate(depth);
+                                state.path.push(index);
+                            });
+                        }
+                        false
+                    }))
+                    .build() as Component
+            })
+            .collect();
+        Layout::get_col_builder()
+            .dim((Length::FIT, Length::FIT))
+            // Rows and separators are as wide as the widest row
+            .cross_align(Alignment::Stretch)
+            .padding(Space::xy(Space::Xs, Space::Xs))
+            .bg_color(ColorToken::Surface)
+            .border_width(1)
+            .border_color(ColorToken::Border)
+            .border_radius(Radius::Sm)
+            .children(rows)
+            .build()
+    }
+}
+
+impl Base for ContextMenu {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        self.layout.draw(draw_handle)
+    }
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        let mut hit_children = self.layout.get_mouse_event_handlers(mouse_event);
+        let (x, y) = self.layout.pos;
+        let (w, h) = self.layout.draw_dim;
+        let pos = mouse_event.pos;
+        if mouse_event.right_button_down && pos.0 >= x && pos.0 <= x + w && pos.1 >= y && pos.1 <= y + h {
+            hit_children.push(self.get_id());
+        }
+        hit_children
+    }
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+    fn execute_on_click(&self, mouse_event: MouseEvent) -> bool {
+        if mouse_event.right_button_down {
+            with_state(&self.get_id(), |state| {
+                state.at = Some(mouse_event.pos);
+                state.path.clear();
+            });
+            // Menus of enclosing areas stay shut
+            return false;
+        }
+        let mut user_fun = self.layout.on_click.borrow_mut();
+        user_fun(mouse_event)
+    }
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.get_paddings()
+    }
+    fn get_key_event_handlers(&self, key_event: KeyEvent) -> Vec<String> {
+        self.layout.get_key_event_handlers(key_event)
+    }
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        self.layout.get_on_key()
+    }
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        self.layout.set_raw_dim(parent_draw_dim);
+    }
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.get_draw_dim()
+    }
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.get_draw_pos()
+    }
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        self.layout.measure_dimensions(parent_draw_dim, id)
+    }
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+    }
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        self.layout.measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+    }
+    fn get_overflow(&self) -> (bool, bool) {
+        self.layout.get_overflow()
+    }
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<contextmenu width={} height={} x={} y={} items={} name='{}'>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                self.items.len(),
+                self.get_id(),
+            ),
+            depth,
+        );
+        for child in layout.children.iter() {
+            child.borrow().debug_dims(depth + 1);
+        }
+        tabbed_print("</contextmenu>", depth);
+    }
+    fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
+        self.layout.get_children()
+    }
+    fn get_align_self(&self) -> Option<Alignment> {
+        self.layout.get_align_self()
+    }
+    fn get_baseline(&self) -> Option<i32> {
+        self.layout.get_baseline()
+    }
+    fn set_stretch(&mut self, stretch: (Option<i32>, Option<i32>)) {
+        self.layout.set_stretch(stretch);
+    }
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+    fn get_by_id(&self, id: &str) -> Option<Rc<RefCell<dyn Base>>> {
+        self.layout.get_by_id(id)
+    }
+    fn get_position(&self) -> Position {
+        self.layout.get_position()
+    }
+    fn get_z_index(&self) -> i32 {
+        self.layout.get_z_index()
+    }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    ffi::{CStr, CString},
+    ops::Range,
+};
+
+use raylib::ffi;
+
+use crate::ui::{component_state, text_wrap::graphemes};
+
+/// Caret, selection and, for uncontrolled inputs, the text of an editable component
+#[derive(Debug, Clone, Default)]
+pub struct EditState {
+    /// Byte index in the content, `None` puts the caret at the end
+    pub caret: Option<usize>,
+    /// Other end of the selection, which runs between it and the caret
+    pub anchor: Option<usize>,
+    /// Content owned by an uncontrolled input
+    pub text: Option<String>,
+    /// Set by edits so the next layout scrolls the caret into view
+    pub follow_caret: bool,
+}
+
+pub fn with_edit_state<R>(id: &str, f: impl FnOnce(&mut EditState) -> R) -> R {
+    component_state::with_state(id, f)
+}
+
+fn clamp_index(content: &str, index: usize) -> usize {
+    let mut index = index.min(content.len());
+    while !content.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Caret clamped to `content`, falling back to the end
+pub fn caret(id: &str, content: &str) -> usize {
+    let caret = with_edit_state(id, |state| state.caret).unwrap_or(content.len());
+    clamp_index(content, caret)
+}
+
+/// Selected byte range of `content`, `None` when nothing is selected
+pub fn selection(id: &str, content: &str) -> Option<Range<usize>> {
+    let anchor = with_edit_state(id, |state| state.anchor)?;
+    let anchor = clamp_index(content, anchor);
+    let caret = caret(id, content);
+    (anchor != caret).then(|| anchor.min(caret)..anchor.max(caret))
+}
+
+/// Start of the grapheme before `index`
+pub fn prev_boundary(text: &str, index: usize) -> usize {
+    index - graphemes(&text[..index]).last().map_or(0, |g| g.len())
+}
+
+/// End of the grapheme after `index`
+pub fn next_boundary(text: &str, index: usize) -> usize {
+    index + graphemes(&text[index..]).first().map_or(0, |g| g.len())
+}
+
+pub fn clipboard_text() -> String {
+    unsafe {
+        let text = ffi::GetClipboardText();
+        if text.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(text).to_string_lossy().into_owned()
+        }
+    }
+}
+
+pub fn set_clipboard_text(text: &str) {
+    if let Ok(text) = CString::new(text) {
+        unsafe {
+            ffi::SetClipboardText(text.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,742 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::{CString, c_void},
+    rc::Rc,
+};
+
+use raylib::{color::Color, ffi, prelude::RaylibDrawHandle};
+use rustybuzz::Face;
+
+use crate::ui::{paint, root::to_physical, shaping, text_wrap::graphemes};
+
+pub const DEFAULT_FAMILY: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontWeight {
+    Thin,
+    Light,
+    Regular,
+    Medium,
+    SemiBold,
+    Bold,
+    Black,
+}
+
+impl FontWeight {
+    /// CSS style numeric weight, used to pick the closest registered face
+    pub fn value(&self) -> i32 {
+        match self {
+            FontWeight::Thin => 100,
+            FontWeight::Light => 300,
+            FontWeight::Regular => 400,
+            FontWeight::Medium => 500,
+            FontWeight::SemiBold => 600,
+            FontWeight::Bold => 700,
+            FontWeight::Black => 900,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontSpec {
+    pub family: String,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+}
+
+impl FontSpec {
+    pub fn new(family: &str) -> Self {
+        Self {
+            family: family.into(),
+            weight: FontWeight::Regular,
+            style: FontStyle::Normal,
+        }
+    }
+    pub fn weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+    pub fn style(mut self, style: FontStyle) -> Self {
+        self.style = style;
+        self
+    }
+    pub fn bold(self) -> Self {
+        self.weight(FontWeight::Bold)
+    }
+    pub fn italic(self) -> Self {
+        self.style(FontStyle::Italic)
+    }
+    pub fn is_default(&self) -> bool {
+        self.family == DEFAULT_FAMILY
+    }
+}
+
+impl Default for FontSpec {
+    fn default() -> Self {
+        FontSpec::new(DEFAULT_FAMILY)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FontSource {
+    Path(String),
+    /// File extension (".ttf", ".otf") and the font bytes
+    Bytes(String, Rc<Vec<u8>>),
+}
+
+type FaceKey = (String, FontWeight, FontStyle);
+
+struct LoadedFont {
+    font: ffi::Font,
+    glyphs: HashSet<char>,
+}
+
+impl LoadedFont {
+    fn new(font: ffi::Font) -> Self {
+        let mut glyphs = HashSet::new();
+        if !font.glyphs.is_null() {
+            for i in 0..font.glyphCount as usize {
+                // raylib keeps an empty entry for codepoints the font file lacks
+                let glyph = unsafe { *font.glyphs.add(i) };
+                if glyph.advanceX > 0 || glyph.image.width > 0 {
+                    if let Some(c) = char::from_u32(glyph.value as u32) {
+                        glyphs.insert(c);
+                    }
+                }
+            }
+        }
+        Self { font, glyphs }
+    }
+}
+
+/// A glyph rasterized by id for shaped text
+struct GlyphTexture {
+    texture: ffi::Texture2D,
+    left: i32,
+    top: i32,
+    width: i32,
+    height: i32,
+}
+
+struct PlacedGlyph {
+    face: FaceKey,
+    glyph_id: u16,
+    /// Pen position relative to the start of the text, in logical pixels
+    x: f32,
+    y: f32,
+}
+
+/// Text run through BiDi reordering and OpenType shaping
+struct ShapedText {
+    glyphs: Vec<PlacedGlyph>,
+    width: f32,
+    ascent: f32,
+}
+
+type ShapeKey = (String, FontWeight, FontStyle, String, i32);
+
+/// A stretch of text drawn with a single font
+struct FontRun {
+    font: ffi::Font,
+    is_default: bool,
+    text: String,
+}
+
+struct FontRegistry {
+    faces: HashMap<FaceKey, FontSource>,
+    fallbacks: HashMap<String, Vec<String>>,
+    loaded: HashMap<(FaceKey, i32), Option<Rc<LoadedFont>>>,
+    default_font: Option<Rc<LoadedFont>>,
+    codepoints: Vec<i32>,
+    face_data: HashMap<FaceKey, Option<Rc<Vec<u8>>>>,
+    glyph_textures: HashMap<(FaceKey, u16, i32), Option<Rc<GlyphTexture>>>,
+    shaped: HashMap<ShapeKey, Option<Rc<ShapedText>>>,
+}
+
+impl FontRegistry {
+    fn new() -> Self {
+        Self {
+            faces: HashMap::new(),
+            fallbacks: HashMap::new(),
+            loaded: HashMap::new(),
+            default_font: None,
+            codepoints: default_codepoints(),
+            face_data: HashMap::new(),
+            glyph_textures: HashMap::new(),
+            shaped: HashMap::new(),
+        }
+    }
+
+    /// Closest registered face: same style preferred, then nearest weight
+    fn resolve_face(&self, family: &str, weight: FontWeight, style: FontStyle) -> Option<FaceKey> {
+        self.faces
+            .keys()
+            .filter(|(face_family, _, _)| face_family == family)
+            .min_by_key(|(_, face_weight, face_style)| {
+                let style_penalty = if *face_style == style { 0 } else { 1000 };
+                style_penalty + (face_weight.value() - weight.value()).abs()
+            })
+            .cloned()
+    }
+
+    fn face_chain(&self, spec: &FontSpec) -> Vec<FaceKey> {
+        let mut families = vec![spec.family.clone()];
+        if let Some(fallbacks) = self.fallbacks.get(&spec.family) {
+            families.extend(fallbacks.iter().cloned());
+        }
+        families
+            .iter()
+            .filter_map(|family| self.resolve_face(family, spec.weight, spec.style))
+            .collect()
+    }
+
+    fn load(&mut self, key: &FaceKey, px: i32) -> Option<Rc<LoadedFont>> {
+        let cache_key = (key.clone(), px);
+        if let Some(loaded) = self.loaded.get(&cache_key) {
+            return loaded.clone();
+        }
+        let loaded = self.faces.get(key).and_then(|source| {
+            let font = unsafe {
+                match source {
+                    FontSource::Path(path) => {
+                        let c_path = CString::new(path.as_str()).ok()?;
+                        ffi::LoadFontEx(
+                            c_path.as_ptr(),
+                            px,
+                            self.codepoints.as_ptr() as *mut i32,
+                            self.codepoints.len() as i32,
+                        )
+                    }
+                    FontSource::Bytes(ext, bytes) => {
+                        let c_ext = CString::new(ext.as_str()).ok()?;
+                        ffi::LoadFontFromMemory(
+                            c_ext.as_ptr(),
+                            bytes.as_ptr(),
+                            bytes.len() as i32,
+                            px,
+                            self.codepoints.as_ptr() as *mut i32,
+                            self.codepoints.len() as i32,
+                        )
+                    }
+                }
+            };
+            if font.texture.id == 0 || font.glyphs.is_null() {
+                log_warning(&format!("FONT: Could not load {:?} at {}px", key, px));
+                return None;
+            }
+            Some(Rc::new(LoadedFont::new(font)))
+        });
+        // Failed loads are cached too so a broken file is not retried every frame
+        self.loaded.insert(cache_key, loaded.clone());
+        loaded
+    }
+
+    /// Drops the atlases of faces matching `stale` and frees them on the GPU
+    fn unload_fonts(&mut self, stale: impl Fn(&FaceKey) -> bool) {
+        self.loaded.retain(|(key, _), loaded| {
+            if !stale(key) {
+                return true;
+            }
+            if let Some(loaded) = loaded {
+                unsafe { ffi::UnloadFont(loaded.font) };
+            }
+            false
+        });
+    }
+
+    /// Drops the shaping glyphs of faces matching `stale` and frees their textures
+    fn unload_glyph_textures(&mut self, stale: impl Fn(&FaceKey) -> bool) {
+        self.glyph_textures.retain(|(key, _, _), glyph| {
+            if !stale(key) {
+                return true;
+            }
+            if let Some(glyph) = glyph {
+                unsafe { ffi::UnloadTexture(glyph.texture) };
+            }
+            false
+        });
+    }
+
+    fn default_font(&mut self) -> Rc<LoadedFont> {
+        if self.default_font.is_none() {
+            let font = unsafe { ffi::GetFontDefault() };
+            self.default_font = Some(Rc::new(LoadedFont::new(font)));
+        }
+        self.default_font.clone().unwrap()
+    }
+
+    /// Raw font file, needed for shaping since raylib only keeps the atlas
+    fn face_data(&mut self, key: &FaceKey) -> Option<Rc<Vec<u8>>> {
+        if let Some(data) = self.face_data.get(key) {
+            return data.clone();
+        }
+        let data = match self.faces.get(key)? {
+            FontSource::Path(path) => match std::fs::read(path) {
+                Ok(bytes) => Some(Rc::new(bytes)),
+                Err(err) => {
+                    log_warning(&format!("FONT: Could not read {}: {}", path, err));
+                    None
+                }
+            },
+            FontSource::Bytes(_, bytes) => Some(bytes.clone()),
+        };
+        self.face_data.insert(key.clone(), data.clone());
+        data
+    }
+
+    /// Shapes complex text with the registered faces. `None` for simple text or when only
+    /// the default font is available, which then falls back to per codepoint drawing.
+    fn shape_text(&mut self, spec: &FontSpec, text: &str, size: i32) -> Option<Rc<ShapedText>> {
+        if !shaping::is_complex(text) {
+            return None;
+        }
+        let cache_key = (spec.family.clone(), spec.weight, spec.style, text.to_string(), size);
+        if let Some(shaped) = self.shaped.get(&cache_key) {
+            return shaped.clone();
+        }
+        let data = self
+            .face_chain(spec)
+            .iter()
+            .filter_map(|key| self.face_data(key).map(|data| (key.clone(), data)))
+            .collect::<Vec<_>>();
+        let faces = data
+            .iter()
+            .filter_map(|(key, data)| Face::from_slice(data, 0).map(|face| (key, face)))
+            .collect::<Vec<_>>();
+        let shaped = faces.first().map(|(_, primary)| {
+            let mut glyphs = vec![];
+            let mut x = 0.0;
+            for run in shaping::visual_runs(text) {
+                let run_text = &text[run.range];
+                // First face with every character of the run, otherwise the primary face
+                let (key, face) = faces
+                    .iter()
+                    .find(|(_, face)| {
+                        run_text
+                            .chars()
+                            .all(|c| c.is_whitespace() || c.is_control() || face.glyph_index(c).is_some())
+                    })
+                    .unwrap_or(&faces[0]);
+                for glyph in shaping::shape(face, run_text, run.rtl, size as f32) {
+                    glyphs.push(PlacedGlyph {
+                        face: (*key).clone(),
+                        glyph_id: glyph.glyph_id,
+                        x: x + glyph.x_offset,
+                        y: -glyph.y_offset,
+                    });
+                    x += glyph.x_advance;
+                }
+            }
+            Rc::new(ShapedText {
+                glyphs,
+                width: x,
+                ascent: primary.ascender() as f32 * size as f32 / primary.units_per_em() as f32,
+            })
+        });
+        // Text changes with every keystroke, keep the cache from growing without bound
+        if self.shaped.len() > 4096 {
+            self.shaped.clear();
+        }
+        self.shaped.insert(cache_key, shaped.clone());
+        shaped
+    }
+
+    fn glyph_texture(&mut self, key: &FaceKey, glyph_id: u16, px: i32) -> Option<Rc<GlyphTexture>> {
+        let cache_key = (key.clone(), glyph_id, px);
+        if let Some(texture) = self.glyph_textures.get(&cache_key) {
+            return texture.clone();
+        }
+        let texture = self.face_data(key).and_then(|data| {
+            let face = Face::from_slice(&data, 0)?;
+            let bitmap = shaping::rasterize_glyph(&face, glyph_id, px as f32)?;
+            let mut pixels = Vec::with_capacity(bitmap.coverage.len() * 4);
+            for alpha in bitmap.coverage.iter() {
+                pixels.extend_from_slice(&[255, 255, 255, *alpha]);
+            }
+            let image = ffi::Image {
+                data: pixels.as_mut_ptr() as *mut c_void,
+                width: bitmap.width as i32,
+                height: bitmap.height as i32,
+                mipmaps: 1,
+                format: ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+            };
+            // The pixels are copied to the GPU, `pixels` is freed by Rust afterwards
+            let texture = unsafe { ffi::LoadTextureFromImage(image) };
+            unsafe {
+                ffi::SetTextureFilter(texture, ffi::TextureFilter::TEXTURE_FILTER_BILINEAR as i32);
+            }
+            Some(Rc::new(GlyphTexture {
+                texture,
+                left: bitmap.left,
+                top: bitmap.top,
+                width: bitmap.width as i32,
+                height: bitmap.height as i32,
+            }))
+        });
+        self.glyph_textures.insert(cache_key, texture.clone());
+        texture
+    }
+
+    fn draw_shaped(&mut self, shaped: &ShapedText, pos: (i32, i32), size: i32, color: Color) {
+        let px = to_physical(size).max(1);
+        // Glyphs are rasterized at physical pixels and drawn back at logical size
+        let scale = px as f32 / size.max(1) as f32;
+        let baseline = pos.1 as f32 + shaped.ascent;
+        for glyph in shaped.glyphs.iter() {
+            let Some(texture) = self.glyph_texture(&glyph.face, glyph.glyph_id, px) else {
+                continue;
+            };
+            unsafe {
+                ffi::DrawTexturePro(
+                    texture.texture,
+                    ffi::Rectangle {
+                        x: 0.0,
+                        y: 0.0,
+                        width: texture.width as f32,
+                        height: texture.height as f32,
+                    },
+                    ffi::Rectangle {
+                        x: pos.0 as f32 + glyph.x + texture.left as f32 / scale,
+                        y: baseline + glyph.y + texture.top as f32 / scale,
+                        width: texture.width as f32 / scale,
+                        height: texture.height as f32 / scale,
+                    },
+                    ffi::Vector2 { x: 0.0, y: 0.0 },
+                    0.0,
+                    color.into(),
+                );
+            }
+        }
+    }
+
+    /// Splits `text` into runs, each char going to the first font in the chain that has it.
+    /// The default font is the last resort.
+    fn runs(&mut self, spec: &FontSpec, text: &str, size: i32) -> Vec<FontRun> {
+        let px = to_physical(size).max(1);
+        let chain = self
+            .face_chain(spec)
+            .iter()
+            .filter_map(|key| self.load(key, px))
+            .collect::<Vec<_>>();
+        let default_font = self.default_font();
+
+        let mut runs: Vec<FontRun> = vec![];
+        for c in text.chars() {
+            let (font, is_default) = match chain.iter().find(|loaded| loaded.glyphs.contains(&c)) {
+                Some(loaded) => (loaded.font, false),
+                None => match chain.first() {
+                    // Nothing has the glyph, keep it in the primary font so it draws as '?'
+                    Some(primary) if !default_font.glyphs.contains(&c) => (primary.font, false),
+                    _ => (default_font.font, true),
+                },
+            };
+            match runs.last_mut() {
+                Some(run) if run.is_default == is_default && run.font.texture.id == font.texture.id => {
+                    run.text.push(c);
+                }
+                _ => runs.push(FontRun {
+                    font,
+                    is_default,
+                    text: c.to_string(),
+                }),
+            }
+        }
+        runs
+    }
+}
+
+fn default_codepoints() -> Vec<i32> {
+    let ranges = [
+        (0x20, 0x7E),     // Basic Latin
+        (0xA0, 0x24F),    // Latin-1 Supplement, Latin Extended-A/B
+        (0x370, 0x4FF),   // Greek, Cyrillic
+        (0x2010, 0x205E), // General punctuation
+        (0x2190, 0x21FF), // Arrows
+    ];
+    ranges
+        .iter()
+        .flat_map(|(start, end)| *start..=*end)
+        .collect()
+}
+
+/// Spacing raylib's `MeasureText`/`DrawText` use with the default font
+fn default_spacing(size: i32) -> f32 {
+    (size.max(10) / 10) as f32
+}
+
+fn run_spacing(run: &FontRun, size: i32) -> f32 {
+    if run.is_default { default_spacing(size) } else { 0.0 }
+}
+
+/// Warns through raylib's log, so it follows `SetTraceLogLevel` like raylib's own messages
+pub(crate) fn log_warning(message: &str) {
+    let Ok(message) = CString::new(message) else {
+        return;
+    };
+    unsafe {
+        ffi::TraceLog(ffi::TraceLogLevel::LOG_WARNING as i32, c"%s".as_ptr(), message.as_ptr());
+    }
+}
+
+fn measure_run(run: &FontRun, size: i32, spacing: f32) -> f32 {
+    let c_text = CString::new(run.text.as_str()).unwrap_or_default();
+    unsafe { ffi::MeasureTextEx(run.font, c_text.as_ptr(), size as f32, spacing).x }
+}
+
+pub fn register_font_file(family: &str, weight: FontWeight, style: FontStyle, path: &str) {
+    register_font(family, weight, style, FontSource::Path(path.into()));
+}
+
+pub fn register_font_bytes(
+    family: &str,
+    weight: FontWeight,
+    style: FontStyle,
+    ext: &str,
+    bytes: Vec<u8>,
+) {
+    register_font(
+        family,
+        weight,
+        style,
+        FontSource::Bytes(ext.into(), Rc::new(bytes)),
+    );
+}
+
+pub fn register_font(family: &str, weight: FontWeight, style: FontStyle, source: FontSource) {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let key = (family.to_string(), weight, style);
+        registry.unload_fonts(|loaded_key| *loaded_key == key);
+        registry.unload_glyph_textures(|glyph_key| *glyph_key == key);
+        registry.face_data.remove(&key);
+        registry.shaped.clear();
+        registry.faces.insert(key, source);
+    });
+}
+
+/// Families tried in order when `family` lacks a glyph
+pub fn set_fallbacks(family: &str, fallbacks: Vec<&str>) {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.fallbacks.insert(
+            family.to_string(),
+            fallbacks.iter().map(|f| f.to_string()).collect(),
+        );
+        registry.shaped.clear();
+    });
+}
+
+/// Codepoints baked into font atlases loaded from now on
+pub fn set_codepoints(codepoints: Vec<i32>) {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.codepoints = codepoints;
+        registry.unload_fonts(|_| true);
+    });
+}
+
+/// Loads the atlases for `sizes` up front instead of on first use.
+/// Needs the window to be open.
+pub fn preload(font: &FontSpec, sizes: &[i32]) {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        for size in sizes {
+            registry.runs(font, " ", *size);
+        }
+    });
+}
+
+pub fn has_glyph(font: &FontSpec, c: char, size: i32) -> bool {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let px = to_physical(size).max(1);
+        let chain = registry.face_chain(font);
+        chain
+            .iter()
+            .filter_map(|key| registry.load(key, px))
+            .any(|loaded| loaded.glyphs.contains(&c))
+            || registry.default_font().glyphs.contains(&c)
+    })
+}
+
+/// Distance from the top of a line of text to its baseline
+pub fn ascent(font: &FontSpec, size: i32) -> i32 {
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let data = registry.face_chain(font).first().and_then(|key| registry.face_data(key));
+        match data.as_deref().and_then(|data| Face::from_slice(data, 0)) {
+            Some(face) => (face.ascender() as f32 * size as f32 / face.units_per_em() as f32).round() as i32,
+            // raylib's default font sits its baseline at about four fifths of the size
+            None => size * 4 / 5,
+        }
+    })
+}
+
+pub fn measure_text(font: &FontSpec, text: &str, size: i32) -> i32 {
+    measure_text_spaced(font, text, size, 0)
+}
+
+/// Width of `text` with `letter_spacing` extra pixels between characters
+pub fn measure_text_spaced(font: &FontSpec, text: &str, size: i32, letter_spacing: i32) -> i32 {
+    measure_piece(font, text, size, letter_spacing).0.ceil() as i32
+}
+
+/// Unrounded width of `text` and the spacing put before it when it follows other text.
+/// Pieces measured one by one add up to the width of the joined text.
+pub fn measure_piece(font: &FontSpec, text: &str, size: i32, letter_spacing: i32) -> (f32, f32) {
+    if text.is_empty() {
+        return (0.0, 0.0);
+    }
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        // Letter spacing would break up cursive scripts, so shaped text ignores it
+        if let Some(shaped) = registry.shape_text(font, text, size) {
+            return (shaped.width, 0.0);
+        }
+        let runs = registry.runs(font, text, size);
+        let mut width = 0.0;
+        let mut leading = 0.0;
+        for (idx, run) in runs.iter().enumerate() {
+            let spacing = run_spacing(run, size) + letter_spacing as f32;
+            if idx > 0 {
+                width += spacing;
+            } else {
+                leading = spacing;
+            }
+            width += measure_run(run, size, spacing);
+        }
+        (width, leading)
+    })
+}
+
+pub fn draw_text(
+    draw_handle: &mut RaylibDrawHandle,
+    font: &FontSpec,
+    text: &str,
+    pos: (i32, i32),
+    size: i32,
+    color: Color,
+) {
+    draw_text_spaced(draw_handle, font, text, pos, size, 0, color);
+}
+
+pub fn draw_text_spaced(
+    _draw_handle: &mut RaylibDrawHandle,
+    font: &FontSpec,
+    text: &str,
+    pos: (i32, i32),
+    size: i32,
+    letter_spacing: i32,
+    color: Color,
+) {
+    if text.is_empty() {
+        return;
+    }
+    let color = paint::faded(color);
+    FONT_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(shaped) = registry.shape_text(font, text, size) {
+            registry.draw_shaped(&shaped, pos, size, color);
+            return;
+        }
+        // Codepoint fonts can't shape, but right to left text is still put in display order
+        let visual;
+        let text = if shaping::has_rtl(text) {
+            visual = shaping::visual_text(text);
+            visual.as_str()
+        } else {
+            text
+        };
+        let runs = registry.runs(font, text, size);
+        let mut x = pos.0 as f32;
+        for (idx, run) in runs.iter().enumerate() {
+            let spacing = run_spacing(run, size) + letter_spacing as f32;
+            if idx > 0 {
+                x += spacing;
+            }
+            let c_text = CString::new(run.text.as_str()).unwrap_or_default();
+            unsafe {
+                ffi::DrawTextEx(
+                    run.font,
+                    c_text.as_ptr(),
+                    ffi::Vector2 {
+                        x,
+                        y: pos.1 as f32,
+                    },
+                    size as f32,
+                    spacing,
+                    color.into(),
+                );
+            }
+            x += measure_run(run, size, spacing);
+        }
+    });
+}
+
+/// Caret positions of a single line as (byte index, x), one per grapheme boundary.
+/// Indices follow the logical order of `text` while x follows the displayed order.
+pub fn caret_stops(font: &FontSpec, text: &str, size: i32) -> Vec<(usize, i32)> {
+    let mut stops: Vec<(usize, i32)> = vec![];
+    let mut seen = HashSet::new();
+    let mut x = 0;
+    for run in shaping::visual_runs(text) {
+        let run_text = &text[run.range.clone()];
+        // Prefix widths add up grapheme by grapheme rather than measuring every prefix
+        let mut boundaries = vec![(0, 0.0)];
+        let (mut boundary, mut width) = (0, 0.0);
+        for grapheme in graphemes(run_text) {
+            let (grapheme_width, spacing) = measure_piece(font, grapheme, size, 0);
+            if boundary > 0 {
+                width += spacing;
+            }
+            boundary += grapheme.len();
+            width += grapheme_width;
+            boundaries.push((boundary, width));
+        }
+        let run_width = width.ceil() as i32;
+        for (boundary, prefix_width) in boundaries {
+            let prefix_width = prefix_width.ceil() as i32;
+            let stop_x = if run.rtl { x + run_width - prefix_width } else { x + prefix_width };
+            let index = run.range.start + boundary;
+            if seen.insert(index) {
+                stops.push((index, stop_x));
+            }
+        }
+        x += run_width;
+    }
+    if stops.is_empty() {
+        stops.push((0, 0));
+    }
+    stops
+}
+
+/// x of the caret placed before byte `index` of `text`
+pub fn caret_x(font: &FontSpec, text: &str, size: i32, index: usize) -> i32 {
+    let stops = caret_stops(font, text, size);
+    stops
+        .iter()
+        .filter(|(stop_index, _)| *stop_index <= index)
+        .max_by_key(|(stop_index, _)| *stop_index)
+        .map(|(_, x)| *x)
+        .unwrap_or(0)
+}
+
+/// Byte index of the caret position closest to `x`
+pub fn index_at_x(font: &FontSpec, text: &str, size: i32, x: i32) -> usize {
+    caret_stops(font, text, size)
+        .iter()
+        .min_by_key(|(_, stop_x)| (stop_x - x).abs())
+        .map(|(index, _)| *index)
+        .unwrap_or(0)
+}
+
+thread_local! {
+    static FONT_REGISTRY: RefCell<FontRegistry> = RefCell::new(FontRegistry::new());
+}
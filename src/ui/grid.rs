@@ -0,0 +1,611 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use colored::Colorize;
+use raylib::{color::Color, prelude::RaylibDrawHandle};
+
+use crate::ui::{
+    common::{self, AbsoluteDraw, Alignment, Base, Component, ID, KeyEvent, Length, MouseEvent, Position, tabbed_print},
+    layout::{Layout, LayoutProps},
+    paint::{self, Shadow},
+    style::{Style, TextStyle},
+};
+
+/// Size of one grid row or column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    Fixed(i32),
+    /// Share of the space left after fixed and auto tracks
+    Fr(f32),
+    /// Fits the largest cell in the track, cells that `FILL` stretch to it instead
+    Auto,
+}
+
+/// A child placed in the grid
+#[derive(Clone)]
+pub struct GridCell {
+    pub child: Component,
+    /// (row, column), auto placed in the next free spot when `None`
+    pub at: Option<(usize, usize)>,
+    /// (rows, columns) covered
+    pub span: (usize, usize),
+    /// Horizontal placement inside the cell, the grid's `justify_items` when `None`.
+    /// `Start`, `Center`, `End` and `Stretch` apply, the rest act like `Start`.
+    pub justify: Option<Alignment>,
+    /// Vertical placement inside the cell, the grid's `align_items` when `None`
+    pub align: Option<Alignment>,
+    /// Resolved (row, column, rows, columns)
+    area: (usize, usize, usize, usize),
+}
+
+impl GridCell {
+    pub fn new(child: Component) -> Self {
+        Self {
+            child,
+            at: None,
+            span: (1, 1),
+            justify: None,
+            align: None,
+            area: (0, 0, 1, 1),
+        }
+    }
+    pub fn at(mut self, row: usize, column: usize) -> Self {
+        self.at = Some((row, column));
+        self
+    }
+    pub fn span(mut self, rows: usize, columns: usize) -> Self {
+        self.span = (rows.max(1), columns.max(1));
+        self
+    }
+    pub fn justify(mut self, align: Alignment) -> Self {
+        self.justify = Some(align);
+        self
+    }
+    pub fn align(mut self, align: Alignment) -> Self {
+        self.align = Some(align);
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct GridProps {
+    layout: LayoutProps,
+    cells: Vec<GridCell>,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    auto_rows: Track,
+    gaps: (i32, i32),
+    justify_items: Alignment,
+    align_items: Alignment,
+}
+
+impl GridProps {
+    pub fn new() -> Self {
+        Self {
+            layout: Layout::get_row_builder().overflow_y(false),
+            cells: vec![],
+            columns: vec![Track::Fr(1.0)],
+            rows: vec![],
+            auto_rows: Track::Auto,
+            gaps: (0, 0),
+            justify_items: Alignment::Start,
+            align_items: Alignment::Start,
+        }
+    }
+    pub fn columns(mut self, columns: Vec<Track>) -> Self {
+        self.columns = columns;
+        self
+    }
+    /// Explicit rows, rows added past them use `auto_rows`
+    pub fn rows(mut self, rows: Vec<Track>) -> Self {
+        self.rows = rows;
+        self
+    }
+    pub fn auto_rows(mut self, track: Track) -> Self {
+        self.auto_rows = track;
+        self
+    }
+    pub fn gap(mut self, gap: impl Into<i32>) -> Self {
+        let gap = gap.into();
+        self.gaps = (gap, gap);
+        self
+    }
+    pub fn row_gap(mut self, gap: impl Into<i32>) -> Self {
+        self.gaps.0 = gap.into();
+        self
+    }
+    pub fn column_gap(mut self, gap: impl Into<i32>) -> Self {
+        self.gaps.1 = gap.into();
+        self
+    }
+    pub fn justify_items(mut self, align: Alignment) -> Self {
+        self.justify_items = align;
+        self
+    }
+    pub fn align_items(mut self, align: Alignment) -> Self {
+        self.align_items = align;
+        self
+    }
+    /// Adds a child in the next free cell
+    pub fn child(mut self, child: Component) -> Self {
+        self.cells.push(GridCell::new(child));
+        self
+    }
+    pub fn children(mut self, children: Vec<Component>) -> Self {
+        self.cells.extend(children.into_iter().map(GridCell::new));
+        self
+    }
+    pub fn cell(mut self, cell: GridCell) -> Self {
+        self.cells.push(cell);
+        self
+    }
+
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        self.layout = self.layout.dim(dim);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.layout = self.layout.padding(padding);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout = self.layout.bg_color(color);
+        self
+    }
+    pub fn border_width(mut self, border_width: i32) -> Self {
+        self.layout = self.layout.border_width(border_width);
+        self
+    }
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
+        self.layout = self.layout.border_color(border_color);
+        self
+    }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        self.layout = self.layout.border_radius(radius);
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.layout = self.layout.shadow(shadow);
+        self
+    }
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.layout = self.layout.opacity(opacity);
+        self
+    }
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        self.layout = self.layout.dbg_name(name);
+        self
+    }
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.layout = self.layout.flex(flex);
+        self
+    }
+    pub fn on_click(mut self, f: Box<dyn FnMut(MouseEvent) -> bool>) -> Self {
+        self.layout = self.layout.on_click(f);
+        self
+    }
+    pub fn set_position(mut self, position: Position) -> Self {
+        self.layout = self.layout.set_position(position);
+        self
+    }
+    pub fn align_self(mut self, align: Alignment) -> Self {
+        self.layout = self.layout.align_self(align);
+        self
+    }
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.layout = self.layout.z_index(z_index);
+        self
+    }
+    pub fn style(mut self, style: &Style) -> Self {
+        self.layout = self.layout.style(style);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Grid>> {
+        let mut grid = Grid {
+            layout: self.layout.get_layout(),
+            cells: self.cells,
+            columns: if self.columns.is_empty() { vec![Track::Fr(1.0)] } else { self.columns },
+            rows: self.rows,
+            auto_rows: self.auto_rows,
+            gaps: self.gaps,
+            justify_items: self.justify_items,
+            align_items: self.align_items,
+            column_sizes: vec![],
+            row_sizes: vec![],
+        };
+        grid.place_cells();
+        Rc::new(RefCell::new(grid))
+    }
+}
+
+/// Children laid out on explicit row and column tracks
+pub struct Grid {
+    // Box, padding and identity, it has no children of its own
+    layout: Layout,
+    cells: Vec<GridCell>,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    auto_rows: Track,
+    // (row gap, column gap)
+    gaps: (i32, i32),
+    justify_items: Alignment,
+    align_items: Alignment,
+    column_sizes: Vec<i32>,
+    row_sizes: Vec<i32>,
+}
+
+impl Grid {
+    pub fn get_builder() -> GridProps {
+        GridProps::new()
+    }
+
+    /// Resolves every cell's area, placed cells first and then the rest in reading order
+    fn place_cells(&mut self) {
+        let columns = self.columns.len();
+        let mut taken: Vec<Vec<bool>> = vec![];
+        let fits = |taken: &mut Vec<Vec<bool>>, row: usize, column: usize, span: (usize, usize), claim: bool| {
+            if column + span.1 > columns {
+                return false;
+            }
+            while taken.len() < row + span.0 {
+                taken.push(vec![false; columns]);
+            }
+            let free = (row..row + span.0).all(|r| (column..column + span.1).all(|c| !taken[r][c]));
+            if free && claim {
+                for r in row..row + span.0 {
+                    for c in column..column + span.1 {
+                        taken[r][c] = true;
+                    }
+                }
+            }
+            free
+        };
+        for cell in self.cells.iter_mut() {
+            if let Some((row, column)) = cell.at {
+                let column = column.min(columns - 1);
+                let span = (cell.span.0, cell.span.1.min(columns - column));
+                fits(&mut taken, row, column, span, true);
+                cell.area = (row, column, span.0, span.1);
+            }
+        }
+        let mut cursor = (0, 0);
+        for cell in self.cells.iter_mut().filter(|cell| cell.at.is_none()) {
+            let span = (cell.span.0, cell.span.1.min(columns));
+            loop {
+                if fits(&mut taken, cursor.0, cursor.1, span, true) {
+                    cell.area = (cursor.0, cursor.1, span.0, span.1);
+                    break;
+                }
+                cursor.1 += 1;
+                if cursor.1 >= columns {
+                    cursor = (cursor.0 + 1, 0);
+                }
+            }
+        }
+    }
+
+    fn row_tracks(&self) -> Vec<Track> {
+        let row_count = self.cells.iter().map(|cell| cell.area.0 + cell.area.2).max().unwrap_or(0);
+        let mut rows = self.rows.clone();
+        while rows.len() < row_count {
+            rows.push(self.auto_rows);
+        }
+        rows
+    }
+
+    /// Space inside the padding
+    fn content_dim(&self) -> (i32, i32) {
+        let padding = self.layout.padding;
+        (
+            self.layout.draw_dim.0 - padding.0 - padding.2,
+            self.layout.draw_dim.1 - padding.1 - padding.3,
+        )
+    }
+
+    /// Sizes of the tracks along one axis. `sizes` holds each cell's (first track, span,
+    /// size) for cells that size auto tracks. Fit containers have no space to share, so
+    /// their fraction tracks act like auto ones.
+    fn resolve_tracks(tracks: &[Track], available: i32, gap: i32, sizes: &[(usize, usize, i32)], fit: bool) -> Vec<i32> {
+        let is_auto = |track: &Track| match track {
+            Track::Auto => true,
+            Track::Fr(_) => fit,
+            Track::Fixed(_) => false,
+        };
+        let mut resolved = tracks
+            .iter()
+            .map(|track| match track {
+                Track::Fixed(size) => *size,
+                _ => 0,
+            })
+            .collect::<Vec<i32>>();
+        for (start, _, size) in sizes.iter().filter(|(_, span, _)| *span == 1) {
+            if is_auto(&tracks[*start]) {
+                resolved[*start] = resolved[*start].max(*size);
+            }
+        }
+        // Spanning cells grow the last auto track they cover when they don't fit
+        for (start, span, size) in sizes.iter().filter(|(_, span, _)| *span > 1) {
+            let end = (start + span).min(tracks.len());
+            let current = resolved[*start..end].iter().sum::<i32>() + gap * (end - start - 1) as i32;
+            if let Some(last_auto) = (*start..end).rev().find(|index| is_auto(&tracks[*index])) {
+                resolved[last_auto] += (size - current).max(0);
+            }
+        }
+        if !fit {
+            let total_fr = tracks
+                .iter()
+                .map(|track| match track {
+                    Track::Fr(fr) => *fr,
+                    _ => 0.0,
+                })
+                .sum::<f32>();
+            if total_fr > 0.0 {
+                let used = resolved.iter().sum::<i32>() + gap * (tracks.len() as i32 - 1).max(0);
+                let free = (available - used).max(0);
+                for (index, track) in tracks.iter().enumerate() {
+                    if let Track::Fr(fr) = track {
+                        resolved[index] = f32::floor(free as f32 * fr / total_fr) as i32;
+                    }
+                }
+            }
+        }
+        resolved
+    }
+
+    fn span_size(sizes: &[i32], start: usize, span: usize, gap: i32) -> i32 {
+        let end = (start + span).min(sizes.len());
+        if end <= start {
+            return 0;
+        }
+        sizes[start..end].iter().sum::<i32>() + gap * (end - start - 1) as i32
+    }
+
+    fn offset_of(sizes: &[i32], index: usize, gap: i32) -> i32 {
+        sizes[..index.min(sizes.len())].iter().map(|size| size + gap).sum()
+    }
+
+    fn fit_dim(&self) -> (i32, i32) {
+        let padding = self.layout.padding;
+        (
+            Grid::span_size(&self.column_sizes, 0, self.column_sizes.len(), self.gaps.1) + padding.0 + padding.2,
+            Grid::span_size(&self.row_sizes, 0, self.row_sizes.len(), self.gaps.0) + padding.1 + padding.3,
+        )
+    }
+}
+
+impl Base for Grid {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        // The layout has no children, so this only paints the box
+        let mut abs_draw = self.layout.draw(draw_handle);
+        paint::with_opacity(self.layout.opacity, || {
+            // Out of flow and z-indexed cells are drawn on top by the root
+            for cell in self.cells.iter().filter(|cell| !common::draws_later(&*cell.child.borrow())) {
+                abs_draw.extend(cell.child.borrow().draw(draw_handle));
+            }
+        });
+        abs_draw
+    }
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        let mut hit_children = Vec::new();
+        for cell in self.cells.iter() {
+            hit_children.extend(cell.child.borrow().get_mouse_event_handlers(mouse_event));
+        }
+        hit_children.extend(self.layout.get_mouse_event_handlers(mouse_event));
+        hit_children
+    }
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.padding
+    }
+    fn get_key_event_handlers(&self, key_event: KeyEvent) -> Vec<String> {
+        let mut hit_children = Vec::new();
+        for cell in self.cells.iter() {
+            hit_children.extend(cell.child.borrow().get_key_event_handlers(key_event));
+        }
+        hit_children
+    }
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        Rc::new(RefCell::new(|_key_event| true))
+    }
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        self.layout.set_raw_dim(parent_draw_dim);
+        let (fit_width, fit_height) = self.fit_dim();
+        match self.layout.dim.0 {
+            Length::FIT => self.layout.draw_dim.0 = fit_width,
+            Length::FIT_PER(p) => self.layout.draw_dim.0 = fit_width * p / 100,
+            _ => {}
+        }
+        match self.layout.dim.1 {
+            Length::FIT => self.layout.draw_dim.1 = fit_height,
+            Length::FIT_PER(p) => self.layout.draw_dim.1 = fit_height * p / 100,
+            _ => {}
+        }
+        self.layout.apply_stretch();
+    }
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.draw_dim
+    }
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.pos
+    }
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        let (content_width, content_height) = self.content_dim();
+        let (row_gap, column_gap) = self.gaps;
+        let fit_width = matches!(self.layout.dim.0, Length::FIT | Length::FIT_PER(_));
+        let fit_height = matches!(self.layout.dim.1, Length::FIT | Length::FIT_PER(_));
+        let row_tracks = self.row_tracks();
+        let mut ret_id = id;
+
+        // Columns first, auto ones fit their cells measured with no width to fill
+        let mut column_contents = vec![];
+        for cell in self.cells.iter() {
+            let (_, column, _, columns) = cell.area;
+            let sizes_auto = (column..column + columns)
+                .any(|c| matches!(self.columns[c], Track::Auto) || (fit_width && matches!(self.columns[c], Track::Fr(_))));
+            if sizes_auto {
+                let mut child = cell.child.borrow_mut();
+                child.set_raw_dim((0, content_height));
+                ret_id = child.measure_dimensions((0, content_height), ret_id + 1);
+                column_contents.push((column, columns, child.get_draw_dim().0));
+            }
+        }
+        self.column_sizes = Grid::resolve_tracks(&self.columns, content_width, column_gap, &column_contents, fit_width);
+
+        // Then rows, with each cell wrapping at its column width
+        let mut row_contents = vec![];
+        for cell in self.cells.iter() {
+            let (row, column, rows, columns) = cell.area;
+            let sizes_auto = (row..row + rows)
+                .any(|r| matches!(row_tracks[r], Track::Auto) || (fit_height && matches!(row_tracks[r], Track::Fr(_))));
+            if sizes_auto {
+                let width = Grid::span_size(&self.column_sizes, column, columns, column_gap);
+                let mut child = cell.child.borrow_mut();
+                child.set_raw_dim((width, 0));
+                ret_id = child.measure_dimensions((width, 0), ret_id + 1);
+                row_contents.push((row, rows, child.get_draw_dim().1));
+            }
+        }
+        self.row_sizes = Grid::resolve_tracks(&row_tracks, content_height, row_gap, &row_contents, fit_height);
+
+        ret_id = id;
+        for cell in self.cells.iter() {
+            let (row, column, rows, columns) = cell.area;
+            let cell_dim = (
+                Grid::span_size(&self.column_sizes, column, columns, column_gap),
+                Grid::span_size(&self.row_sizes, row, rows, row_gap),
+            );
+            let stretch = (
+                cell.justify.unwrap_or(self.justify_items) == Alignment::Stretch,
+                cell.align.unwrap_or(self.align_items) == Alignment::Stretch,
+            );
+            let mut child = cell.child.borrow_mut();
+            child.set_stretch((stretch.0.then_some(cell_dim.0), stretch.1.then_some(cell_dim.1)));
+            child.set_raw_dim(cell_dim);
+            ret_id = child.measure_dimensions(cell_dim, ret_id + 1);
+        }
+        self.set_raw_dim(parent_draw_dim);
+        ret_id = ret_id + 1;
+        if let ID::Auto(_) = &self.layout.dbg_name {
+            self.layout.dbg_name = ID::Auto(ret_id.to_string());
+        }
+        ret_id
+    }
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+        let (border_left, border_top, _, _) = self.layout.border_widths;
+        let origin = (
+            self.layout.pos.0 + border_left + self.layout.padding.0,
+            self.layout.pos.1 + border_top + self.layout.padding.1,
+        );
+        let (row_gap, column_gap) = self.gaps;
+        for cell in self.cells.iter() {
+            let (row, column, rows, columns) = cell.area;
+            let cell_width = Grid::span_size(&self.column_sizes, column, columns, column_gap);
+            let cell_height = Grid::span_size(&self.row_sizes, row, rows, row_gap);
+            let mut child = cell.child.borrow_mut();
+            let (child_width, child_height) = child.get_draw_dim();
+            let place = |align: Alignment, space: i32, size: i32| match align {
+                Alignment::Center => (space - size) / 2,
+                Alignment::End => space - size,
+                _ => 0,
+            };
+            child.measure_positions((
+                origin.0
+                    + Grid::offset_of(&self.column_sizes, column, column_gap)
+                    + place(cell.justify.unwrap_or(self.justify_items), cell_width, child_width),
+                origin.1
+                    + Grid::offset_of(&self.row_sizes, row, row_gap)
+                    + place(cell.align.unwrap_or(self.align_items), cell_height, child_height),
+            ));
+        }
+    }
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        self.layout.measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+        for cell in self.cells.iter() {
+            cell.child.borrow_mut().measure_overflows(
+                self.layout.draw_dim,
+                self.layout.pos,
+                scroll_map,
+                y_offset,
+            );
+        }
+    }
+    fn get_overflow(&self) -> (bool, bool) {
+        self.layout.get_overflow()
+    }
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<grid width={} height={} x={} y={} bg_color={} columns={:?} rows={:?} gaps=({},{}) name='{}' flex={}>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                "███████"
+                    .truecolor(layout.bg_color.r, layout.bg_color.g, layout.bg_color.b)
+                    .bold(),
+                self.column_sizes,
+                self.row_sizes,
+                self.gaps.0,
+                self.gaps.1,
+                self.get_id(),
+                layout.flex
+            ),
+            depth,
+        );
+        for cell in self.cells.iter() {
+            cell.child.borrow().debug_dims(depth + 1);
+        }
+        tabbed_print("</grid>", depth);
+    }
+    fn get_children(&self) -> Vec<Component> {
+        self.cells.iter().map(|cell| cell.child.clone()).collect()
+    }
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        let text_style = inherited.merge(&self.layout.text_style);
+        for cell in self.cells.iter() {
+            cell.child.borrow_mut().inherit_text_style(&text_style);
+        }
+    }
+    fn get_align_self(&self) -> Option<Alignment> {
+        self.layout.align_self
+    }
+    fn set_stretch(&mut self, stretch: (Option<i32>, Option<i32>)) {
+        self.layout.stretch = stretch;
+    }
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+    fn get_by_id(&self, id: &str) -> Option<Component> {
+        for cell in self.cells.iter() {
+            if cell.child.borrow().get_id() == id {
+                return Some(cell.child.clone());
+            }
+            if let Some(found) = cell.child.borrow().get_by_id(id) {
+                return Some(found);
+            }
+        }
+        None
+    }
+    fn get_position(&self) -> Position {
+        self.layout.get_position()
+    }
+    fn get_z_index(&self) -> i32 {
+        self.layout.z_index
+    }
+}
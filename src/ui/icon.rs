@@ -0,0 +1,370 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::c_void,
+    hash::{DefaultHasher, Hash, Hasher},
+    rc::Rc,
+};
+
+use raylib::{color::Color, ffi, prelude::RaylibDrawHandle};
+
+use crate::ui::{
+    common::{AbsoluteDraw, Base, KeyEvent, Length, MouseEvent, tabbed_print},
+    layout::{Layout, LayoutProps},
+    paint,
+    root::{scale_factor, to_physical},
+    style::Style,
+    svg::SvgDocument,
+    theme,
+    tooltip::Tooltip,
+};
+
+use colored::Colorize;
+
+/// Bundled icons on a 24 x 24 grid, paths adapted from Material Icons (Apache 2.0)
+pub mod icons {
+    pub const CLOSE: &str = r#"<svg viewBox="0 0 24 24"><path d="M19 6.41 17.59 5 12 10.59 6.41 5 5 6.41 10.59 12 5 17.59 6.41 19 12 13.41 17.59 19 19 17.59 13.41 12z"/></svg>"#;
+    pub const CHECK: &str = r#"<svg viewBox="0 0 24 24"><path d="M9 16.17 4.83 12l-1.42 1.41L9 19 21 7l-1.41-1.41z"/></svg>"#;
+    pub const PLUS: &str = r#"<svg viewBox="0 0 24 24"><path d="M19 13h-6v6h-2v-6H5v-2h6V5h2v6h6v2z"/></svg>"#;
+    pub const MINUS: &str = r#"<svg viewBox="0 0 24 24"><path d="M19 13H5v-2h14v2z"/></svg>"#;
+    pub const TRASH: &str = r#"<svg viewBox="0 0 24 24"><path d="M6 19c0 1.1.9 2 2 2h8c1.1 0 2-.9 2-2V7H6v12zM19 4h-3.5l-1-1h-5l-1 1H5v2h14V4z"/></svg>"#;
+    pub const SEND: &str = r#"<svg viewBox="0 0 24 24"><path d="M2.01 21 23 12 2.01 3 2 10l15 2-15 2z"/></svg>"#;
+    pub const CHEVRON_DOWN: &str = r#"<svg viewBox="0 0 24 24"><path d="M16.59 8.59 12 13.17 7.41 8.59 6 10l6 6 6-6z"/></svg>"#;
+    pub const CHEVRON_UP: &str = r#"<svg viewBox="0 0 24 24"><path d="M12 8l-6 6 1.41 1.41L12 10.83l4.59 4.58L18 14z"/></svg>"#;
+    pub const MENU: &str = r#"<svg viewBox="0 0 24 24"><path d="M3 18h18v-2H3v2zm0-5h18v-2H3v2zm0-7v2h18V6H3z"/></svg>"#;
+    pub const SEARCH: &str = r#"<svg viewBox="0 0 24 24"><path d="M15.5 14h-.79l-.28-.27A6.471 6.471 0 0 0 16 9.5 6.5 6.5 0 1 0 9.5 16c1.61 0 3.09-.59 4.23-1.57l.27.28v.79l5 4.99L20.49 19l-4.99-5zm-6 0C7.01 14 5 11.99 5 9.5S7.01 5 9.5 5 14 7.01 14 9.5 11.99 14 9.5 14z"/></svg>"#;
+    pub const USER: &str = r#"<svg viewBox="0 0 24 24"><path d="M12 12c2.21 0 4-1.79 4-4s-1.79-4-4-4-4 1.79-4 4 1.79 4 4 4zm0 2c-2.67 0-8 1.34-8 4v2h16v-2c0-2.66-5.33-4-8-4z"/></svg>"#;
+    /// Filled dot for status indicators
+    pub const DOT: &str = r#"<svg viewBox="0 0 24 24"><circle cx="12" cy="12" r="6"/></svg>"#;
+    /// Hollow status ring, drawn with the even-odd rule
+    pub const RING: &str = r#"<svg viewBox="0 0 24 24" fill-rule="evenodd"><path d="M12 5a7 7 0 1 0 0 14 7 7 0 1 0 0-14zm0 2.5a4.5 4.5 0 1 0 0 9 4.5 4.5 0 1 0 0-9z"/></svg>"#;
+}
+
+/// Rasterized textures kept before the least recently used one is unloaded
+const MAX_TEXTURES: usize = 256;
+
+/// Parsed documents and their rasterized textures, icons are rebuilt with the UI every event
+#[derive(Default)]
+struct IconCache {
+    documents: HashMap<u64, Option<Rc<SvgDocument>>>,
+    /// Keyed by source hash and physical size, with the use they were last asked for on
+    textures: HashMap<(u64, i32, i32), (ffi::Texture2D, u64)>,
+    uses: u64,
+}
+
+thread_local! {
+    static ICON_CACHE: RefCell<IconCache> = RefCell::new(IconCache::default());
+}
+
+fn source_key(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn document(key: u64, source: &str) -> Option<Rc<SvgDocument>> {
+    ICON_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .documents
+            .entry(key)
+            .or_insert_with(|| SvgDocument::parse(source).map(Rc::new))
+            .clone()
+    })
+}
+
+/// White texture with the document's coverage as alpha, so it can be tinted when drawn
+fn texture(key: u64, document: &SvgDocument, width: i32, height: i32) -> ffi::Texture2D {
+    ICON_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.uses += 1;
+        let uses = cache.uses;
+        if let Some((texture, last_used)) = cache.textures.get_mut(&(key, width, height)) {
+            *last_used = uses;
+            return *texture;
+        }
+        // Icons drawn at many sizes, e.g. while a window is resized, would fill the GPU
+        if cache.textures.len() >= MAX_TEXTURES {
+            let oldest = cache
+                .textures
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some((texture, _)) = oldest.and_then(|oldest| cache.textures.remove(&oldest)) {
+                unsafe { ffi::UnloadTexture(texture) };
+            }
+        }
+        let coverage = document.rasterize(width as usize, height as usize);
+        let mut pixels = Vec::with_capacity(coverage.len() * 4);
+        for alpha in coverage.iter() {
+            pixels.extend_from_slice(&[255, 255, 255, *alpha]);
+        }
+        let image = ffi::Image {
+            data: pixels.as_mut_ptr() as *mut c_void,
+            width,
+            height,
+            mipmaps: 1,
+            format: ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+        };
+        // The pixels are copied to the GPU, `pixels` is freed by Rust afterwards
+        let texture = unsafe { ffi::LoadTextureFromImage(image) };
+        unsafe {
+            ffi::SetTextureFilter(texture, ffi::TextureFilter::TEXTURE_FILTER_BILINEAR as i32);
+        }
+        cache.textures.insert((key, width, height), (texture, uses));
+        texture
+    })
+}
+
+pub struct IconProps {
+    pub layout: LayoutProps,
+    pub source: String,
+    pub color: Color,
+}
+
+impl IconProps {
+    pub fn new() -> Self {
+        let layout = Layout::get_col_builder()
+            .dim((Length::FIXED(24), Length::FIXED(24)))
+            .bg_color(Color::new(0, 0, 0, 0))
+            .padding((0, 0, 0, 0))
+            .flex(1.0);
+        Self {
+            layout,
+            source: String::new(),
+            color: theme::current().palette.text,
+        }
+    }
+
+    /// SVG markup, e.g. one of `icons`
+    pub fn svg(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    /// Width and height
+    pub fn size(self, size: impl Into<i32>) -> Self {
+        let size = size.into();
+        self.dim((Length::FIXED(size), Length::FIXED(size)))
+    }
+
+    /// Tint for the filled shapes, the SVG's own colours are ignored
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        let layout = self.layout.dim(dim);
+        self.layout = layout;
+        self
+    }
+
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        let layout = self.layout.bg_color(color);
+        self.layout = layout;
+        self
+    }
+
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        let layout = self.layout.padding(padding);
+        self.layout = layout;
+        self
+    }
+
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        let layout = self.layout.dbg_name(name);
+        self.layout = layout;
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout = self.layout.tooltip(tooltip);
+        self
+    }
+
+    pub fn flex(mut self, flex: f32) -> Self {
+        let layout = self.layout.flex(flex);
+        self.layout = layout;
+        self
+    }
+
+    pub fn on_click(mut self, f: Box<dyn FnMut(MouseEvent) -> bool>) -> Self {
+        let layout = self.layout.on_click(f);
+        self.layout = layout;
+        self
+    }
+
+    /// The text colour, if set, tints the icon
+    pub fn style(mut self, style: &Style) -> Self {
+        if let Some(color) = style.text.color {
+            self.color = color;
+        }
+        self.layout = style.apply_box(self.layout);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Icon>> {
+        let key = source_key(&self.source);
+        Rc::new(RefCell::new(Icon {
+            layout: self.layout.get_layout(),
+            document: document(key, &self.source),
+            key,
+            color: self.color,
+            icon_rect: (0, 0, 0, 0),
+        }))
+    }
+}
+
+/// An SVG drawn in a single colour at the component's size
+pub struct Icon {
+    layout: Layout,
+    /// `None` when the source isn't valid SVG, nothing is drawn
+    document: Option<Rc<SvgDocument>>,
+    key: u64,
+    color: Color,
+    /// See [`Layout::content_rect`]
+    icon_rect: (i32, i32, i32, i32),
+}
+
+impl Icon {
+    pub fn get_builder() -> IconProps {
+        IconProps::new()
+    }
+}
+
+impl Base for Icon {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.get_draw_pos()
+    }
+
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        let abs_draws = self.layout.draw(draw_handle);
+        let (x, y, w, h) = self.icon_rect;
+        let Some(document) = self.document.as_ref() else {
+            return abs_draws;
+        };
+        self.layout.draw_clipped(self.icon_rect, || {
+            // Rasterized at physical size so icons stay sharp on high-DPI screens
+            let texture = texture(self.key, document, to_physical(w), to_physical(h));
+            unsafe {
+                ffi::DrawTexturePro(
+                    texture,
+                    ffi::Rectangle {
+                        x: 0.0,
+                        y: 0.0,
+                        width: texture.width as f32,
+                        height: texture.height as f32,
+                    },
+                    ffi::Rectangle {
+                        x: x as f32,
+                        y: y as f32,
+                        width: texture.width as f32 / scale_factor(),
+                        height: texture.height as f32 / scale_factor(),
+                    },
+                    ffi::Vector2 { x: 0.0, y: 0.0 },
+                    0.0,
+                    paint::faded(self.color).into(),
+                );
+            }
+        });
+        abs_draws
+    }
+
+    fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
+        vec![]
+    }
+
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        self.layout.get_mouse_event_handlers(mouse_event)
+    }
+
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.get_paddings()
+    }
+
+    fn get_key_event_handlers(&self, _key_event: KeyEvent) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        self.layout.on_key.clone()
+    }
+
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        let aspect = self.document.as_ref().map_or(1.0, |document| document.aspect_ratio());
+        // Both FIT is 24 high at the viewBox's aspect ratio
+        self.layout.draw_dim = self.layout.leaf_draw_dim(parent_draw_dim, (24.0 * aspect, 24.0));
+    }
+
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.get_draw_dim()
+    }
+
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        self.layout.measure_leaf(parent_draw_dim, id)
+    }
+
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+    }
+
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        self.icon_rect = self.layout.content_rect(y_offset);
+        self.layout
+            .measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+    }
+
+    fn get_overflow(&self) -> (bool, bool) {
+        (false, false)
+    }
+
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<icon width={} height={} x={} y={} color={} name='{}'>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                "███████".truecolor(self.color.r, self.color.g, self.color.b).bold(),
+                self.get_id(),
+            ),
+            depth,
+        );
+        tabbed_print("</icon>", depth);
+    }
+
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+
+    fn get_by_id(&self, _id: &str) -> Option<Rc<RefCell<dyn Base>>> {
+        None
+    }
+
+    fn get_position(&self) -> super::common::Position {
+        self.layout.get_position()
+    }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
+}
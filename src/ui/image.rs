@@ -0,0 +1,514 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{CString, c_void},
+    rc::Rc,
+    sync::mpsc::{Receiver, Sender, channel},
+};
+
+use raylib::{
+    color::Color,
+    ffi,
+    prelude::RaylibDrawHandle,
+};
+
+use crate::ui::{
+    common::{AbsoluteDraw, Base, KeyEvent, Length, MouseEvent, tabbed_print},
+    layout::{Layout, LayoutProps},
+    paint::{self, Fill},
+    style::Style,
+    theme,
+};
+
+use colored::Colorize;
+
+/// Side of the placeholder drawn for a `FIT` image that hasn't loaded yet
+const PLACEHOLDER_SIZE: i32 = 32;
+
+/// Cached images kept before the least recently used one is unloaded
+const MAX_TEXTURES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFit {
+    /// Stretches to the box
+    Fill,
+    /// Scales to fit inside the box, keeping the aspect ratio
+    Contain,
+    /// Scales to cover the box, cropping the overflow
+    Cover,
+    /// Natural size, centred and cropped
+    None,
+}
+
+#[derive(Clone)]
+pub enum ImageSource {
+    Path(String),
+    /// Encoded PNG or JPEG data, cached under `key`
+    Bytes { key: String, data: Rc<[u8]> },
+}
+
+impl ImageSource {
+    fn key(&self) -> &str {
+        match self {
+            ImageSource::Path(path) => path,
+            ImageSource::Bytes { key, .. } => key,
+        }
+    }
+}
+
+enum TextureState {
+    Loading,
+    Ready(ffi::Texture2D),
+    Failed,
+}
+
+/// RGBA pixels decoded off the main thread, textures can only be made on it
+struct Decoded {
+    key: String,
+    pixels: Option<(Vec<u8>, i32, i32)>,
+}
+
+struct ImageCache {
+    /// Keyed by source, with the use they were last asked for on
+    textures: HashMap<String, (TextureState, u64)>,
+    uses: u64,
+    sender: Sender<Decoded>,
+    receiver: Receiver<Decoded>,
+}
+
+thread_local! {
+    static IMAGE_CACHE: RefCell<ImageCache> = {
+        let (sender, receiver) = channel();
+        RefCell::new(ImageCache {
+            textures: HashMap::new(),
+            uses: 0,
+            sender,
+            receiver,
+        })
+    };
+}
+
+fn file_type(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        ".jpg"
+    } else if data.starts_with(b"BM") {
+        ".bmp"
+    } else {
+        ".png"
+    }
+}
+
+fn decode(data: &[u8]) -> Option<(Vec<u8>, i32, i32)> {
+    let file_type = CString::new(file_type(data)).ok()?;
+    unsafe {
+        let mut image = ffi::LoadImageFromMemory(file_type.as_ptr(), data.as_ptr(), data.len() as i32);
+        if image.data.is_null() {
+            return None;
+        }
+        ffi::ImageFormat(
+            &mut image,
+            ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+        );
+        let len = (image.width * image.height * 4) as usize;
+        let pixels = std::slice::from_raw_parts(image.data as *const u8, len).to_vec();
+        let (width, height) = (image.width, image.height);
+        ffi::UnloadImage(image);
+        Some((pixels, width, height))
+    }
+}
+
+/// Texture for `source`, starting a background load the first time it's asked for.
+/// `None` while loading or when the image couldn't be decoded.
+fn request_texture(source: &ImageSource) -> Option<ffi::Texture2D> {
+    IMAGE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.uses += 1;
+        let uses = cache.uses;
+        if let Some((state, last_used)) = cache.textures.get_mut(source.key()) {
+            *last_used = uses;
+            return match state {
+                TextureState::Ready(texture) => Some(*texture),
+                _ => None,
+            };
+        }
+        // Pending loads are skipped, their result would arrive for a key that's gone
+        if cache.textures.len() >= MAX_TEXTURES {
+            let oldest = cache
+                .textures
+                .iter()
+                .filter(|(_, (state, _))| !matches!(state, TextureState::Loading))
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some((TextureState::Ready(texture), _)) =
+                oldest.and_then(|oldest| cache.textures.remove(&oldest))
+            {
+                unsafe { ffi::UnloadTexture(texture) };
+            }
+        }
+        let key = source.key().to_string();
+        cache.textures.insert(key.clone(), (TextureState::Loading, uses));
+        let sender = cache.sender.clone();
+        match source {
+            ImageSource::Path(path) => {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let pixels = std::fs::read(&path).ok().and_then(|data| decode(&data));
+                    let _ = sender.send(Decoded { key, pixels });
+                });
+            }
+            ImageSource::Bytes { data, .. } => {
+                let data = data.to_vec();
+                std::thread::spawn(move || {
+                    let pixels = decode(&data);
+                    let _ = sender.send(Decoded { key, pixels });
+                });
+            }
+        }
+        None
+    })
+}
+
+/// Uploads finished loads, true when any finished so the UI should be rebuilt.
+/// Called by the root every frame.
+pub fn poll_loads() -> bool {
+    IMAGE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let decoded = cache.receiver.try_iter().collect::<Vec<_>>();
+        for Decoded { key, pixels } in decoded.iter() {
+            let state = match pixels {
+                Some((pixels, width, height)) => {
+                    let image = ffi::Image {
+                        data: pixels.as_ptr() as *mut c_void,
+                        width: *width,
+                        height: *height,
+                        mipmaps: 1,
+                        format: ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+                    };
+                    // The pixels are copied to the GPU, Rust frees them afterwards
+                    let texture = unsafe { ffi::LoadTextureFromImage(image) };
+                    unsafe {
+                        ffi::SetTextureFilter(texture, ffi::TextureFilter::TEXTURE_FILTER_BILINEAR as i32);
+                    }
+                    TextureState::Ready(texture)
+                }
+                None => TextureState::Failed,
+            };
+            let uses = cache.uses;
+            cache.textures.insert(key.clone(), (state, uses));
+        }
+        !decoded.is_empty()
+    })
+}
+
+/// Frees the texture cached under `key`, the next use loads it again
+pub fn unload_image(key: &str) {
+    IMAGE_CACHE.with(|cache| {
+        if let Some((TextureState::Ready(texture), _)) = cache.borrow_mut().textures.remove(key) {
+            unsafe { ffi::UnloadTexture(texture) };
+        }
+    });
+}
+
+pub struct ImageProps {
+    pub layout: LayoutProps,
+    pub source: Option<ImageSource>,
+    pub fit: ImageFit,
+    pub radius: i32,
+    pub tint: Color,
+    pub placeholder_color: Color,
+}
+
+impl ImageProps {
+    pub fn new() -> Self {
+        let layout = Layout::get_col_builder()
+            .dim((Length::FIT, Length::FIT))
+            .bg_color(Color::new(0, 0, 0, 0))
+            .padding((0, 0, 0, 0))
+            .flex(1.0);
+        Self {
+            layout,
+            source: None,
+            fit: ImageFit::Contain,
+            radius: 0,
+            tint: Color::WHITE,
+            placeholder_color: theme::current().palette.surface_alt,
+        }
+    }
+
+    /// PNG or JPEG file, cached by its path
+    pub fn path(mut self, path: &str) -> Self {
+        self.source = Some(ImageSource::Path(path.to_string()));
+        self
+    }
+
+    /// Encoded PNG or JPEG data, cached under `key`
+    pub fn bytes(mut self, key: &str, data: Rc<[u8]>) -> Self {
+        self.source = Some(ImageSource::Bytes {
+            key: key.to_string(),
+            data,
+        });
+        self
+    }
+
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    /// Corner radius the image is clipped to
+    pub fn radius(mut self, radius: i32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn tint(mut self, tint: impl Into<Color>) -> Self {
+        self.tint = tint.into();
+        self
+    }
+
+    /// Drawn while loading and when the image fails to load
+    pub fn placeholder_color(mut self, color: impl Into<Color>) -> Self {
+        self.placeholder_color = color.into();
+        self
+    }
+
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        let layout = self.layout.dim(dim);
+        self.layout = layout;
+        self
+    }
+
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        let layout = self.layout.bg_color(color);
+        self.layout = layout;
+        self
+    }
+
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        let layout = self.layout.padding(padding);
+        self.layout = layout;
+        self
+    }
+
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        let layout = self.layout.dbg_name(name);
+        self.layout = layout;
+        self
+    }
+
+    pub fn flex(mut self, flex: f32) -> Self {
+        let layout = self.layout.flex(flex);
+        self.layout = layout;
+        self
+    }
+
+    pub fn on_click(mut self, f: Box<dyn FnMut(MouseEvent) -> bool>) -> Self {
+        let layout = self.layout.on_click(f);
+        self.layout = layout;
+        self
+    }
+
+    pub fn style(mut self, style: &Style) -> Self {
+        self.layout = style.apply_box(self.layout);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Image>> {
+        Rc::new(RefCell::new(Image {
+            layout: self.layout.get_layout(),
+            source: self.source,
+            fit: self.fit,
+            radius: self.radius,
+            tint: self.tint,
+            placeholder_color: self.placeholder_color,
+            image_rect: (0, 0, 0, 0),
+        }))
+    }
+}
+
+pub struct Image {
+    layout: Layout,
+    source: Option<ImageSource>,
+    fit: ImageFit,
+    radius: i32,
+    tint: Color,
+    placeholder_color: Color,
+    /// See [`Layout::content_rect`]
+    image_rect: (i32, i32, i32, i32),
+}
+
+impl Image {
+    pub fn get_builder() -> ImageProps {
+        ImageProps::new()
+    }
+
+    fn texture(&self) -> Option<ffi::Texture2D> {
+        self.source.as_ref().and_then(request_texture)
+    }
+
+    /// Source and destination rectangles for `texture` in the content box
+    fn fit_rects(&self, texture: ffi::Texture2D) -> (ffi::Rectangle, ffi::Rectangle) {
+        let (x, y, w, h) = self.image_rect;
+        let (x, y, w, h) = (x as f32, y as f32, w as f32, h as f32);
+        let (tw, th) = (texture.width as f32, texture.height as f32);
+        let full = ffi::Rectangle { x: 0.0, y: 0.0, width: tw, height: th };
+        let centered = |width: f32, height: f32| ffi::Rectangle {
+            x: x + (w - width) / 2.0,
+            y: y + (h - height) / 2.0,
+            width,
+            height,
+        };
+        let crop = |width: f32, height: f32| ffi::Rectangle {
+            x: (tw - width) / 2.0,
+            y: (th - height) / 2.0,
+            width,
+            height,
+        };
+        match self.fit {
+            ImageFit::Fill => (full, ffi::Rectangle { x, y, width: w, height: h }),
+            ImageFit::Contain => {
+                let scale = (w / tw).min(h / th);
+                (full, centered(tw * scale, th * scale))
+            }
+            ImageFit::Cover => {
+                let scale = (w / tw).max(h / th);
+                (crop(w / scale, h / scale), ffi::Rectangle { x, y, width: w, height: h })
+            }
+            ImageFit::None => {
+                let (width, height) = (tw.min(w), th.min(h));
+                (crop(width, height), centered(width, height))
+            }
+        }
+    }
+}
+
+impl Base for Image {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.get_draw_pos()
+    }
+
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        let abs_draws = self.layout.draw(draw_handle);
+        self.layout.draw_clipped(self.image_rect, || {
+            let radii = [self.radius as f32; 4];
+            match self.texture() {
+                Some(texture) => {
+                    let (source, dest) = self.fit_rects(texture);
+                    let tint = self.tint;
+                    paint::fill_rounded(dest, radii, Fill::Texture { texture, source, tint });
+                }
+                None => {
+                    let (x, y, w, h) = self.image_rect;
+                    let dest = ffi::Rectangle {
+                        x: x as f32,
+                        y: y as f32,
+                        width: w as f32,
+                        height: h as f32,
+                    };
+                    paint::fill_rounded(dest, radii, Fill::Solid(self.placeholder_color));
+                }
+            }
+        });
+        abs_draws
+    }
+
+    fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
+        vec![]
+    }
+
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        self.layout.get_mouse_event_handlers(mouse_event)
+    }
+
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.get_paddings()
+    }
+
+    fn get_key_event_handlers(&self, _key_event: KeyEvent) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        self.layout.on_key.clone()
+    }
+
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        let natural = self
+            .texture()
+            .map_or((PLACEHOLDER_SIZE, PLACEHOLDER_SIZE), |t| (t.width, t.height));
+        let natural = (natural.0 as f32, natural.1 as f32);
+        self.layout.draw_dim = self.layout.leaf_draw_dim(parent_draw_dim, natural);
+    }
+
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.get_draw_dim()
+    }
+
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        self.layout.measure_leaf(parent_draw_dim, id)
+    }
+
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+    }
+
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        self.image_rect = self.layout.content_rect(y_offset);
+        self.layout
+            .measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+    }
+
+    fn get_overflow(&self) -> (bool, bool) {
+        (false, false)
+    }
+
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<image width={} height={} x={} y={} bg_color={} source='{}' fit={:?} radius={} name='{}'>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                "███████".truecolor(layout.bg_color.r, layout.bg_color.g, layout.bg_color.b).bold(),
+                self.source.as_ref().map_or("", |source| source.key()),
+                self.fit,
+                self.radius,
+                self.get_id(),
+            ),
+            depth,
+        );
+        tabbed_print("</image>", depth);
+    }
+
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+
+    fn get_by_id(&self, _id: &str) -> Option<Rc<RefCell<dyn Base>>> {
+        None
+    }
+
+    fn get_position(&self) -> super::common::Position {
+        self.layout.get_position()
+    }
+}
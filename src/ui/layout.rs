@@ -6,15 +6,18 @@ use std::{
 };
 
 use colored::Colorize;
-use raylib::{
-    color::Color,
-    prelude::{RaylibDraw, RaylibDrawHandle},
-};
+use raylib::{color::Color, ffi, prelude::RaylibDrawHandle};
 
-use crate::ui::common::{
+use crate::ui::common::{self,
     AbsoluteDraw, Alignment, Base, Component, Direction, ID, KeyEvent, Length, MouseEvent,
     Position, generate_id, get_drawable_y_and_h, tabbed_print,
 };
+use crate::ui::animation::{self, Animated, Animation, Motion, Property, Transition};
+use crate::ui::paint::{self, Fill, Gradient, Shadow};
+use crate::ui::font::FontSpec;
+use crate::ui::style::{Style, TextStyle};
+use crate::ui::theme;
+use crate::ui::tooltip::Tooltip;
 
 pub struct Layout {
     pub children: Vec<Rc<RefCell<dyn Base>>>,
@@ -25,8 +28,35 @@ pub struct Layout {
     pub direction: Direction,
     // Padding (top, right, bottom, left)
     pub padding: (i32, i32, i32, i32),
-    pub border_width: i32,
-    pub border_color: Color,
+    // Border widths (left, top, right, bottom)
+    pub border_widths: (i32, i32, i32, i32),
+    // Border colors (left, top, right, bottom)
+    pub border_colors: (Color, Color, Color, Color),
+    // Corner radii (top-left, top-right, bottom-right, bottom-left)
+    pub border_radius: (i32, i32, i32, i32),
+    pub shadows: Vec<Shadow>,
+    pub gradient: Option<Gradient>,
+    pub opacity: f32,
+    // Unclipped (x, y, w, h) of the box including borders
+    pub paint_rect: (i32, i32, i32, i32),
+    // Vertical band the box is clipped to when it overflows its parent
+    pub clip: Option<(i32, i32)>,
+    // Text properties handed down to the text inside
+    pub text_style: TextStyle,
+    // Shift from where the layout would be placed, children move with it
+    pub offset: (i32, i32),
+    pub motion: Motion,
+    // Flows children onto new lines instead of squeezing them
+    pub wrap: bool,
+    // Gap between wrapped lines
+    pub cross_gap: i32,
+    // Placement of wrapped lines on the cross axis
+    pub align_content: Alignment,
+    lines: Vec<WrapLine>,
+    // Cross axis alignment overriding the parent's
+    pub align_self: Option<Alignment>,
+    // Space forced by a stretching parent, set before measuring
+    pub stretch: (Option<i32>, Option<i32>),
     pub main_align: Alignment,
     pub cross_align: Alignment,
     pub gap: i32,
@@ -38,6 +68,33 @@ pub struct Layout {
     pub overflow: (bool, bool),
     pub scroll_offset: i32,
     pub position: Position,
+    pub z_index: i32,
+    pub tooltip: Option<Tooltip>,
+}
+
+/// Children `start..end` of a wrapping layout sharing one line
+#[derive(Debug, Clone, Copy)]
+struct WrapLine {
+    start: usize,
+    end: usize,
+    // Length along the main axis including gaps
+    main: i32,
+    // Thickness, the largest child across the main axis
+    cross: i32,
+}
+
+/// Offset of the first item and the gap between items sharing `free` space along an axis
+fn distribute(align: Alignment, free: i32, count: usize, gap: i32) -> (i32, i32) {
+    let free = free.max(0);
+    let count = count.max(1) as i32;
+    match align {
+        Alignment::Center => (free / 2, gap),
+        Alignment::End => (free, gap),
+        Alignment::SpaceBetween if count > 1 => (0, gap + free / (count - 1)),
+        Alignment::SpaceAround => (free / count / 2, gap + free / count),
+        Alignment::SpaceEvenly => (free / (count + 1), gap + free / (count + 1)),
+        _ => (0, gap),
+    }
 }
 
 pub struct LayoutProps {
@@ -64,10 +121,27 @@ impl Clone for LayoutProps {
                 children_func: self.layout.children_func.clone(),
                 overflow: self.layout.overflow,
                 scroll_offset: self.layout.scroll_offset,
-                position: self.layout.position,
+                position: self.layout.position.clone(),
+                z_index: self.layout.z_index,
+                tooltip: self.layout.tooltip.clone(),
                 on_key: self.layout.on_key.clone(),
-                border_width: self.layout.border_width,
-                border_color: self.layout.border_color,
+                border_widths: self.layout.border_widths,
+                border_colors: self.layout.border_colors,
+                border_radius: self.layout.border_radius,
+                shadows: self.layout.shadows.clone(),
+                gradient: self.layout.gradient.clone(),
+                opacity: self.layout.opacity,
+                paint_rect: self.layout.paint_rect,
+                clip: self.layout.clip,
+                text_style: self.layout.text_style.clone(),
+                offset: self.layout.offset,
+                motion: self.layout.motion.clone(),
+                wrap: self.layout.wrap,
+                cross_gap: self.layout.cross_gap,
+                align_content: self.layout.align_content,
+                lines: self.layout.lines.clone(),
+                align_self: self.layout.align_self,
+                stretch: self.layout.stretch,
             },
         }
     }
@@ -75,6 +149,7 @@ impl Clone for LayoutProps {
 
 impl LayoutProps {
     pub fn new() -> Self {
+        let border = theme::current().palette.border;
         Self {
             layout: Layout {
                 children: vec![],
@@ -100,8 +175,25 @@ impl LayoutProps {
                 scroll_offset: 0,
                 overflow: (false, true),
                 position: Position::Auto,
-                border_width: 0,
-                border_color: Color::BLACK,
+                z_index: 0,
+                border_widths: (0, 0, 0, 0),
+                border_colors: (border, border, border, border),
+                border_radius: (0, 0, 0, 0),
+                shadows: vec![],
+                gradient: None,
+                opacity: 1.0,
+                paint_rect: (0, 0, 0, 0),
+                clip: None,
+                text_style: TextStyle::default(),
+                offset: (0, 0),
+                motion: Motion::default(),
+                wrap: false,
+                cross_gap: 0,
+                align_content: Alignment::Start,
+                lines: vec![],
+                align_self: None,
+                stretch: (None, None),
+                tooltip: None,
             },
         }
     }
@@ -113,8 +205,8 @@ impl LayoutProps {
         self.layout.dim = dim;
         self
     }
-    pub fn bg_color(mut self, color: Color) -> Self {
-        self.layout.bg_color = color;
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout.bg_color = color.into();
         self
     }
     pub fn direction(mut self, direction: Direction) -> Self {
@@ -133,8 +225,8 @@ impl LayoutProps {
         self.layout.padding = padding;
         self
     }
-    pub fn gap(mut self, gap: i32) -> Self {
-        self.layout.gap = gap;
+    pub fn gap(mut self, gap: impl Into<i32>) -> Self {
+        self.layout.gap = gap.into();
         self
     }
     pub fn dbg_name(mut self, name: &str) -> Self {
@@ -169,14 +261,127 @@ impl LayoutProps {
         self.layout.position = position;
         self
     }
+    /// Stacking order when out of the flow or non-zero, higher is drawn later and clicked first
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.layout.z_index = z_index;
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout.tooltip = Some(tooltip.into());
+        self
+    }
     pub fn border_width(mut self, border_width: i32) -> Self {
-        self.layout.border_width = border_width;
+        self.layout.border_widths = (border_width, border_width, border_width, border_width);
+        self
+    }
+    /// Per side widths (left, top, right, bottom)
+    pub fn border_widths(mut self, border_widths: (i32, i32, i32, i32)) -> Self {
+        self.layout.border_widths = border_widths;
+        self
+    }
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
+        let border_color = border_color.into();
+        self.layout.border_colors = (border_color, border_color, border_color, border_color);
+        self
+    }
+    /// Per side colors (left, top, right, bottom)
+    pub fn border_colors(mut self, border_colors: (Color, Color, Color, Color)) -> Self {
+        self.layout.border_colors = border_colors;
+        self
+    }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        let radius = radius.into();
+        self.layout.border_radius = (radius, radius, radius, radius);
+        self
+    }
+    /// Per corner radii (top-left, top-right, bottom-right, bottom-left)
+    pub fn border_radii(mut self, radii: (i32, i32, i32, i32)) -> Self {
+        self.layout.border_radius = radii;
+        self
+    }
+    /// Adds a drop shadow, shadows are drawn in the order they are added
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.layout.shadows.push(shadow);
+        self
+    }
+    pub fn shadows(mut self, shadows: Vec<Shadow>) -> Self {
+        self.layout.shadows = shadows;
+        self
+    }
+    /// Background gradient, drawn instead of `bg_color`
+    pub fn gradient(mut self, gradient: Gradient) -> Self {
+        self.layout.gradient = Some(gradient);
+        self
+    }
+    /// Opacity of the layout and everything inside it, between 0.0 and 1.0
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.layout.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+    /// Text colour for the text inside, unless it sets its own
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout.text_style.color = Some(color.into());
+        self
+    }
+    /// Font for the text inside, unless it sets its own
+    pub fn font(mut self, font: FontSpec) -> Self {
+        self.layout.text_style.font = Some(font);
+        self
+    }
+    /// Font size for the text inside, unless it sets its own
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.layout.text_style.font_size = Some(size.into());
+        self
+    }
+    /// Cross axis alignment of this layout in its parent, overriding the parent's `cross_align`
+    pub fn align_self(mut self, align: Alignment) -> Self {
+        self.layout.align_self = Some(align);
+        self
+    }
+    /// Children keep their own size and move to a new line when the current one is full
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.layout.wrap = wrap;
+        self
+    }
+    /// Gap between wrapped lines
+    pub fn cross_gap(mut self, gap: impl Into<i32>) -> Self {
+        self.layout.cross_gap = gap.into();
+        self
+    }
+    /// Placement of wrapped lines when they don't fill the cross axis
+    pub fn align_content(mut self, align: Alignment) -> Self {
+        self.layout.align_content = align;
+        self
+    }
+    pub fn offset(mut self, offset: (i32, i32)) -> Self {
+        self.layout.offset = offset;
+        self
+    }
+    /// Animates changes of `property` between rebuilds, needs a `dbg_name`
+    pub fn transition(mut self, property: Property, transition: Transition) -> Self {
+        self.layout.motion.transitions.retain(|(p, _)| *p != property);
+        self.layout.motion.transitions.push((property, transition));
         self
     }
-    pub fn border_color(mut self, border_color: Color) -> Self {
-        self.layout.border_color = border_color;
+    /// Keyframe animation started when the layout first appears, needs a `dbg_name`
+    pub fn animation(mut self, animation: Animation) -> Self {
+        self.layout.motion.animation = Some(animation);
         self
     }
+    /// Played when the layout is added to the tree, needs a `dbg_name`
+    pub fn enter(mut self, animation: Animation) -> Self {
+        self.layout.motion.enter = Some(animation);
+        self
+    }
+    /// Played when the layout is removed from the tree, needs a `dbg_name`
+    pub fn exit(mut self, animation: Animation) -> Self {
+        self.layout.motion.exit = Some(animation);
+        self
+    }
+    pub fn style(mut self, style: &Style) -> Self {
+        self.layout.text_style = self.layout.text_style.merge(&style.text);
+        style.apply_box(self)
+    }
     pub fn build(self) -> Rc<RefCell<Layout>> {
         let layout = self.layout;
         Rc::new(RefCell::new(Layout {
@@ -197,9 +402,26 @@ impl LayoutProps {
             overflow: layout.overflow,
             scroll_offset: layout.scroll_offset,
             position: layout.position,
+            z_index: layout.z_index,
             on_key: layout.on_key.clone(),
-            border_color: layout.border_color,
-            border_width: layout.border_width,
+            border_widths: layout.border_widths,
+            border_colors: layout.border_colors,
+            border_radius: layout.border_radius,
+            shadows: layout.shadows,
+            gradient: layout.gradient,
+            opacity: layout.opacity,
+            paint_rect: layout.paint_rect,
+            clip: layout.clip,
+            text_style: layout.text_style,
+            offset: layout.offset,
+            motion: layout.motion,
+            wrap: layout.wrap,
+            cross_gap: layout.cross_gap,
+            align_content: layout.align_content,
+            lines: layout.lines,
+            align_self: layout.align_self,
+            stretch: layout.stretch,
+            tooltip: layout.tooltip,
         }))
     }
     pub fn get_layout(self) -> Layout {
@@ -235,13 +457,327 @@ impl Layout {
             let child_ref = child.clone();
             let child_ref = child_ref.borrow();
             match child_ref.get_position() {
-                Position::Auto => auto_children.push(child.clone()),
-                Position::Abs(_, _) => abs_children.push(child.clone()),
-                Position::Sticky(_, _) => sticky_children.push(child.clone()),
+                Position::Auto | Position::Relative(_, _) => auto_children.push(child.clone()),
+                Position::Abs(_) => abs_children.push(child.clone()),
+                Position::Sticky(_) => sticky_children.push(child.clone()),
             }
         }
         (auto_children, abs_children, sticky_children)
     }
+
+    /// Size of a childless box holding content of `natural` size, for leaves like icons and
+    /// images. A FIT side takes the natural length, or follows the other side at the natural
+    /// aspect ratio when only one side is FIT.
+    pub(crate) fn leaf_draw_dim(
+        &self,
+        parent_draw_dim: (i32, i32),
+        natural: (f32, f32),
+    ) -> (i32, i32) {
+        let (draw_width, draw_height) = common::get_draw_dim(
+            self.dim,
+            parent_draw_dim,
+            &Vec::new(),
+            self.direction,
+            self.border_widths,
+        );
+        let padding = self.padding;
+        let (pad_w, pad_h) = (padding.0 + padding.2, padding.1 + padding.3);
+        let aspect = natural.0 / natural.1.max(1.0);
+        match (self.dim.0 == Length::FIT, self.dim.1 == Length::FIT) {
+            (true, true) => (natural.0.round() as i32 + pad_w, natural.1.round() as i32 + pad_h),
+            (true, false) => {
+                let width = ((draw_height - pad_h) as f32 * aspect).round() as i32;
+                (width + pad_w, draw_height)
+            }
+            (false, true) => {
+                let height = ((draw_width - pad_w) as f32 / aspect).round() as i32;
+                (draw_width, height + pad_h)
+            }
+            (false, false) => (draw_width, draw_height),
+        }
+    }
+
+    /// Measures a childless box, keeping the size `set_raw_dim` gave it since the layout
+    /// would measure a FIT box without children as empty
+    pub(crate) fn measure_leaf(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        let draw_dim = self.draw_dim;
+        let id = self.measure_dimensions(parent_draw_dim, id);
+        self.draw_dim = draw_dim;
+        id
+    }
+
+    /// Content box before scroll clipping, `draw_dim` only keeps the visible part.
+    /// `draw_dim` is already inside the borders, so only the origin moves past them.
+    pub(crate) fn content_rect(&self, y_offset: i32) -> (i32, i32, i32, i32) {
+        let padding = self.padding;
+        let (border_left, border_top, _, _) = self.border_widths;
+        (
+            self.pos.0 + border_left + padding.0,
+            self.pos.1 - y_offset + border_top + padding.1,
+            self.draw_dim.0 - padding.0 - padding.2,
+            self.draw_dim.1 - padding.1 - padding.3,
+        )
+    }
+
+    /// Runs `draw` clipped to the visible part of the box, scrolled content is drawn whole.
+    /// Nothing is drawn when that part or `content` is empty.
+    pub(crate) fn draw_clipped(&self, content: (i32, i32, i32, i32), draw: impl FnOnce()) {
+        let (visible_w, visible_h) = self.draw_dim;
+        if visible_w <= 0 || visible_h <= 0 || content.2 <= 0 || content.3 <= 0 {
+            return;
+        }
+        let (border_left, border_top, _, _) = self.border_widths;
+        paint::push_clip((self.pos.0 + border_left, self.pos.1 + border_top, visible_w, visible_h));
+        draw();
+        paint::pop_clip();
+    }
+}
+
+impl Layout {
+    /// Replaces the built values with this frame's animated ones
+    fn animate(&mut self) {
+        let ID::Manual(key) = &self.dbg_name else {
+            return;
+        };
+        if self.motion.is_empty() {
+            return;
+        }
+        let animated = animation::animate(
+            key,
+            &self.motion,
+            Animated {
+                bg_color: self.bg_color,
+                opacity: self.opacity,
+                offset: self.offset,
+                size: self.draw_dim,
+            },
+        );
+        self.bg_color = animated.bg_color;
+        self.opacity = animated.opacity;
+        self.offset = animated.offset;
+        self.draw_dim = animated.size;
+    }
+    /// (main, cross) of `pair` for this layout's direction, also converts back
+    fn along<T>(&self, pair: (T, T)) -> (T, T) {
+        match self.direction {
+            Direction::Row => pair,
+            Direction::Column => (pair.1, pair.0),
+        }
+    }
+    /// Sizes to the space a stretching parent forces, called at the end of `set_raw_dim`
+    pub(crate) fn apply_stretch(&mut self) {
+        let (border_left, border_top, border_right, border_bottom) = self.border_widths;
+        if let Some(width) = self.stretch.0 {
+            self.draw_dim.0 = width - border_left - border_right;
+        }
+        if let Some(height) = self.stretch.1 {
+            self.draw_dim.1 = height - border_top - border_bottom;
+        }
+    }
+    /// Measures children at their own size and breaks them into lines
+    fn measure_lines(&mut self, children: &[Component], parent_draw_dim: (i32, i32), id: usize) -> usize {
+        // A fit layout wraps at the space its parent gives it
+        let fits = match self.direction {
+            Direction::Row => matches!(self.dim.0, Length::FIT | Length::FIT_PER(_)),
+            Direction::Column => matches!(self.dim.1, Length::FIT | Length::FIT_PER(_)),
+        };
+        let outer = if fits { parent_draw_dim } else { self.draw_dim };
+        let space = (
+            outer.0 - self.padding.0 - self.padding.2,
+            outer.1 - self.padding.1 - self.padding.3,
+        );
+        let max_main = self.along(space).0;
+        let mut ret_id = id;
+        let mut lines: Vec<WrapLine> = vec![];
+        for (index, child) in children.iter().enumerate() {
+            let mut child = child.borrow_mut();
+            child.set_raw_dim(space);
+            ret_id = child.measure_dimensions(space, ret_id + 1);
+            let (main, cross) = self.along(child.get_draw_dim());
+            match lines.last_mut() {
+                Some(line) if line.main + self.gap + main <= max_main => {
+                    line.end = index + 1;
+                    line.main += self.gap + main;
+                    line.cross = line.cross.max(cross);
+                }
+                _ => lines.push(WrapLine {
+                    start: index,
+                    end: index + 1,
+                    main,
+                    cross,
+                }),
+            }
+        }
+        // Stretched lines share the cross space the lines leave free
+        let cross_fits = match self.direction {
+            Direction::Row => matches!(self.dim.1, Length::FIT | Length::FIT_PER(_)),
+            Direction::Column => matches!(self.dim.0, Length::FIT | Length::FIT_PER(_)),
+        };
+        if self.align_content == Alignment::Stretch && !cross_fits && !lines.is_empty() {
+            let used = lines.iter().map(|line| line.cross).sum::<i32>()
+                + self.cross_gap * (lines.len() as i32 - 1);
+            let extra = (self.along(space).1 - used).max(0) / lines.len() as i32;
+            for line in lines.iter_mut() {
+                line.cross += extra;
+            }
+        }
+        for line in lines.iter() {
+            for child in children[line.start..line.end].iter() {
+                let mut child = child.borrow_mut();
+                if child.get_align_self().unwrap_or(self.cross_align) != Alignment::Stretch {
+                    continue;
+                }
+                let (main, _) = self.along(child.get_draw_dim());
+                let dim = self.along((main, line.cross));
+                child.set_stretch(self.along((None, Some(line.cross))));
+                child.set_raw_dim(dim);
+                ret_id = child.measure_dimensions(dim, ret_id + 1);
+            }
+        }
+        self.lines = lines;
+        ret_id
+    }
+    /// Sizes fit dimensions to the wrapped lines
+    fn fit_lines(&mut self) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let main = self.lines.iter().map(|line| line.main).max().unwrap_or(0);
+        let cross = self.lines.iter().map(|line| line.cross).sum::<i32>()
+            + self.cross_gap * (self.lines.len() as i32 - 1);
+        let content = self.along((main, cross));
+        let fit = |length: Length, content: i32, current: i32| match length {
+            Length::FIT => content,
+            Length::FIT_PER(p) => content * p / 100,
+            _ => current,
+        };
+        self.draw_dim = (
+            fit(self.dim.0, content.0 + self.padding.0 + self.padding.2, self.draw_dim.0),
+            fit(self.dim.1, content.1 + self.padding.1 + self.padding.3, self.draw_dim.1),
+        );
+    }
+    /// Places `lines` of children, `main_align` distributes each line and `cross_align` or
+    /// the child's `align_self` places it within the line's thickness
+    fn position_lines(&self, children: &[Component], lines: &[WrapLine]) {
+        let (border_left, border_top, _, _) = self.border_widths;
+        let origin = (
+            self.pos.0 + border_left + self.padding.0,
+            self.pos.1 + border_top + self.padding.1,
+        );
+        let (space_main, space_cross) = self.along((
+            self.draw_dim.0 - self.padding.0 - self.padding.2,
+            self.draw_dim.1 - self.padding.1 - self.padding.3,
+        ));
+        let total_cross = lines.iter().map(|line| line.cross).sum::<i32>()
+            + self.cross_gap * (lines.len() as i32 - 1);
+        let (mut cross_pos, line_gap) =
+            distribute(self.align_content, space_cross - total_cross, lines.len(), self.cross_gap);
+        for line in lines.iter() {
+            let line_children = &children[line.start..line.end];
+            let (mut main_pos, gap) =
+                distribute(self.main_align, space_main - line.main, line_children.len(), self.gap);
+            let aligns = line_children
+                .iter()
+                .map(|child| child.borrow().get_align_self().unwrap_or(self.cross_align))
+                .collect::<Vec<_>>();
+            let mut positions = vec![];
+            for (child, align) in line_children.iter().zip(aligns.iter()) {
+                let mut child = child.borrow_mut();
+                let (main, cross) = self.along(child.get_draw_dim());
+                let cross_offset = match align {
+                    Alignment::Center => (line.cross - cross) / 2,
+                    Alignment::End => line.cross - cross,
+                    _ => 0,
+                };
+                let (x, y) = self.along((main_pos, cross_pos + cross_offset));
+                let shift = self.relative_shift(&*child);
+                let pos = (origin.0 + x + shift.0, origin.1 + y + shift.1);
+                child.measure_positions(pos);
+                positions.push(pos);
+                main_pos += main + gap;
+            }
+            // Baselines are known once the children are placed, so they are moved after
+            if self.direction == Direction::Row && aligns.contains(&Alignment::Baseline) {
+                let baselines = line_children
+                    .iter()
+                    .map(|child| {
+                        let child = child.borrow();
+                        child.get_baseline().unwrap_or(child.get_draw_dim().1)
+                    })
+                    .collect::<Vec<_>>();
+                let line_baseline = baselines
+                    .iter()
+                    .zip(aligns.iter())
+                    .filter(|(_, align)| **align == Alignment::Baseline)
+                    .map(|(baseline, _)| *baseline)
+                    .max()
+                    .unwrap_or(0);
+                for (index, child) in line_children.iter().enumerate() {
+                    if aligns[index] == Alignment::Baseline {
+                        let (x, y) = positions[index];
+                        child.borrow_mut().measure_positions((x, y + line_baseline - baselines[index]));
+                    }
+                }
+            }
+            cross_pos += line.cross + line_gap;
+        }
+    }
+    /// Draws shadows, background and borders with the full rounded shape, clipped to the
+    /// part of the box that is visible in its parent
+    fn paint_box(&self) {
+        let (x, y, w, h) = self.paint_rect;
+        if self.draw_dim.1 <= 0 || w <= 0 || h <= 0 {
+            return;
+        }
+        if let Some((clip_y, clip_h)) = self.clip {
+            let extent = self.shadows.iter().map(Shadow::extent).max().unwrap_or(0);
+            paint::push_clip((x - extent, clip_y, w + extent * 2, clip_h));
+        }
+        let rect = ffi::Rectangle {
+            x: x as f32,
+            y: y as f32,
+            width: w as f32,
+            height: h as f32,
+        };
+        let (tl, tr, br, bl) = self.border_radius;
+        let background = match &self.gradient {
+            Some(gradient) => Fill::Gradient(gradient),
+            None => Fill::Solid(self.bg_color),
+        };
+        paint::paint_box(
+            rect,
+            [tl as f32, tr as f32, br as f32, bl as f32],
+            &self.shadows,
+            background,
+            self.border_widths,
+            self.border_colors,
+        );
+        if self.clip.is_some() {
+            paint::pop_clip();
+        }
+    }
+    fn draw_children(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        let mut abs_draw = vec![];
+        for child in self.children.iter() {
+            let child = child.borrow();
+            if common::draws_later(&*child) {
+                abs_draw.push(AbsoluteDraw::new(&child.get_id()));
+            } else {
+                abs_draw.extend(child.draw(draw_handle));
+            }
+        }
+        abs_draw
+    }
+    /// Shift of a `Relative` child, percentages are of the space inside the padding
+    fn relative_shift(&self, child: &dyn Base) -> (i32, i32) {
+        match child.get_position() {
+            Position::Relative(x, y) => (
+                x.resolve(self.draw_dim.0 - self.padding.0 - self.padding.2),
+                y.resolve(self.draw_dim.1 - self.padding.1 - self.padding.3),
+            ),
+            _ => (0, 0),
+        }
+    }
 }
 
 impl Base for Layout {
@@ -258,54 +794,10 @@ impl Base for Layout {
         self.on_click.clone()
     }
     fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
-        let visible_height = self.draw_dim.1;
-        let start_y = self.pos.1;
-        let border_color = self.border_color;
-        let border_width = self.border_width;
-        if visible_height > 0 {
-            if border_width > 0 {
-                draw_handle.draw_rectangle(
-                    self.pos.0,
-                    start_y,
-                    self.draw_dim.0 + border_width * 2,
-                    visible_height + border_width * 2,
-                    border_color,
-                );
-                draw_handle.draw_rectangle(
-                    self.pos.0 + border_width,
-                    start_y + border_width,
-                    self.draw_dim.0,
-                    visible_height,
-                    // self.draw_dim.0 - (border_width*2),
-                    // visible_height - (border_width * 2),
-                    self.bg_color,
-                );
-            } else {
-                draw_handle.draw_rectangle(
-                    self.pos.0,
-                    start_y,
-                    self.draw_dim.0,
-                    visible_height,
-                    self.bg_color,
-                );
-            }
-        }
-
-        let (auto_children, mut abs_children, sticky_children) = self.get_children_by_pos();
-        abs_children.extend(sticky_children);
-        let mut abs_draw = Vec::with_capacity(abs_children.len());
-
-        for child in abs_children.iter() {
-            abs_draw.push(AbsoluteDraw::new(&child.borrow().get_id()));
-        }
-
-        for child in auto_children.iter() {
-            let child = child.clone();
-            let abs_child_draws = child.borrow().draw(draw_handle);
-            abs_draw.extend(abs_child_draws);
-        }
-
-        abs_draw
+        paint::with_opacity(self.opacity, || {
+            self.paint_box();
+            self.draw_children(draw_handle)
+        })
     }
     fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
         let mut hit_children = Vec::new();
@@ -330,20 +822,44 @@ impl Base for Layout {
     fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
         self.children.clone()
     }
+    fn get_align_self(&self) -> Option<Alignment> {
+        self.align_self
+    }
+    fn get_baseline(&self) -> Option<i32> {
+        let (auto_children, _, _) = self.get_children_by_pos();
+        let first = auto_children.first()?.borrow();
+        first
+            .get_baseline()
+            .map(|baseline| first.get_draw_pos().1 - self.pos.1 + baseline)
+    }
+    fn set_stretch(&mut self, stretch: (Option<i32>, Option<i32>)) {
+        self.stretch = stretch;
+    }
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        let text_style = inherited.merge(&self.text_style);
+        for child in self.children.iter() {
+            child.borrow_mut().inherit_text_style(&text_style);
+        }
+    }
     fn set_raw_dim(&mut self, parent_dim: (i32, i32)) {
         let (draw_width, draw_height) = crate::ui::common::get_draw_dim(
             self.dim,
             parent_dim,
             &self.children,
             self.direction,
-            self.border_width,
+            self.border_widths,
         );
         self.draw_dim = (draw_width, draw_height);
+        if self.wrap {
+            self.fit_lines();
+        }
+        self.apply_stretch();
     }
     fn get_draw_dim(&self) -> (i32, i32) {
         self.draw_dim
     }
     fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        self.animate();
         let (auto_children, mut abs_children, sticky_children) = self.get_children_by_pos();
         abs_children.extend(sticky_children);
         // let auto_children = self.get_children();
@@ -353,9 +869,13 @@ impl Base for Layout {
             .map(|child| child.borrow().get_flex())
             .sum::<f32>();
         let mut ret_id = id;
-        for child in auto_children.iter() {
+        if self.wrap {
+            ret_id = self.measure_lines(&auto_children, parent_draw_dim, ret_id);
+        }
+        for child in auto_children.iter().filter(|_| !self.wrap) {
             let mut child = child.borrow_mut();
             let flex: f32 = child.get_flex();
+            let stretches = child.get_align_self().unwrap_or(self.cross_align) == Alignment::Stretch;
 
             match self.direction {
                 Direction::Row => {
@@ -363,6 +883,9 @@ impl Base for Layout {
                     let allowed_width = allowed_width - (self.gap * (auto_children_len - 1));
                     let child_width = f32::floor(flex * (allowed_width as f32 / total_flex)) as i32;
                     let child_height = self.draw_dim.1 - self.padding.1 - self.padding.3;
+                    if stretches {
+                        child.set_stretch((None, Some(child_height)));
+                    }
                     child.set_raw_dim((child_width, child_height));
                     ret_id = child.measure_dimensions((child_width, child_height), ret_id + 1);
                 }
@@ -372,6 +895,9 @@ impl Base for Layout {
                     let child_height =
                         f32::floor(flex * (allowed_height as f32 / total_flex)) as i32;
                     let child_width = self.draw_dim.0 - self.padding.0 - self.padding.2;
+                    if stretches {
+                        child.set_stretch((Some(child_width), None));
+                    }
                     child.set_raw_dim((child_width, child_height));
                     ret_id = child.measure_dimensions((child_width, child_height), ret_id + 1);
                 }
@@ -383,7 +909,11 @@ impl Base for Layout {
             child.set_raw_dim(self.draw_dim);
             ret_id = child.measure_dimensions(self.draw_dim, ret_id + 1)
         }
+        let animated_dim = self.draw_dim;
         self.set_raw_dim(parent_draw_dim);
+        if self.motion.transitions.iter().any(|(p, _)| *p == Property::Size) {
+            self.draw_dim = animated_dim;
+        }
         ret_id = ret_id + 1;
         if let ID::Auto(_) = &self.dbg_name {
             self.dbg_name = ID::Auto(ret_id.to_string());
@@ -391,122 +921,52 @@ impl Base for Layout {
         ret_id
     }
     fn measure_positions(&mut self, passed_pos: (i32, i32)) {
-        let border_width = self.border_width;
-        self.pos = (passed_pos.0, passed_pos.1);
-        let mut padding_left = self.padding.0;
-        let mut padding_top = self.padding.1;
-
+        let (border_left, border_top, border_right, border_bottom) = self.border_widths;
+        self.pos = (passed_pos.0 + self.offset.0, passed_pos.1 + self.offset.1);
+        self.paint_rect = (
+            self.pos.0,
+            self.pos.1,
+            self.draw_dim.0 + border_left + border_right,
+            self.draw_dim.1 + border_top + border_bottom,
+        );
         let (auto_children, mut abs_children, sticky_children) = self.get_children_by_pos();
         abs_children.extend(sticky_children);
 
         let auto_children_len = auto_children.len();
 
-        //Special Handling of abs children
+        // Out of flow children are placed against the box, anchored ones are left to the root
         for child in abs_children.iter() {
             let mut child = child.borrow_mut();
             let position = child.get_position();
-            match position {
-                Position::Auto => {
-                    panic!("Auto positioned children should not reach here")
-                }
-                Position::Abs(x, y) => {
-                    child.measure_positions((self.pos.0 + x, self.pos.1 + y));
-                }
-                Position::Sticky(x, y) => {
-                    child.measure_positions((self.pos.0 + x, self.pos.1 + y));
-                }
+            let Some(placement) = position.placement() else {
+                panic!("Auto positioned children should not reach here")
+            };
+            if placement.anchor.is_none() {
+                let pos = placement.resolve(self.paint_rect, child.get_draw_dim());
+                child.measure_positions(pos);
             }
         }
 
         if auto_children_len == 0 {
             return;
         }
-
-        let mut cross_paddings = Vec::from(
-            (0..auto_children_len)
-                .map(|_| self.padding.1)
-                .collect::<Vec<i32>>(),
-        );
-
-        let mut comparisons = [self.main_align, self.cross_align];
-
-        if self.direction == Direction::Column {
-            comparisons.swap(0, 1);
-        }
-
-        if comparisons[0] != Alignment::Start {
-            let self_width = self.draw_dim.0 - self.padding.0 - self.padding.2;
-            let children_width = auto_children
-                .iter()
-                .map(|child| child.borrow().get_draw_dim().0);
-            let children_width = match self.direction {
-                Direction::Row => children_width.sum(),
-                Direction::Column => children_width.max().unwrap(),
-            };
-            let total_gap = self.gap * (auto_children_len as i32 - 1);
-            let remaining_space = self_width - children_width - total_gap;
-
-            if comparisons[0] == Alignment::Center {
-                if self.direction == Direction::Column {
-                    // If column and cross align center, each child is centered
-                    for (idx, child) in auto_children.iter().enumerate() {
-                        let child_width = child.borrow().get_draw_dim().0;
-                        cross_paddings[idx] = self.padding.0 + (self_width - child_width) / 2;
-                    }
-                } else {
-                    padding_left = self.padding.0 + remaining_space / 2;
-                }
-            }
-
-            if comparisons[0] == Alignment::End {
-                padding_left = self.padding.0 + remaining_space;
-            }
-        }
-
-        if comparisons[1] != Alignment::Start {
-            let self_height = self.draw_dim.1 - self.padding.1 - self.padding.3;
-            let children_height = auto_children
-                .iter()
-                .map(|child| child.borrow().get_draw_dim().1);
-            let children_height = match self.direction {
-                Direction::Row => children_height.max().unwrap(),
-                Direction::Column => children_height.sum(),
-            };
-            let total_gap = self.gap * (auto_children_len as i32 - 1);
-            let remaining_space = self_height - children_height - total_gap;
-            let remaining_space = remaining_space.max(0);
-            if comparisons[1] == Alignment::Center {
-                padding_top = self.padding.1 + remaining_space / 2;
-            }
-            if comparisons[1] == Alignment::End {
-                padding_top = self.padding.1 + remaining_space;
-            }
-        }
-
-        let mut next_pos = self.pos;
-        if self.direction == Direction::Column && self.cross_align == Alignment::Center {
-            next_pos.0 = self.pos.0 + cross_paddings[0] + border_width;
+        let lines = if self.wrap {
+            self.lines.clone()
         } else {
-            next_pos.0 += padding_left + border_width;
-        }
-        next_pos.1 += padding_top + border_width;
-        for (idx, child) in auto_children.iter().enumerate() {
-            let mut child = child.borrow_mut();
-            child.measure_positions(next_pos);
-
-            let (child_width, child_height) = child.get_draw_dim();
-            if idx < self.children.len() - 1 {
-                match self.direction {
-                    Direction::Row => next_pos.0 += child_width + self.gap,
-                    Direction::Column => {
-                        if self.cross_align == Alignment::Center {
-                            next_pos.0 = self.pos.0 + cross_paddings[idx + 1];
-                        }
-                        next_pos.1 += child_height + self.gap
-                    }
-                }
-            }
-        }
+            // Unwrapped children share one line as thick as the layout
+            let mains = auto_children.iter().map(|child| self.along(child.borrow().get_draw_dim()).0);
+            vec![WrapLine {
+                start: 0,
+                end: auto_children_len,
+                main: mains.sum::<i32>() + self.gap * (auto_children_len as i32 - 1),
+                cross: self.along((
+                    self.draw_dim.0 - self.padding.0 - self.padding.2,
+                    self.draw_dim.1 - self.padding.1 - self.padding.3,
+                ))
+                .1,
+            }]
+        };
+        self.position_lines(&auto_children, &lines);
     }
 
     fn measure_overflows(
@@ -541,13 +1001,20 @@ impl Base for Layout {
         let (start_y, visible_height) =
             get_drawable_y_and_h(container_y, container_h, start_y, content_h);
 
+        self.paint_rect.1 = content_y - y_offset;
+        self.clip = if visible_height != content_h {
+            Some((container_y, container_h))
+        } else {
+            None
+        };
         self.draw_dim.1 = visible_height;
         self.pos.1 = start_y;
 
         for child in self.children.iter() {
             let mut child = child.borrow_mut();
             match child.get_position() {
-                Position::Auto => {
+                position if position.is_anchored() => {}
+                Position::Auto | Position::Relative(_, _) => {
                     child.measure_overflows(
                         self.get_draw_dim(),
                         self.get_draw_pos(),
@@ -555,7 +1022,7 @@ impl Base for Layout {
                         y_offset + scroll_top,
                     );
                 }
-                Position::Sticky(_, _) => {
+                Position::Sticky(_) => {
                     child.measure_overflows(
                         self.get_draw_dim(),
                         self.get_draw_pos(),
@@ -563,7 +1030,7 @@ impl Base for Layout {
                         scroll_top,
                     );
                 }
-                Position::Abs(_, _) => {
+                Position::Abs(_) => {
                     child.measure_overflows(
                         self.get_draw_dim(),
                         self.get_draw_pos(),
@@ -646,6 +1113,12 @@ impl Base for Layout {
     }
 
     fn get_position(&self) -> Position {
-        self.position
+        self.position.clone()
+    }
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.tooltip.clone()
     }
 }
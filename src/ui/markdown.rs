@@ -0,0 +1,1016 @@
+use std::{cell::RefCell, rc::Rc};
+
+use raylib::color::Color;
+
+use crate::ui::{
+    common::{Alignment, Component, Length, MouseEvent, Position},
+    font::{self, FontSpec},
+    layout::{Layout, LayoutProps},
+    paint::Shadow,
+    span::{MONOSPACE_FAMILY, TextSpan},
+    style::Style,
+    text_layout::{TextLayout, TextLayoutProps},
+    text_wrap::WhiteSpace,
+    theme,
+    tooltip::Tooltip,
+};
+
+/// What `MarkdownProps::style` accepts
+pub trait MarkdownStyling {
+    fn apply_to(self, props: MarkdownProps) -> MarkdownProps;
+}
+
+impl MarkdownStyling for MarkdownStyle {
+    fn apply_to(self, mut props: MarkdownProps) -> MarkdownProps {
+        props.layout = props.layout.gap(self.block_gap);
+        props.style = self;
+        props
+    }
+}
+
+/// Box properties go to the outer layout, text colour, font and size to the body text
+impl MarkdownStyling for &Style {
+    fn apply_to(self, mut props: MarkdownProps) -> MarkdownProps {
+        if let Some(color) = self.text.color {
+            props.style.text_color = Some(color);
+        }
+        if let Some(font) = &self.text.font {
+            props.style.font = Some(font.clone());
+        }
+        if let Some(size) = self.text.font_size {
+            props.style.font_size = size;
+        }
+        props.layout = self.apply_box(props.layout);
+        props
+    }
+}
+
+/// Element kinds passed to the styling hooks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkdownElement {
+    Heading(usize),
+    Paragraph,
+    CodeBlock,
+    BlockQuote,
+    List,
+    ListItem,
+    ListMarker,
+    Rule,
+}
+
+#[derive(Clone)]
+pub struct MarkdownStyle {
+    /// Body font, `None` inherits it from the enclosing layouts
+    pub font: Option<FontSpec>,
+    pub font_size: i32,
+    /// Body text colour, `None` inherits it from the enclosing layouts
+    pub text_color: Option<Color>,
+    /// Font sizes for `#` to `######`
+    pub heading_sizes: [i32; 6],
+    pub link_color: Color,
+    pub code_family: String,
+    pub code_color: Color,
+    pub code_bg: Color,
+    pub quote_color: Color,
+    pub quote_bg: Color,
+    pub rule_color: Color,
+    pub block_gap: i32,
+    pub list_indent: i32,
+    pub bullet: String,
+}
+
+impl MarkdownStyle {
+    pub fn new() -> Self {
+        let theme = theme::current();
+        let sizes = theme.typography;
+        Self {
+            font: None,
+            font_size: theme.typography.md,
+            text_color: None,
+            // Halfway steps fill the gaps of the type scale
+            heading_sizes: [
+                sizes.xl,
+                (sizes.xl + sizes.lg) / 2,
+                sizes.lg,
+                (sizes.lg + sizes.md) / 2,
+                sizes.md,
+                sizes.md,
+            ],
+            link_color: theme.palette.link,
+            code_family: MONOSPACE_FAMILY.into(),
+            code_color: theme.palette.text,
+            code_bg: theme.palette.surface_alt,
+            quote_color: theme.palette.text_muted,
+            quote_bg: Color {
+                a: 20,
+                ..theme.palette.text
+            },
+            rule_color: theme.palette.border,
+            block_gap: 6,
+            list_indent: 24,
+            bullet: String::from("•"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    CodeBlock(String),
+    Quote(Vec<Block>),
+    /// Start number for ordered lists
+    List(Option<usize>, Vec<Vec<Block>>),
+    Rule,
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn heading_level(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, text.to_string()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let chars = line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+    chars.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| chars.iter().all(|c| c == marker))
+}
+
+fn fence(line: &str) -> Option<(char, usize)> {
+    let marker = line.chars().next()?;
+    if marker != '`' && marker != '~' {
+        return None;
+    }
+    let count = line.chars().take_while(|c| *c == marker).count();
+    if count >= 3 { Some((marker, count)) } else { None }
+}
+
+/// (ordered start, width of the marker including the following space)
+fn list_marker(line: &str) -> Option<(Option<usize>, usize)> {
+    let mut chars = line.chars();
+    let first = chars.next()?;
+    if first == '-' || first == '*' || first == '+' {
+        return match chars.next() {
+            None => Some((None, 1)),
+            Some(' ') => Some((None, 2)),
+            _ => None,
+        };
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = &line[digits..];
+    if !(rest.starts_with('.') || rest.starts_with(')')) {
+        return None;
+    }
+    let start = line[..digits].parse::<usize>().ok()?;
+    match rest[1..].chars().next() {
+        None => Some((Some(start), digits + 1)),
+        Some(' ') => Some((Some(start), digits + 2)),
+        _ => None,
+    }
+}
+
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    heading_level(trimmed).is_some()
+        || fence(trimmed).is_some()
+        || trimmed.starts_with('>')
+        || is_rule(trimmed)
+        || list_marker(trimmed).is_some()
+}
+
+/// Joins paragraph lines, keeping hard breaks (two trailing spaces or a backslash).
+/// With `hard_breaks` every newline is kept, as chat clients do.
+fn join_paragraph(lines: &[String], hard_breaks: bool) -> String {
+    let mut text = String::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let is_last = idx == lines.len() - 1;
+        let hard_break = hard_breaks || line.ends_with("  ") || line.ends_with('\\');
+        let content = line.trim_end().trim_end_matches('\\');
+        text.push_str(content.trim_start());
+        if !is_last {
+            text.push(if hard_break { '\n' } else { ' ' });
+        }
+    }
+    text
+}
+
+fn parse_blocks(lines: &[String], hard_breaks: bool) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<String> = vec![];
+    let mut i = 0;
+
+    macro_rules! flush_paragraph {
+        () => {
+            if !paragraph.is_empty() {
+                blocks.push(Block::Paragraph(join_paragraph(&paragraph, hard_breaks)));
+                paragraph.clear();
+            }
+        };
+    }
+
+    while i < lines.len() {
+        let line = &lines[i];
+        let trimmed = line.trim_start();
+        let indent = leading_spaces(line);
+
+        if trimmed.is_empty() {
+            flush_paragraph!();
+            i += 1;
+            continue;
+        }
+
+        // Indented code, only where it can't be a paragraph continuation
+        if indent >= 4 && paragraph.is_empty() {
+            let mut code = vec![];
+            while i < lines.len() && (leading_spaces(&lines[i]) >= 4 || lines[i].trim().is_empty()) {
+                code.push(lines[i].get(4..).unwrap_or("").to_string());
+                i += 1;
+            }
+            while code.last().is_some_and(|line| line.trim().is_empty()) {
+                code.pop();
+            }
+            blocks.push(Block::CodeBlock(code.join("\n")));
+            continue;
+        }
+
+        if let Some((marker, count)) = fence(trimmed) {
+            flush_paragraph!();
+            let mut code = vec![];
+            i += 1;
+            while i < lines.len() {
+                let closing = lines[i].trim();
+                if fence(closing).is_some_and(|(m, c)| m == marker && c >= count)
+                    && closing.chars().all(|c| c == marker)
+                {
+                    i += 1;
+                    break;
+                }
+                let strip = leading_spaces(&lines[i]).min(indent);
+                code.push(lines[i][strip..].to_string());
+                i += 1;
+            }
+            blocks.push(Block::CodeBlock(code.join("\n")));
+            continue;
+        }
+
+        if let Some((level, text)) = heading_level(trimmed) {
+            flush_paragraph!();
+            blocks.push(Block::Heading(level, text));
+            i += 1;
+            continue;
+        }
+
+        // Setext headings underline the paragraph above them
+        if !paragraph.is_empty() && !trimmed.contains(' ') {
+            if trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-') {
+                let level = if trimmed.starts_with('=') { 1 } else { 2 };
+                blocks.push(Block::Heading(level, join_paragraph(&paragraph, hard_breaks)));
+                paragraph.clear();
+                i += 1;
+                continue;
+            }
+        }
+
+        if is_rule(trimmed) {
+            flush_paragraph!();
+            blocks.push(Block::Rule);
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            flush_paragraph!();
+            let mut quoted = vec![];
+            while i < lines.len() {
+                let quote_line = lines[i].trim_start();
+                if let Some(rest) = quote_line.strip_prefix('>') {
+                    quoted.push(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+                } else if !quote_line.is_empty()
+                    && !starts_block(quote_line)
+                    && quoted.last().is_some_and(|last: &String| !last.trim().is_empty())
+                {
+                    // Lazy continuation of the quoted paragraph
+                    quoted.push(quote_line.to_string());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse_blocks(&quoted, hard_breaks)));
+            continue;
+        }
+
+        if let Some((start, marker_width)) = list_marker(trimmed) {
+            // A list can interrupt a paragraph only when its first item has content
+            if paragraph.is_empty() || trimmed.len() > marker_width {
+                flush_paragraph!();
+                let ordered = start.is_some();
+                let content_col = indent + marker_width;
+                let mut items: Vec<Vec<String>> = vec![];
+                let mut item = vec![trimmed[marker_width..].to_string()];
+                i += 1;
+                while i < lines.len() {
+                    let next = &lines[i];
+                    let next_trimmed = next.trim_start();
+                    let next_indent = leading_spaces(next);
+                    if next_trimmed.is_empty() {
+                        // Blank lines stay in the item if the list continues after them
+                        let continues = lines[i + 1..]
+                            .iter()
+                            .find(|line| !line.trim().is_empty())
+                            .is_some_and(|line| {
+                                leading_spaces(line) >= content_col
+                                    || (leading_spaces(line) == indent
+                                        && list_marker(line.trim_start())
+                                            .is_some_and(|(s, _)| s.is_some() == ordered))
+                            });
+                        if !continues {
+                            break;
+                        }
+                        item.push(String::new());
+                    } else if next_indent >= content_col {
+                        item.push(next[content_col..].to_string());
+                    } else if next_indent == indent
+                        && list_marker(next_trimmed).is_some_and(|(s, _)| s.is_some() == ordered)
+                    {
+                        let (_, width) = list_marker(next_trimmed).unwrap();
+                        items.push(std::mem::replace(&mut item, vec![next_trimmed[width..].to_string()]));
+                    } else if !starts_block(next_trimmed)
+                        && item.last().is_some_and(|last| !last.trim().is_empty())
+                    {
+                        item.push(next_trimmed.to_string());
+                    } else {
+                        break;
+                    }
+                    i += 1;
+                }
+                items.push(item);
+                blocks.push(Block::List(
+                    start,
+                    items.iter().map(|item| parse_blocks(item, hard_breaks)).collect(),
+                ));
+                continue;
+            }
+        }
+
+        paragraph.push(line.to_string());
+        i += 1;
+    }
+    flush_paragraph!();
+    blocks
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct InlineState {
+    bold: bool,
+    italic: bool,
+    link: bool,
+}
+
+fn styled_span(text: &str, state: InlineState) -> TextSpan {
+    let mut span = TextSpan::new(text);
+    if state.bold {
+        span = span.bold();
+    }
+    if state.italic {
+        span = span.italic();
+    }
+    span
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|x| **x == c).count()
+}
+
+/// Closing delimiter of `n` `c`s after `start`, not preceded by whitespace
+fn find_closing(chars: &[char], start: usize, c: char, n: usize) -> Option<usize> {
+    let mut j = start + 1;
+    while j + n <= chars.len() {
+        if chars[j] == '\\' {
+            j += 2;
+            continue;
+        }
+        if chars[j] == '`' {
+            // Code spans bind tighter than emphasis
+            let ticks = run_length(chars, j, '`');
+            match find_code_end(chars, j + ticks, ticks) {
+                Some(end) => {
+                    j = end + ticks;
+                    continue;
+                }
+                None => {
+                    j += ticks;
+                    continue;
+                }
+            }
+        }
+        let run = run_length(chars, j, c);
+        if run >= n && !chars[j - 1].is_whitespace() {
+            let intraword_underscore = c == '_'
+                && chars.get(j + run).is_some_and(|next| next.is_alphanumeric());
+            if !intraword_underscore && (run == n || n == 2 || run > 2) {
+                // With `***` the closing delimiter is the innermost part of the run
+                return Some(j + run - n);
+            }
+        }
+        j += run.max(1);
+    }
+    None
+}
+
+fn find_code_end(chars: &[char], start: usize, ticks: usize) -> Option<usize> {
+    let mut j = start;
+    while j < chars.len() {
+        if chars[j] == '`' {
+            let run = run_length(chars, j, '`');
+            if run == ticks {
+                return Some(j);
+            }
+            j += run;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+/// `[label](url)` starting at `start`: (label end, url, index after the link)
+fn parse_link(chars: &[char], start: usize) -> Option<(usize, String, usize)> {
+    let mut depth = 0;
+    let mut j = start;
+    let label_end = loop {
+        match chars.get(j)? {
+            '\\' => j += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break j;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    };
+    if chars.get(label_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_start = label_end + 2;
+    let url_end = url_start + chars[url_start..].iter().position(|c| *c == ')')?;
+    let target: String = chars[url_start..url_end].iter().collect();
+    // Drop an optional title: [label](url "title")
+    let url = target.split_whitespace().next().unwrap_or("");
+    let url = url.trim_start_matches('<').trim_end_matches('>');
+    Some((label_end, url.to_string(), url_end + 1))
+}
+
+fn parse_inlines(
+    chars: &[char],
+    state: InlineState,
+    style: &MarkdownStyle,
+    spans: &mut Vec<TextSpan>,
+) {
+    let mut plain = String::new();
+    let mut i = 0;
+
+    macro_rules! flush_plain {
+        () => {
+            if !plain.is_empty() {
+                spans.push(styled_span(&plain, state));
+                plain.clear();
+            }
+        };
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\\' && chars.get(i + 1).is_some_and(|next| next.is_ascii_punctuation()) {
+            plain.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+
+        if c == '`' {
+            let ticks = run_length(chars, i, '`');
+            if let Some(end) = find_code_end(chars, i + ticks, ticks) {
+                flush_plain!();
+                let code: String = chars[i + ticks..end].iter().collect();
+                let code = if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') {
+                    code[1..code.len() - 1].to_string()
+                } else {
+                    code
+                };
+                spans.push(
+                    TextSpan::new(&code)
+                        .font_family(&style.code_family)
+                        .color(style.code_color)
+                        .background(style.code_bg),
+                );
+                i = end + ticks;
+                continue;
+            }
+            plain.extend(&chars[i..i + ticks]);
+            i += ticks;
+            continue;
+        }
+
+        if c == '*' || c == '_' {
+            let run = run_length(chars, i, c);
+            let opens = chars.get(i + run).is_some_and(|next| !next.is_whitespace());
+            let intraword = c == '_' && i > 0 && chars[i - 1].is_alphanumeric();
+            if opens && !intraword {
+                let n = run.min(2);
+                if let Some(end) = find_closing(chars, i + n - 1, c, n) {
+                    flush_plain!();
+                    let mut inner = state;
+                    if n == 2 {
+                        inner.bold = true;
+                    } else {
+                        inner.italic = true;
+                    }
+                    parse_inlines(&chars[i + n..end], inner, style, spans);
+                    i = end + n;
+                    continue;
+                }
+            }
+            plain.extend(&chars[i..i + run]);
+            i += run;
+            continue;
+        }
+
+        if c == '[' && !state.link {
+            if let Some((label_end, url, next)) = parse_link(chars, i) {
+                flush_plain!();
+                let mut label_spans = vec![];
+                let mut inner = state;
+                inner.link = true;
+                parse_inlines(&chars[i + 1..label_end], inner, style, &mut label_spans);
+                spans.extend(
+                    label_spans
+                        .into_iter()
+                        .map(|span| span.link(&url).color(style.link_color)),
+                );
+                i = next;
+                continue;
+            }
+        }
+
+        // Autolinks: <https://...> and bare URLs
+        let rest: String = chars[i..chars.len().min(i + 8)].iter().collect();
+        let at_word_start = i == 0 || chars[i - 1].is_whitespace() || chars[i - 1] == '(';
+        if !state.link && c == '<' && (rest.starts_with("<http://") || rest.starts_with("<https:/")) {
+            if let Some(len) = chars[i..].iter().position(|c| *c == '>') {
+                flush_plain!();
+                let url: String = chars[i + 1..i + len].iter().collect();
+                spans.push(styled_span(&url, state).link(&url).color(style.link_color));
+                i += len + 1;
+                continue;
+            }
+        }
+        if !state.link && at_word_start && (rest.starts_with("http://") || rest.starts_with("https://")) {
+            let len = chars[i..]
+                .iter()
+                .position(|c| c.is_whitespace())
+                .unwrap_or(chars.len() - i);
+            let mut url: String = chars[i..i + len].iter().collect();
+            // Trailing punctuation usually belongs to the sentence
+            while url.ends_with(['.', ',', ';', ':', '!', '?', ')']) {
+                url.pop();
+            }
+            flush_plain!();
+            spans.push(styled_span(&url, state).link(&url).color(style.link_color));
+            i += url.chars().count();
+            continue;
+        }
+
+        plain.push(c);
+        i += 1;
+    }
+    flush_plain!();
+}
+
+fn inline_spans(text: &str, style: &MarkdownStyle) -> Vec<TextSpan> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut spans = vec![];
+    parse_inlines(&chars, InlineState::default(), style, &mut spans);
+    spans
+}
+
+pub type TextHook = Rc<dyn Fn(MarkdownElement, TextLayoutProps) -> TextLayoutProps>;
+pub type ContainerHook = Rc<dyn Fn(MarkdownElement, LayoutProps) -> LayoutProps>;
+
+struct Renderer {
+    style: MarkdownStyle,
+    text_hook: Option<TextHook>,
+    container_hook: Option<ContainerHook>,
+    on_link_click: Rc<RefCell<dyn FnMut(String)>>,
+    /// `FIT` when the markdown root shrinks to its content, `FILL` otherwise
+    width: Length,
+}
+
+impl Renderer {
+    fn text(&self, element: MarkdownElement, props: TextLayoutProps) -> Component {
+        let on_link_click = self.on_link_click.clone();
+        let mut props = props.on_link_click(Box::new(move |url| {
+            let mut f = on_link_click.borrow_mut();
+            f(url)
+        }));
+        if let Some(font) = &self.style.font {
+            props = props.font(font.clone());
+        }
+        if let Some(color) = self.style.text_color {
+            props = props.text_color(color);
+        }
+        let props = match &self.text_hook {
+            Some(hook) => hook(element, props),
+            None => props,
+        };
+        props.build()
+    }
+
+    fn container(&self, element: MarkdownElement, props: LayoutProps) -> Component {
+        let props = match &self.container_hook {
+            Some(hook) => hook(element, props),
+            None => props,
+        };
+        props.build()
+    }
+
+    fn blocks(&self, blocks: &[Block]) -> Vec<Component> {
+        blocks.iter().map(|block| self.block(block)).collect()
+    }
+
+    fn block(&self, block: &Block) -> Component {
+        let style = &self.style;
+        match block {
+            Block::Heading(level, text) => {
+                let spans = inline_spans(text, style)
+                    .into_iter()
+                    .map(|span| span.bold())
+                    .collect();
+                let size = style.heading_sizes[(level - 1).min(5)];
+                self.text(
+                    MarkdownElement::Heading(*level),
+                    TextLayout::get_builder()
+                        .spans(spans)
+                        .font_size(size)
+                        .dim((self.width, Length::FIT)),
+                )
+            }
+            Block::Paragraph(text) => self.text(
+                MarkdownElement::Paragraph,
+                TextLayout::get_builder()
+                    .spans(inline_spans(text, style))
+                    .font_size(style.font_size)
+                    .dim((self.width, Length::FIT)),
+            ),
+            Block::CodeBlock(code) => {
+                let code = self.text(
+                    MarkdownElement::CodeBlock,
+                    TextLayout::get_builder()
+                        .spans(vec![
+                            TextSpan::new(code)
+                                .font_family(&style.code_family)
+                                .color(style.code_color),
+                        ])
+                        .font_size(style.font_size)
+                        .white_space(WhiteSpace::Pre)
+                        .dim((Length::FIT, Length::FIT)),
+                );
+                self.container(
+                    MarkdownElement::CodeBlock,
+                    Layout::get_col_builder()
+                        .dim((self.width, Length::FIT))
+                        .bg_color(style.code_bg)
+                        .padding((8, 6, 8, 6))
+                        .overflow_y(false)
+                        .children(vec![code]),
+                )
+            }
+            Block::Quote(blocks) => {
+                let quote_renderer = Renderer {
+                    style: MarkdownStyle {
+                        text_color: Some(style.quote_color),
+                        ..style.clone()
+                    },
+                    text_hook: self.text_hook.clone(),
+                    container_hook: self.container_hook.clone(),
+                    on_link_click: self.on_link_click.clone(),
+                    width: self.width,
+                };
+                self.container(
+                    MarkdownElement::BlockQuote,
+                    Layout::get_col_builder()
+                        .dim((self.width, Length::FIT))
+                        .bg_color(style.quote_bg)
+                        .padding((style.list_indent / 2, 4, 4, 4))
+                        .gap(style.block_gap)
+                        .overflow_y(false)
+                        .children(quote_renderer.blocks(blocks)),
+                )
+            }
+            Block::List(start, items) => {
+                let bullet_font = style.font.clone().unwrap_or_default();
+                let bullet = if style.bullet.chars().all(|c| font::has_glyph(&bullet_font, c, style.font_size)) {
+                    style.bullet.clone()
+                } else {
+                    String::from("-")
+                };
+                let children = items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, item)| {
+                        let marker_text = match start {
+                            Some(start) => format!("{}.", start + idx),
+                            None => bullet.clone(),
+                        };
+                        // The marker hangs in the item's left padding
+                        let marker = self.text(
+                            MarkdownElement::ListMarker,
+                            TextLayout::get_builder()
+                                .content(&marker_text)
+                                .font_size(style.font_size)
+                                .wrap(false)
+                                .set_position(Position::abs(0, 0)),
+                        );
+                        let mut children = vec![marker];
+                        children.extend(self.blocks(item));
+                        self.container(
+                            MarkdownElement::ListItem,
+                            Layout::get_col_builder()
+                                .dim((self.width, Length::FIT))
+                                .padding((style.list_indent, 0, 0, 0))
+                                .gap(style.block_gap)
+                                .overflow_y(false)
+                                .children(children),
+                        )
+                    })
+                    .collect();
+                self.container(
+                    MarkdownElement::List,
+                    Layout::get_col_builder()
+                        .dim((self.width, Length::FIT))
+                        .gap(style.block_gap / 2)
+                        .overflow_y(false)
+                        .children(children),
+                )
+            }
+            Block::Rule => self.container(
+                MarkdownElement::Rule,
+                Layout::get_row_builder()
+                    .dim((Length::FILL, Length::FIXED(1)))
+                    .bg_color(style.rule_color)
+                    .overflow_y(false),
+            ),
+        }
+    }
+}
+
+pub struct MarkdownProps {
+    pub layout: LayoutProps,
+    pub content: String,
+    pub hard_breaks: bool,
+    pub style: MarkdownStyle,
+    pub text_hook: Option<TextHook>,
+    pub container_hook: Option<ContainerHook>,
+    pub on_link_click: Rc<RefCell<dyn FnMut(String)>>,
+}
+
+impl MarkdownProps {
+    pub fn new() -> Self {
+        let layout = Layout::get_col_builder()
+            .dim((Length::FILL, Length::FIT))
+            .main_align(Alignment::Start)
+            .cross_align(Alignment::Start)
+            .overflow_y(false);
+        let style = MarkdownStyle::new();
+        Self {
+            layout: layout.gap(style.block_gap),
+            content: String::from(""),
+            hard_breaks: false,
+            style,
+            text_hook: None,
+            container_hook: None,
+            on_link_click: Rc::new(RefCell::new(|_url| {})),
+        }
+    }
+    pub fn content(mut self, content: &str) -> Self {
+        self.content = content.into();
+        self
+    }
+    /// Treat every newline in a paragraph as a line break
+    pub fn hard_breaks(mut self, hard_breaks: bool) -> Self {
+        self.hard_breaks = hard_breaks;
+        self
+    }
+    /// Takes a `MarkdownStyle`, or a shared `Style` for the box and the body text
+    pub fn style(self, style: impl MarkdownStyling) -> Self {
+        style.apply_to(self)
+    }
+    /// Adjusts the text builder of every heading, paragraph, code block or list marker
+    pub fn text_hook(mut self, f: TextHook) -> Self {
+        self.text_hook = Some(f);
+        self
+    }
+    /// Adjusts the layout builder of every code block, quote, list, list item or rule
+    pub fn container_hook(mut self, f: ContainerHook) -> Self {
+        self.container_hook = Some(f);
+        self
+    }
+    pub fn on_link_click(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_link_click = Rc::new(RefCell::new(f));
+        self
+    }
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        self.layout = self.layout.dim(dim);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout = self.layout.bg_color(color);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.layout = self.layout.padding(padding);
+        self
+    }
+    pub fn border_width(mut self, border_width: i32) -> Self {
+        self.layout = self.layout.border_width(border_width);
+        self
+    }
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
+        self.layout = self.layout.border_color(border_color);
+        self
+    }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        self.layout = self.layout.border_radius(radius);
+        self
+    }
+    pub fn border_radii(mut self, radii: (i32, i32, i32, i32)) -> Self {
+        self.layout = self.layout.border_radii(radii);
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.layout = self.layout.shadow(shadow);
+        self
+    }
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        self.layout = self.layout.dbg_name(name);
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout = self.layout.tooltip(tooltip);
+        self
+    }
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.layout = self.layout.flex(flex);
+        self
+    }
+    pub fn on_click(mut self, f: Box<dyn FnMut(MouseEvent) -> bool>) -> Self {
+        self.layout = self.layout.on_click(f);
+        self
+    }
+    pub fn set_position(mut self, position: Position) -> Self {
+        self.layout = self.layout.set_position(position);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Layout>> {
+        let width = match self.layout.clone().get_layout().dim.0 {
+            Length::FIT | Length::FIT_PER(_) => Length::FIT,
+            _ => Length::FILL,
+        };
+        let renderer = Renderer {
+            style: self.style,
+            text_hook: self.text_hook,
+            container_hook: self.container_hook,
+            on_link_click: self.on_link_click,
+            width,
+        };
+        let lines = self
+            .content
+            .replace("\r\n", "\n")
+            .split('\n')
+            .map(|line| line.replace('\t', "    "))
+            .collect::<Vec<_>>();
+        let children = renderer.blocks(&parse_blocks(&lines, self.hard_breaks));
+        self.layout.children(children).build()
+    }
+}
+
+/// Renders CommonMark into `Layout`/`TextLayout` components
+pub struct Markdown {}
+
+impl Markdown {
+    pub fn get_builder() -> MarkdownProps {
+        MarkdownProps::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::font::{FontStyle, FontWeight};
+
+    /// (text, bold, italic) of each span
+    fn emphasis(text: &str) -> Vec<(String, bool, bool)> {
+        inline_spans(text, &MarkdownStyle::new())
+            .into_iter()
+            .map(|span| {
+                let bold = span.font_weight == Some(FontWeight::Bold);
+                let italic = span.font_style == Some(FontStyle::Italic);
+                (span.text, bold, italic)
+            })
+            .collect()
+    }
+
+    fn blocks(text: &str) -> Vec<Block> {
+        let lines = text.lines().map(|line| line.to_string()).collect::<Vec<_>>();
+        parse_blocks(&lines, false)
+    }
+
+    fn code(block: &Block) -> &str {
+        match block {
+            Block::CodeBlock(code) => code,
+            other => panic!("expected a code block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn emphasis_nests() {
+        assert_eq!(
+            emphasis("**bold *both* bold**"),
+            vec![
+                ("bold ".to_string(), true, false),
+                ("both".to_string(), true, true),
+                (" bold".to_string(), true, false),
+            ]
+        );
+        assert_eq!(
+            emphasis("_a **b** c_"),
+            vec![
+                ("a ".to_string(), false, true),
+                ("b".to_string(), true, true),
+                (" c".to_string(), false, true),
+            ]
+        );
+        assert_eq!(emphasis("***both***"), vec![("both".to_string(), true, true)]);
+    }
+
+    #[test]
+    fn unmatched_emphasis_stays_plain() {
+        assert_eq!(emphasis("**open"), vec![("**open".to_string(), false, false)]);
+        assert_eq!(emphasis("snake_case_name"), vec![("snake_case_name".to_string(), false, false)]);
+        assert_eq!(emphasis("a * b"), vec![("a * b".to_string(), false, false)]);
+        assert_eq!(emphasis("\\*not\\*"), vec![("*not*".to_string(), false, false)]);
+    }
+
+    #[test]
+    fn code_spans_ignore_emphasis() {
+        let spans = inline_spans("`a *b*` *c*", &MarkdownStyle::new());
+        assert_eq!(spans[0].text, "a *b*");
+        assert_eq!(spans[0].font_family.as_deref(), Some(MONOSPACE_FAMILY));
+        assert_eq!(spans[2].text, "c");
+        assert_eq!(spans[2].font_style, Some(FontStyle::Italic));
+    }
+
+    #[test]
+    fn fenced_code_keeps_its_lines() {
+        let parsed = blocks("```rust\nlet a = *b*;\n\n  indented\n```\nafter");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(code(&parsed[0]), "let a = *b*;\n\n  indented");
+        assert!(matches!(&parsed[1], Block::Paragraph(text) if text == "after"));
+    }
+
+    #[test]
+    fn fences_close_on_a_long_enough_marker() {
+        assert_eq!(code(&blocks("````\n```\n~~~~\n````")[0]), "```\n~~~~");
+        // An unclosed fence runs to the end
+        assert_eq!(code(&blocks("~~~\na\nb")[0]), "a\nb");
+        // The fence's indent is taken off its lines
+        assert_eq!(code(&blocks("  ```\n    code\n  ```")[0]), "  code");
+    }
+
+    #[test]
+    fn fence_interrupts_a_paragraph() {
+        let parsed = blocks("text\n```\ncode\n```");
+        assert!(matches!(&parsed[0], Block::Paragraph(text) if text == "text"));
+        assert_eq!(code(&parsed[1]), "code");
+    }
+}
@@ -0,0 +1,205 @@
+use std::{cell::RefCell, rc::Rc};
+
+use raylib::color::Color;
+
+use crate::ui::{
+    animation::Animation,
+    common::{Alignment, Component, Length, Offset, Placement, Position},
+    layout::Layout,
+};
+
+thread_local! {
+    static PENDING: RefCell<Vec<Layer>> = RefCell::new(vec![]);
+}
+
+/// Content drawn above the app in its own layer. It can be shown from anywhere while the
+/// UI is built and doesn't take part in the layout of the component that shows it.
+#[derive(Clone)]
+pub struct OverlayProps {
+    pub content: Option<Component>,
+    /// Blocks input to everything below and keeps keyboard focus inside
+    pub modal: bool,
+    /// Fills the window behind the content
+    pub backdrop: Option<Color>,
+    /// Places the content against the window or its `anchor`, centred when unset
+    pub placement: Option<Placement>,
+    pub padding: (i32, i32, i32, i32),
+    pub close_on_escape: bool,
+    /// Clicks outside the content dismiss it
+    pub close_on_backdrop: bool,
+    /// Called to close the overlay, it stays up until the builder stops showing it
+    pub on_dismiss: Rc<RefCell<dyn FnMut()>>,
+    /// Overlays stack in `z_index` order, then in the order they're shown
+    pub z_index: i32,
+    pub dbg_name: Option<String>,
+    pub enter: Option<Animation>,
+    pub exit: Option<Animation>,
+}
+
+impl OverlayProps {
+    pub fn new() -> Self {
+        Self {
+            content: None,
+            modal: false,
+            backdrop: None,
+            placement: None,
+            padding: (0, 0, 0, 0),
+            close_on_escape: false,
+            close_on_backdrop: false,
+            on_dismiss: Rc::new(RefCell::new(|| {})),
+            z_index: 0,
+            dbg_name: None,
+            enter: None,
+            exit: None,
+        }
+    }
+
+    /// Dialog over a dimmed window, closed with Escape or a click outside it
+    pub fn modal() -> Self {
+        Self::new()
+            .set_modal(true)
+            .backdrop(Color::new(0, 0, 0, 120))
+            .close_on_escape(true)
+            .close_on_backdrop(true)
+    }
+
+    /// Notification in the bottom right corner that leaves the app usable
+    pub fn toast() -> Self {
+        Self::new().placement(Placement::new().right(16).bottom(16))
+    }
+
+    /// Content under the component `anchor`, closed with Escape or a click elsewhere
+    pub fn popover(anchor: &str) -> Self {
+        Self::new()
+            .placement(Placement::new().top(Offset::Percent(100.0)).anchor(anchor))
+            .close_on_escape(true)
+            .close_on_backdrop(true)
+    }
+
+    pub fn content(mut self, content: Component) -> Self {
+        self.content = Some(content);
+        self
+    }
+    pub fn set_modal(mut self, modal: bool) -> Self {
+        self.modal = modal;
+        self
+    }
+    pub fn backdrop(mut self, color: impl Into<Color>) -> Self {
+        self.backdrop = Some(color.into());
+        self
+    }
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = Some(placement);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.padding = padding;
+        self
+    }
+    pub fn close_on_escape(mut self, close: bool) -> Self {
+        self.close_on_escape = close;
+        self
+    }
+    pub fn close_on_backdrop(mut self, close: bool) -> Self {
+        self.close_on_backdrop = close;
+        self
+    }
+    pub fn on_dismiss(mut self, f: Box<dyn FnMut()>) -> Self {
+        self.on_dismiss = Rc::new(RefCell::new(f));
+        self
+    }
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        self.dbg_name = Some(name.to_string());
+        self
+    }
+    /// Plays on the backdrop layer when the overlay is first shown, needs a `dbg_name`
+    pub fn enter(mut self, animation: Animation) -> Self {
+        self.enter = Some(animation);
+        self
+    }
+    /// Plays on the backdrop layer once the overlay stops being shown, needs a `dbg_name`
+    pub fn exit(mut self, animation: Animation) -> Self {
+        self.exit = Some(animation);
+        self
+    }
+
+    /// Shows the overlay for the UI being built
+    pub fn show(self) {
+        let Some(content) = self.content else {
+            return;
+        };
+        let content: Component = match self.placement {
+            Some(placement) => Layout::get_row_builder()
+                .dim((Length::FIT, Length::FIT))
+                .set_position(Position::Abs(placement))
+                .children(vec![content])
+                .build(),
+            None => content,
+        };
+        let mut root = Layout::get_col_builder()
+            .dim((Length::FILL, Length::FILL))
+            .padding(self.padding)
+            .main_align(Alignment::Center)
+            .cross_align(Alignment::Center)
+            .children(vec![content.clone()]);
+        if let Some(color) = self.backdrop {
+            root = root.bg_color(color);
+        }
+        if let Some(name) = &self.dbg_name {
+            root = root.dbg_name(name);
+        }
+        if let Some(animation) = self.enter {
+            root = root.enter(animation);
+        }
+        if let Some(animation) = self.exit {
+            root = root.exit(animation);
+        }
+        let layer = Layer {
+            root: root.build(),
+            content,
+            modal: self.modal,
+            close_on_escape: self.close_on_escape,
+            close_on_backdrop: self.close_on_backdrop,
+            on_dismiss: self.on_dismiss,
+            z_index: self.z_index,
+        };
+        PENDING.with(|pending| pending.borrow_mut().push(layer));
+    }
+}
+
+/// A shown overlay, laid out and drawn by `UIRoot` as its own tree
+pub(crate) struct Layer {
+    pub root: Component,
+    pub content: Component,
+    pub modal: bool,
+    pub close_on_escape: bool,
+    pub close_on_backdrop: bool,
+    pub on_dismiss: Rc<RefCell<dyn FnMut()>>,
+    pub z_index: i32,
+}
+
+impl Layer {
+    /// Whether `pos` is over the content rather than the backdrop
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        let content = self.content.borrow();
+        let (x, y) = content.get_draw_pos();
+        let (w, h) = content.get_draw_dim();
+        pos.0 >= x && pos.0 <= x + w && pos.1 >= y && pos.1 <= y + h
+    }
+
+    pub fn dismiss(&self) {
+        let mut on_dismiss = self.on_dismiss.borrow_mut();
+        on_dismiss();
+    }
+}
+
+/// Overlays shown since the last call, bottom first
+pub(crate) fn take() -> Vec<Layer> {
+    let mut layers = PENDING.with(|pending| pending.take());
+    layers.sort_by_key(|layer| layer.z_index);
+    layers
+}
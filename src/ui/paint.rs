@@ -0,0 +1,401 @@
+use std::{
+    cell::{Cell, RefCell},
+    f32::consts::FRAC_PI_2,
+};
+
+use raylib::{color::Color, ffi};
+
+use crate::ui::root::to_physical;
+
+const RL_TRIANGLES: i32 = 0x0004;
+/// Rings a gradient fill is split into so colour stops interpolate smoothly
+const GRADIENT_RINGS: usize = 8;
+
+/// Background that varies across the box
+#[derive(Debug, Clone)]
+pub enum Gradient {
+    /// `angle` in degrees, 0 runs left to right and 90 top to bottom
+    Linear { angle: f32, stops: Vec<(f32, Color)> },
+    /// From the centre out to the corners
+    Radial { stops: Vec<(f32, Color)> },
+}
+
+impl Gradient {
+    pub fn linear(angle: f32, from: Color, to: Color) -> Self {
+        Gradient::Linear {
+            angle,
+            stops: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    pub fn radial(inner: Color, outer: Color) -> Self {
+        Gradient::Radial {
+            stops: vec![(0.0, inner), (1.0, outer)],
+        }
+    }
+
+    fn color_at(&self, rect: ffi::Rectangle, (x, y): (f32, f32)) -> Color {
+        let (t, stops) = match self {
+            Gradient::Linear { angle, stops } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let project = |x: f32, y: f32| x * cos + y * sin;
+                let corners = [
+                    project(rect.x, rect.y),
+                    project(rect.x + rect.width, rect.y),
+                    project(rect.x, rect.y + rect.height),
+                    project(rect.x + rect.width, rect.y + rect.height),
+                ];
+                let min = corners.iter().cloned().fold(f32::MAX, f32::min);
+                let max = corners.iter().cloned().fold(f32::MIN, f32::max);
+                ((project(x, y) - min) / (max - min).max(f32::EPSILON), stops)
+            }
+            Gradient::Radial { stops } => {
+                let (cx, cy) = (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+                let radius = (rect.width * rect.width + rect.height * rect.height).sqrt() / 2.0;
+                (((x - cx).powi(2) + (y - cy).powi(2)).sqrt() / radius.max(f32::EPSILON), stops)
+            }
+        };
+        stop_color(stops, t)
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b), channel(a.a, b.a))
+}
+
+fn stop_color(stops: &[(f32, Color)], t: f32) -> Color {
+    let Some(first) = stops.first() else {
+        return Color::new(0, 0, 0, 0);
+    };
+    if t <= first.0 {
+        return first.1;
+    }
+    for pair in stops.windows(2) {
+        let ((t0, c0), (t1, c1)) = (pair[0], pair[1]);
+        if t <= t1 {
+            return mix(c0, c1, (t - t0) / (t1 - t0).max(f32::EPSILON));
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// CSS style box shadow
+#[derive(Debug, Clone, Copy)]
+pub struct Shadow {
+    pub offset: (i32, i32),
+    pub blur: i32,
+    pub spread: i32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn new(offset: (i32, i32), blur: i32, color: Color) -> Self {
+        Self {
+            offset,
+            blur,
+            spread: 0,
+            color,
+        }
+    }
+
+    pub fn spread(mut self, spread: i32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// How far the shadow reaches past the box
+    pub fn extent(&self) -> i32 {
+        self.offset.0.abs().max(self.offset.1.abs()) + self.spread.max(0) + self.blur
+    }
+}
+
+pub enum Fill<'a> {
+    Solid(Color),
+    Gradient(&'a Gradient),
+    /// `source` rectangle of `texture` stretched over the shape
+    Texture {
+        texture: ffi::Texture2D,
+        source: ffi::Rectangle,
+        tint: Color,
+    },
+}
+
+thread_local! {
+    static OPACITY: Cell<f32> = Cell::new(1.0);
+    static CLIPS: RefCell<Vec<(i32, i32, i32, i32)>> = RefCell::new(vec![]);
+}
+
+/// Runs `f` with everything it draws faded by `opacity`, nesting multiplies
+pub fn with_opacity<R>(opacity: f32, f: impl FnOnce() -> R) -> R {
+    let previous = OPACITY.with(|current| current.replace(current.get() * opacity.clamp(0.0, 1.0)));
+    let result = f();
+    OPACITY.with(|current| current.set(previous));
+    result
+}
+
+/// `color` with the current opacity applied, for anything drawing outside this module
+pub fn faded(color: Color) -> Color {
+    let opacity = OPACITY.with(|current| current.get());
+    if opacity >= 1.0 {
+        return color;
+    }
+    Color::new(color.r, color.g, color.b, (color.a as f32 * opacity).round() as u8)
+}
+
+fn apply_scissor(rect: Option<(i32, i32, i32, i32)>) {
+    unsafe {
+        match rect {
+            Some((x, y, w, h)) => ffi::BeginScissorMode(
+                to_physical(x),
+                to_physical(y),
+                to_physical(w.max(0)),
+                to_physical(h.max(0)),
+            ),
+            None => ffi::EndScissorMode(),
+        }
+    }
+}
+
+/// Restricts drawing to `rect` (x, y, width, height) within any enclosing clip
+pub fn push_clip(rect: (i32, i32, i32, i32)) {
+    let clip = CLIPS.with(|clips| {
+        let mut clips = clips.borrow_mut();
+        let clip = match clips.last() {
+            Some(&(x, y, w, h)) => {
+                let left = rect.0.max(x);
+                let top = rect.1.max(y);
+                let right = (rect.0 + rect.2).min(x + w);
+                let bottom = (rect.1 + rect.3).min(y + h);
+                (left, top, right - left, bottom - top)
+            }
+            None => rect,
+        };
+        clips.push(clip);
+        clip
+    });
+    apply_scissor(Some(clip));
+}
+
+pub fn pop_clip() {
+    let previous = CLIPS.with(|clips| {
+        let mut clips = clips.borrow_mut();
+        clips.pop();
+        clips.last().cloned()
+    });
+    apply_scissor(previous);
+}
+
+/// Outline of a rectangle whose corners are quarter ellipses with radii (rx, ry), listed as
+/// top-left, top-right, bottom-right, bottom-left. Every outline has the same point count
+/// for a given `segments`, so an inner and outer outline pair up point by point.
+fn rounded_outline(rect: ffi::Rectangle, radii: [(f32, f32); 4], segments: usize) -> Vec<(f32, f32)> {
+    let clamp = |(rx, ry): (f32, f32)| (rx.clamp(0.0, rect.width / 2.0), ry.clamp(0.0, rect.height / 2.0));
+    let [tl, tr, br, bl] = radii.map(clamp);
+    // Walks the corners by increasing angle, starting from the right edge
+    let corners = [
+        (rect.x + rect.width - tr.0, rect.y + tr.1, tr),
+        (rect.x + tl.0, rect.y + tl.1, tl),
+        (rect.x + bl.0, rect.y + rect.height - bl.1, bl),
+        (rect.x + rect.width - br.0, rect.y + rect.height - br.1, br),
+    ];
+    let mut points = Vec::with_capacity(4 * (segments + 1));
+    for (corner, (cx, cy, (rx, ry))) in corners.iter().enumerate() {
+        for step in 0..=segments {
+            let angle = (corner as f32 + step as f32 / segments as f32) * FRAC_PI_2;
+            points.push((cx + rx * angle.cos(), cy - ry * angle.sin()));
+        }
+    }
+    points
+}
+
+fn segments_for(radius: f32) -> usize {
+    ((radius / 2.0).ceil() as usize).clamp(1, 16)
+}
+
+fn uniform(radii: [f32; 4]) -> [(f32, f32); 4] {
+    radii.map(|r| (r, r))
+}
+
+/// Emits one triangle, flipped when needed since rlgl culls clockwise ones
+fn triangle(vertices: [((f32, f32), Color, (f32, f32)); 3]) {
+    let [a, b, c] = vertices;
+    let cross = (b.0.0 - a.0.0) * (c.0.1 - a.0.1) - (b.0.1 - a.0.1) * (c.0.0 - a.0.0);
+    if cross == 0.0 {
+        return;
+    }
+    let ordered = if cross < 0.0 { [a, b, c] } else { [a, c, b] };
+    for ((x, y), color, (u, v)) in ordered {
+        let color = faded(color);
+        unsafe {
+            ffi::rlColor4ub(color.r, color.g, color.b, color.a);
+            ffi::rlTexCoord2f(u, v);
+            ffi::rlVertex2f(x, y);
+        }
+    }
+}
+
+/// Starts a triangle batch, `texture` 0 means flat colour
+fn begin_triangles(texture: u32) {
+    unsafe {
+        // rlgl keeps the previous texture for same mode batches, so set the white pixel explicitly
+        ffi::rlSetTexture(if texture == 0 { ffi::rlGetTextureIdDefault() } else { texture });
+        ffi::rlBegin(RL_TRIANGLES);
+    }
+}
+
+fn end_triangles() {
+    unsafe {
+        ffi::rlEnd();
+        ffi::rlSetTexture(0);
+    }
+}
+
+/// Fills `rect` with corner `radii` (top-left, top-right, bottom-right, bottom-left)
+pub fn fill_rounded(rect: ffi::Rectangle, radii: [f32; 4], fill: Fill) {
+    fill_elliptical(rect, uniform(radii), fill);
+}
+
+fn fill_elliptical(rect: ffi::Rectangle, radii: [(f32, f32); 4], fill: Fill) {
+    if rect.width <= 0.0 || rect.height <= 0.0 {
+        return;
+    }
+    let max_radius = radii.iter().map(|(rx, ry)| rx.max(*ry)).fold(0.0, f32::max);
+    let points = rounded_outline(rect, radii, segments_for(max_radius));
+    let center = (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+    let (texture_id, rings) = match fill {
+        Fill::Texture { texture, .. } => (texture.id, 1),
+        Fill::Gradient(_) => (0, GRADIENT_RINGS),
+        Fill::Solid(_) => (0, 1),
+    };
+    let vertex = |s: f32, (x, y): (f32, f32)| {
+        let point = (center.0 + (x - center.0) * s, center.1 + (y - center.1) * s);
+        match &fill {
+            Fill::Solid(color) => (point, *color, (0.0, 0.0)),
+            Fill::Gradient(gradient) => (point, gradient.color_at(rect, point), (0.0, 0.0)),
+            Fill::Texture { texture, source, tint } => {
+                let u = (source.x + (point.0 - rect.x) / rect.width * source.width) / texture.width as f32;
+                let v = (source.y + (point.1 - rect.y) / rect.height * source.height) / texture.height as f32;
+                (point, *tint, (u, v))
+            }
+        }
+    };
+    begin_triangles(texture_id);
+    for i in 0..points.len() {
+        let (p0, p1) = (points[i], points[(i + 1) % points.len()]);
+        for ring in 0..rings {
+            let (s0, s1) = (ring as f32 / rings as f32, (ring + 1) as f32 / rings as f32);
+            triangle([vertex(s0, p0), vertex(s1, p0), vertex(s1, p1)]);
+            if ring > 0 {
+                triangle([vertex(s0, p0), vertex(s1, p1), vertex(s0, p1)]);
+            }
+        }
+    }
+    end_triangles();
+}
+
+/// `rect` shrunk by `widths` (left, top, right, bottom). The corners shrink by the width
+/// of the sides meeting there as in CSS, so they can turn elliptical.
+fn inset(rect: ffi::Rectangle, radii: [f32; 4], widths: (i32, i32, i32, i32)) -> (ffi::Rectangle, [(f32, f32); 4]) {
+    let (left, top, right, bottom) = (widths.0 as f32, widths.1 as f32, widths.2 as f32, widths.3 as f32);
+    let inner = ffi::Rectangle {
+        x: rect.x + left,
+        y: rect.y + top,
+        width: (rect.width - left - right).max(0.0),
+        height: (rect.height - top - bottom).max(0.0),
+    };
+    let [tl, tr, br, bl] = radii;
+    let inner_radii = [
+        ((tl - left).max(0.0), (tl - top).max(0.0)),
+        ((tr - right).max(0.0), (tr - top).max(0.0)),
+        ((br - right).max(0.0), (br - bottom).max(0.0)),
+        ((bl - left).max(0.0), (bl - bottom).max(0.0)),
+    ];
+    (inner, inner_radii)
+}
+
+/// Draws the band between `rect`'s rounded edge and the edge inset by `widths`
+/// (left, top, right, bottom), each side in its own colour
+pub fn stroke_rounded(
+    rect: ffi::Rectangle,
+    radii: [f32; 4],
+    widths: (i32, i32, i32, i32),
+    colors: (Color, Color, Color, Color),
+) {
+    if widths.0 + widths.1 + widths.2 + widths.3 <= 0 {
+        return;
+    }
+    let (inner, inner_radii) = inset(rect, radii, widths);
+    let segments = segments_for(radii.iter().cloned().fold(0.0, f32::max));
+    let outer_points = rounded_outline(rect, uniform(radii), segments);
+    let inner_points = rounded_outline(inner, inner_radii, segments);
+    // The outline runs top-right, top-left, bottom-left, bottom-right. A corner's first half
+    // takes the colour of the side before it, the rest and the straight edge the side after.
+    let before = [colors.2, colors.1, colors.0, colors.3];
+    let after = [colors.1, colors.0, colors.3, colors.2];
+    begin_triangles(0);
+    for i in 0..outer_points.len() {
+        let j = (i + 1) % outer_points.len();
+        let (corner, step) = (i / (segments + 1), i % (segments + 1));
+        let color = if step * 2 + 1 < segments { before[corner] } else { after[corner] };
+        triangle([
+            (outer_points[i], color, (0.0, 0.0)),
+            (outer_points[j], color, (0.0, 0.0)),
+            (inner_points[j], color, (0.0, 0.0)),
+        ]);
+        triangle([
+            (outer_points[i], color, (0.0, 0.0)),
+            (inner_points[j], color, (0.0, 0.0)),
+            (inner_points[i], color, (0.0, 0.0)),
+        ]);
+    }
+    end_triangles();
+}
+
+/// Draws `shadow` for a box at `rect`, blur approximated by stacked translucent layers
+pub fn draw_shadow(rect: ffi::Rectangle, radii: [f32; 4], shadow: &Shadow) {
+    let layers = (shadow.blur / 2).clamp(0, 8);
+    // Layer alphas multiply out to the shadow's alpha where they all overlap
+    let alpha = shadow.color.a as f32 / 255.0;
+    let layer_alpha = 1.0 - (1.0 - alpha).powf(1.0 / (layers + 1) as f32);
+    let color = Color::new(
+        shadow.color.r,
+        shadow.color.g,
+        shadow.color.b,
+        (layer_alpha * 255.0).round() as u8,
+    );
+    for layer in 0..=layers {
+        let blur = shadow.blur as f32;
+        let expand = if layers == 0 {
+            shadow.spread as f32
+        } else {
+            shadow.spread as f32 + blur / 2.0 - blur * layer as f32 / layers as f32
+        };
+        let layer_rect = ffi::Rectangle {
+            x: rect.x + shadow.offset.0 as f32 - expand,
+            y: rect.y + shadow.offset.1 as f32 - expand,
+            width: rect.width + expand * 2.0,
+            height: rect.height + expand * 2.0,
+        };
+        fill_rounded(layer_rect, radii.map(|r| (r + expand).max(0.0)), Fill::Solid(color));
+    }
+}
+
+/// Paints a box in CSS order: shadows, the background inside the border, then the border
+pub fn paint_box(
+    rect: ffi::Rectangle,
+    radii: [f32; 4],
+    shadows: &[Shadow],
+    background: Fill,
+    border_widths: (i32, i32, i32, i32),
+    border_colors: (Color, Color, Color, Color),
+) {
+    for shadow in shadows.iter() {
+        draw_shadow(rect, radii, shadow);
+    }
+    let (inner, inner_radii) = inset(rect, radii, border_widths);
+    if !matches!(background, Fill::Solid(color) if color.a == 0) {
+        fill_elliptical(inner, inner_radii, background);
+    }
+    stroke_rounded(rect, radii, border_widths, border_colors);
+}
@@ -0,0 +1,173 @@
+/// Which parts of overlapping contours count as inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+/// Vertical samples per pixel row, horizontal coverage is exact
+const SUBSAMPLES: usize = 4;
+
+/// An outline flattened to straight edges, filled into an anti-aliased coverage mask
+#[derive(Debug, Clone, Default)]
+pub struct PathBuilder {
+    edges: Vec<Edge>,
+    start: (f32, f32),
+    current: (f32, f32),
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = (x, y);
+        self.current = (x, y);
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        if (x0, y0) != (x, y) {
+            self.edges.push(Edge { x0, y0, x1: x, y1: y });
+        }
+        self.current = (x, y);
+    }
+
+    pub fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        let steps = curve_steps(&[(x0, y0), (x1, y1), (x, y)]);
+        for i in 1..=steps {
+            let t = i as f32 / steps as f32;
+            let mt = 1.0 - t;
+            self.line_to(
+                mt * mt * x0 + 2.0 * mt * t * x1 + t * t * x,
+                mt * mt * y0 + 2.0 * mt * t * y1 + t * t * y,
+            );
+        }
+    }
+
+    pub fn cubic_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        let steps = curve_steps(&[(x0, y0), (x1, y1), (x2, y2), (x, y)]);
+        for i in 1..=steps {
+            let t = i as f32 / steps as f32;
+            let mt = 1.0 - t;
+            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+            self.line_to(
+                a * x0 + b * x1 + c * x2 + d * x,
+                a * y0 + b * y1 + c * y2 + d * y,
+            );
+        }
+    }
+
+    pub fn close(&mut self) {
+        let (x, y) = self.start;
+        self.line_to(x, y);
+    }
+
+    pub fn current(&self) -> (f32, f32) {
+        self.current
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// (min_x, min_y, max_x, max_y) of every edge
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let first = self.edges.first()?;
+        let mut bounds = (first.x0, first.y0, first.x0, first.y0);
+        for edge in self.edges.iter() {
+            for (x, y) in [(edge.x0, edge.y0), (edge.x1, edge.y1)] {
+                bounds.0 = bounds.0.min(x);
+                bounds.1 = bounds.1.min(y);
+                bounds.2 = bounds.2.max(x);
+                bounds.3 = bounds.3.max(y);
+            }
+        }
+        Some(bounds)
+    }
+
+    pub fn transform(&mut self, f: impl Fn(f32, f32) -> (f32, f32)) {
+        for edge in self.edges.iter_mut() {
+            (edge.x0, edge.y0) = f(edge.x0, edge.y0);
+            (edge.x1, edge.y1) = f(edge.x1, edge.y1);
+        }
+        self.start = f(self.start.0, self.start.1);
+        self.current = f(self.current.0, self.current.1);
+    }
+
+    /// Coverage (0-255) of each pixel in a `width` x `height` mask, row by row
+    pub fn fill(&self, width: usize, height: usize, rule: FillRule) -> Vec<u8> {
+        let mut coverage = vec![0.0f32; width * height];
+        let mut crossings: Vec<(f32, i32)> = vec![];
+        let weight = 1.0 / SUBSAMPLES as f32;
+        for row in 0..height {
+            let acc = &mut coverage[row * width..(row + 1) * width];
+            for sample in 0..SUBSAMPLES {
+                let y = row as f32 + (sample as f32 + 0.5) * weight;
+                crossings.clear();
+                for edge in self.edges.iter() {
+                    let (top, bottom) = (edge.y0.min(edge.y1), edge.y0.max(edge.y1));
+                    if y < top || y >= bottom {
+                        continue;
+                    }
+                    let t = (y - edge.y0) / (edge.y1 - edge.y0);
+                    let winding = if edge.y1 > edge.y0 { 1 } else { -1 };
+                    crossings.push((edge.x0 + t * (edge.x1 - edge.x0), winding));
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                for idx in 0..crossings.len().saturating_sub(1) {
+                    winding += crossings[idx].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(acc, crossings[idx].0, crossings[idx + 1].0, weight);
+                    }
+                }
+            }
+        }
+        coverage
+            .iter()
+            .map(|c| (c.min(1.0) * 255.0).round() as u8)
+            .collect()
+    }
+}
+
+fn curve_steps(points: &[(f32, f32)]) -> usize {
+    let length = points
+        .windows(2)
+        .map(|pair| ((pair[1].0 - pair[0].0).powi(2) + (pair[1].1 - pair[0].1).powi(2)).sqrt())
+        .sum::<f32>();
+    ((length / 3.0).ceil() as usize).clamp(2, 32)
+}
+
+/// Adds the horizontal overlap of `[x0, x1)` with each pixel of the row
+fn add_span(acc: &mut [f32], x0: f32, x1: f32, weight: f32) {
+    let x0 = x0.max(0.0);
+    let x1 = x1.min(acc.len() as f32);
+    if x1 <= x0 {
+        return;
+    }
+    let first = x0.floor() as usize;
+    let last = (x1.ceil() as usize).min(acc.len());
+    for px in first..last {
+        let overlap = x1.min(px as f32 + 1.0) - x0.max(px as f32);
+        if overlap > 0.0 {
+            acc[px] += overlap * weight;
+        }
+    }
+}
@@ -1,14 +1,18 @@
-use crate::ui::common::*;
+use crate::ui::{common::*, font::{self, FontSpec}, paint};
 use raylib::prelude::*;
-use std::{cell::RefCell, collections::HashMap, ffi::CString, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 pub struct RawText {
     pub content: String,
+    pub font: FontSpec,
     pub font_size: i32,
     pub pos: (i32, i32),
     pub dbg_name: ID,
     pub padding: (i32, i32, i32, i32), // top, right, bottom, left
     pub color: Color,
+    pub background: Option<Color>,
+    pub underline: bool,
+    pub letter_spacing: i32,
     pub overflowed: bool,
 }
 
@@ -23,13 +27,29 @@ impl Base for RawText {
         if self.overflowed {
             return vec![]
         }
-        draw_handle.draw_text(
+        let (width, height) = self.get_draw_dim();
+        if let Some(background) = self.background {
+            draw_handle.draw_rectangle(self.pos.0, self.pos.1, width, height, paint::faded(background));
+        }
+        font::draw_text_spaced(
+            draw_handle,
+            &self.font,
             &self.content,
-            self.pos.0,
-            self.pos.1,
+            self.pos,
             self.font_size,
+            self.letter_spacing,
             self.color,
         );
+        if self.underline {
+            let thickness = (self.font_size / 14).max(1);
+            draw_handle.draw_rectangle(
+                self.pos.0,
+                self.pos.1 + self.font_size - thickness,
+                width - self.padding.0 - self.padding.2,
+                thickness,
+                paint::faded(self.color),
+            );
+        }
         vec![]
     }
     fn get_paddings(&self) -> (i32,i32,i32,i32) {
@@ -48,11 +68,7 @@ impl Base for RawText {
         }
     }
     fn get_draw_dim(&self) -> (i32, i32) {
-        let width;
-        unsafe {
-            let c_text = CString::new(self.content.as_str()).unwrap();
-            width = raylib::ffi::MeasureText(c_text.as_ptr(), self.font_size);
-        }
+        let width = font::measure_text_spaced(&self.font, &self.content, self.font_size, self.letter_spacing);
         (
             width
             + self.padding.0 + self.padding.2,
@@ -124,17 +140,24 @@ impl Base for RawText {
     fn get_position(&self) -> Position {
         Position::Auto
     }
+    fn get_baseline(&self) -> Option<i32> {
+        Some(font::ascent(&self.font, self.font_size))
+    }
 }
 
 impl RawText {
-    pub fn new(content: &str, font_size: i32, padding: (i32, i32, i32, i32),color: Color) -> Rc<RefCell<Self>> {
+    pub fn new(content: &str, font: &FontSpec, font_size: i32, padding: (i32, i32, i32, i32),color: Color) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
             content: content.to_string(),
+            font: font.clone(),
             font_size,
             pos: (0, 0),
             padding,
             dbg_name: ID::Auto(generate_id()),
             color,
+            background: None,
+            underline: false,
+            letter_spacing: 0,
             overflowed: false
         }))
     }
@@ -1,32 +1,126 @@
 use raylib::{
+    RaylibHandle,
     color::Color,
     ffi::{KeyboardKey, MouseButton},
     prelude::{RaylibDraw, RaylibDrawHandle},
 };
 use crate::{
-    ui::{common::*},
+    ui::{animation, common::*, image, overlay::{self, Layer}, style::TextStyle, theme, tooltip, window::WindowConfig},
 };
-use std::{cell::RefCell, collections::HashMap, rc::Rc, vec};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    vec,
+};
+
+thread_local! {
+    static WINDOW_SIZE: Cell<(i32, i32)> = Cell::new((0, 0));
+    static RESIZE_HANDLERS: RefCell<Vec<Box<dyn FnMut(ResizeEvent)>>> = RefCell::new(vec![]);
+    static SCALE_FACTOR: Cell<f32> = Cell::new(1.0);
+    static SCALE_OVERRIDE: Cell<Option<f32>> = Cell::new(None);
+    static FOCUSED_ID: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Current window size in logical pixels, valid while the builder runs.
+pub fn window_size() -> (i32, i32) {
+    WINDOW_SIZE.with(|size| size.get())
+}
+
+/// Physical pixels per logical pixel.
+pub fn scale_factor() -> f32 {
+    SCALE_FACTOR.with(|scale| scale.get())
+}
+
+/// Overrides the detected scale factor, `None` goes back to the monitor's DPI.
+/// Takes effect on the next frame.
+pub fn set_scale_factor(scale: Option<f32>) {
+    SCALE_OVERRIDE.with(|scale_override| scale_override.set(scale.map(|s| s.max(0.1))));
+}
+
+/// Converts a logical length to physical pixels.
+pub fn to_physical(value: i32) -> i32 {
+    (value as f32 * scale_factor()).round() as i32
+}
+
+/// Id of the component holding keyboard focus.
+pub fn focused_id() -> Option<String> {
+    FOCUSED_ID.with(|focused| focused.borrow().clone())
+}
+
+/// Breakpoint for the current window width.
+pub fn breakpoint() -> Breakpoint {
+    Breakpoint::from_width(window_size().0)
+}
 
 pub struct UIRoot {}
 impl UIRoot {
+    /// Registers a callback fired whenever the window is resized, before the UI is rebuilt.
+    pub fn on_resize(f: Box<dyn FnMut(ResizeEvent)>) {
+        RESIZE_HANDLERS.with(|handlers| handlers.borrow_mut().push(f));
+    }
+
     pub fn start(builder: Box<dyn Fn() -> Component>, dim: (i32, i32), title: &str) {
-        let (mut rl, thread) = raylib::init()
-            .height(dim.1)
-            .width(dim.0)
-            .title(title)
-            .build();
+        UIRoot::start_with_config(builder, WindowConfig::new().dim(dim).title(title));
+    }
 
-        rl.set_target_fps(60);
+    pub fn start_with_config(builder: Box<dyn Fn() -> Component>, config: WindowConfig) {
+        let (mut rl, thread) = config.open();
+        if config.scale_factor.is_some() {
+            set_scale_factor(config.scale_factor);
+        }
+        if let Some(on_resize) = config.on_resize.clone() {
+            UIRoot::on_resize(Box::new(move |resize_event| {
+                let mut f = on_resize.borrow_mut();
+                f(resize_event)
+            }));
+        }
+
+        let mut scale = UIRoot::detect_scale(&rl, &config);
+        SCALE_FACTOR.with(|scale_factor| scale_factor.set(scale));
+        let mut dim = UIRoot::logical_screen_dim(&rl, scale);
+        WINDOW_SIZE.with(|size| size.set(dim));
 
         let mut should_rebuild_ui = true;
         let mut scroll_map: HashMap<String, i32> = HashMap::new();
         let mut main_child = builder();
+        let mut layers = overlay::take();
         let mut focused_id = None;
 
-        while !rl.window_should_close() {
+        loop {
+            if rl.window_should_close() {
+                if config.allows_close() {
+                    break;
+                }
+                // The veto usually changes app state, e.g. to show a confirmation
+                should_rebuild_ui = true;
+            }
+            let detected_scale = UIRoot::detect_scale(&rl, &config);
+            if detected_scale != scale {
+                scale = detected_scale;
+                SCALE_FACTOR.with(|scale_factor| scale_factor.set(scale));
+                should_rebuild_ui = true;
+            }
+            let screen_dim = UIRoot::logical_screen_dim(&rl, scale);
+            if rl.is_window_resized() || screen_dim != dim {
+                let resize_event = ResizeEvent {
+                    dim: screen_dim,
+                    prev_dim: dim,
+                };
+                dim = screen_dim;
+                WINDOW_SIZE.with(|size| size.set(dim));
+                UIRoot::dispatch_resize(resize_event);
+                should_rebuild_ui = true;
+            }
+
+            // Everything below works in logical pixels
             let mouse_pos = rl.get_mouse_position();
+            let mouse_pos = (
+                (mouse_pos.x / scale) as i32,
+                (mouse_pos.y / scale) as i32,
+            );
             let left_mouse_pressed = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+            let right_mouse_pressed = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT);
 
             let key = rl.get_key_pressed();
             let shift_down = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
@@ -36,16 +130,18 @@ impl UIRoot {
                 || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
 
             let mouse_event = MouseEvent {
-                pos: (mouse_pos.x as i32, mouse_pos.y as i32),
+                pos: mouse_pos,
                 left_button_down: left_mouse_pressed,
+                right_button_down: right_mouse_pressed,
             };
 
+            let time = rl.get_time();
             let mut d = rl.begin_drawing(&thread);
             let wheel_move = d.get_mouse_wheel_move_v();
             let scroll_y = wheel_move.y;
 
             let scroll_event = ScrollEvent {
-                pos: (mouse_pos.x as i32, mouse_pos.y as i32),
+                pos: mouse_pos,
                 delta: scroll_y as i32,
             };
 
@@ -56,78 +152,278 @@ impl UIRoot {
             };
 
             {
-                let a = UIRoot::get_mouse_event_handlers(main_child.clone(), &mut focused_id, mouse_event);
+                let a = UIRoot::handle_mouse_event(main_child.clone(), &layers, &mut focused_id, mouse_event);
+                FOCUSED_ID.with(|focused| *focused.borrow_mut() = focused_id.clone());
 
                 let b = if key_event.ctrl_down || key_event.shift_down || key_event.key.is_some() {
-                    UIRoot::handle_key_event(main_child.clone(),focused_id.clone(),key_event)
+                    UIRoot::handle_key_event(main_child.clone(), &layers, focused_id.clone(), key_event)
                 } else {
                     false
                 };
 
-                let c = UIRoot::get_scroll_event_handler(main_child.clone(), &mut scroll_map, scroll_event);
-                if a || b || c {
+                let c = UIRoot::handle_scroll_event(main_child.clone(), &layers, &mut scroll_map, scroll_event);
+                // Finished image loads replace their placeholders
+                let d = image::poll_loads();
+                // Components resolve their theme tokens while building
+                let e = theme::take_changed();
+                // Transitions and animations advance with every rebuild
+                let f = animation::needs_frame();
+                // Tooltips come up after the pointer rests and go when it leaves or clicks
+                let mut hover_roots = UIRoot::active_roots(&main_child, &layers);
+                hover_roots.reverse();
+                let hovered = tooltip::hovered(&hover_roots, mouse_pos);
+                let g = tooltip::update(hovered, mouse_pos, left_mouse_pressed, time);
+                if a || b || c || d || e || f || g {
                     should_rebuild_ui = true;
                 }
             }
             if should_rebuild_ui {
+                animation::begin_frame(time);
+                let previous_roots = UIRoot::roots(&main_child, &layers);
                 main_child = builder();
-                UIRoot::measure_dimensions(main_child.clone(), dim);
-                UIRoot::measure_positions(main_child.clone());
-                UIRoot::measure_overflows(main_child.clone(), dim, &mut scroll_map);
-                UIRoot::draw(&mut d, main_child.clone());
+                layers = overlay::take();
+                // Overlays are laid out against the window like the root, each on its own.
+                // Auto ids carry on from one root to the next so they stay unique.
+                let roots = UIRoot::roots(&main_child, &layers);
+                let mut next_id = 0;
+                for root in roots.iter() {
+                    next_id = UIRoot::measure_dimensions(root.clone(), dim, next_id);
+                    UIRoot::measure_positions(root.clone());
+                    UIRoot::measure_overflows(root.clone(), dim, &mut scroll_map);
+                }
+                for root in roots.iter() {
+                    UIRoot::place_anchored(root.clone(), &roots, dim, &mut scroll_map);
+                }
+                animation::end_frame(&previous_roots);
+                let mut drawn = roots;
+                drawn.extend(UIRoot::tooltip_bubble(&drawn, dim, next_id, &mut scroll_map));
+                let clear_color = config
+                    .clear_color
+                    .unwrap_or_else(|| theme::current().palette.background);
+                UIRoot::draw(&mut d, &drawn, clear_color, scale);
                 should_rebuild_ui = false;
             }
         }
     }
 
-    fn draw(draw_handle: &mut RaylibDrawHandle, root_child: Component) {
-        draw_handle.clear_background(Color::BLACK);
+    fn detect_scale(rl: &RaylibHandle, config: &WindowConfig) -> f32 {
+        if let Some(scale) = SCALE_OVERRIDE.with(|scale_override| scale_override.get()) {
+            return scale;
+        }
+        if config.high_dpi {
+            // raylib already scales drawing and input for high-DPI windows
+            return 1.0;
+        }
+        let dpi = rl.get_window_scale_dpi();
+        if dpi.x > 0.0 { dpi.x } else { 1.0 }
+    }
 
-        let child = root_child.borrow();
-        let mut abs_draw = { child.draw(draw_handle) };
+    fn logical_screen_dim(rl: &RaylibHandle, scale: f32) -> (i32, i32) {
+        (
+            (rl.get_screen_width() as f32 / scale) as i32,
+            (rl.get_screen_height() as f32 / scale) as i32,
+        )
+    }
 
-        loop {
-            let mut new_abs_draws = vec![];
-            for draw_instruction in abs_draw.iter() {
-                let AbsoluteDraw { component_id } = draw_instruction;
-                // let child = root.get_by_id(component_id);
-                let child = UIRoot::get_by_id(root_child.clone(),component_id);
-                if let Some(child) = child {
-                    let child = child.borrow();
-                    let child_pos = child.get_position();
-                    match child_pos {
-                        Position::Auto => {
-                            panic!("No auto children should exist here")
-                        }
-                        Position::Sticky(_, _) => {
-                            let more_abs_draw = child.draw(draw_handle);
-                            new_abs_draws.extend(more_abs_draw);
-                        }
-                        Position::Abs(_, _) => {
-                            let more_abs_draw = child.draw(draw_handle);
-                            new_abs_draws.extend(more_abs_draw);
-                        }
-                    }
+    fn dispatch_resize(resize_event: ResizeEvent) {
+        // Taken out while they run so a handler can register another one
+        let mut handlers = RESIZE_HANDLERS.with(|handlers| handlers.take());
+        for handler in handlers.iter_mut() {
+            handler(resize_event);
+        }
+        RESIZE_HANDLERS.with(|registered| {
+            let mut registered = registered.borrow_mut();
+            handlers.append(&mut registered);
+            *registered = handlers;
+        });
+    }
+
+    /// The app's tree followed by the overlays' from bottom to top
+    fn roots(main_child: &Component, layers: &[Layer]) -> Vec<Component> {
+        let mut roots = vec![main_child.clone()];
+        roots.extend(layers.iter().map(|layer| layer.root.clone()));
+        roots
+    }
+
+    /// Roots that take input, a modal overlay shuts out everything below it
+    fn active_roots(main_child: &Component, layers: &[Layer]) -> Vec<Component> {
+        let mut roots = vec![main_child.clone()];
+        for layer in layers {
+            if layer.modal {
+                roots.clear();
+            }
+            roots.push(layer.root.clone());
+        }
+        roots
+    }
+
+    fn draw(
+        draw_handle: &mut RaylibDrawHandle,
+        roots: &[Component],
+        clear_color: Color,
+        scale: f32,
+    ) {
+        draw_handle.clear_background(clear_color);
+        // Components draw in logical pixels, the camera zoom maps them to the screen
+        unsafe {
+            raylib::ffi::BeginMode2D(raylib::ffi::Camera2D {
+                offset: raylib::ffi::Vector2 { x: 0.0, y: 0.0 },
+                target: raylib::ffi::Vector2 { x: 0.0, y: 0.0 },
+                rotation: 0.0,
+                zoom: scale,
+            });
+        }
+
+        for root in roots {
+            UIRoot::draw_tree(draw_handle, root.clone());
+        }
+        // Removed layouts finish their exit animation over the new tree
+        animation::draw_ghosts(|ghost| UIRoot::draw_tree(draw_handle, ghost.clone()));
+        unsafe {
+            raylib::ffi::EndMode2D();
+        }
+    }
+
+    /// Draws `root_child`, then the out of flow and z-indexed components inside it on top
+    fn draw_tree(draw_handle: &mut RaylibDrawHandle, root_child: Component) {
+        let layers = UIRoot::layers(&root_child);
+        let below = UIRoot::below_root(&layers);
+        for layer in layers[..below].iter() {
+            layer.borrow().draw(draw_handle);
+        }
+        root_child.borrow().draw(draw_handle);
+        for layer in layers[below..].iter() {
+            layer.borrow().draw(draw_handle);
+        }
+    }
+
+    /// Components their parents skip when drawing, in draw order. Equal `z_index`es keep
+    /// tree order.
+    fn layers(root_child: &Component) -> Vec<Component> {
+        fn collect(component: &Component, layers: &mut Vec<Component>) {
+            for child in component.borrow().get_children() {
+                if draws_later(&*child.borrow()) {
+                    layers.push(child.clone());
                 }
+                collect(&child, layers);
             }
-            if new_abs_draws.is_empty() {
-                break;
-            } else {
-                abs_draw = new_abs_draws;
+        }
+        let mut layers = vec![];
+        collect(root_child, &mut layers);
+        layers.sort_by_key(|layer| layer.borrow().get_z_index());
+        layers
+    }
+
+    /// Number of `layers` with a negative `z_index`, drawn before the root
+    fn below_root(layers: &[Component]) -> usize {
+        layers.partition_point(|layer| layer.borrow().get_z_index() < 0)
+    }
+
+    /// Draw order rank of every component's layer, counting the root's own
+    fn layer_ranks(root_child: &Component) -> HashMap<String, usize> {
+        fn mark(component: &Component, rank: usize, ranks: &mut HashMap<String, usize>) {
+            let component = component.borrow();
+            ranks.insert(component.get_id(), rank);
+            for child in component.get_children() {
+                if !draws_later(&*child.borrow()) {
+                    mark(&child, rank, ranks);
+                }
             }
         }
+        let layers = UIRoot::layers(root_child);
+        let below = UIRoot::below_root(&layers);
+        let mut ranks = HashMap::new();
+        mark(root_child, below, &mut ranks);
+        for (index, layer) in layers.iter().enumerate() {
+            let rank = if index < below { index } else { index + 1 };
+            mark(layer, rank, &mut ranks);
+        }
+        ranks
+    }
+
+    /// Places components anchored to another one, after overflows so the anchor's on screen
+    /// position is known. Anchors are looked up in all `roots`, a missing one places against
+    /// the window.
+    fn place_anchored(
+        root_child: Component,
+        roots: &[Component],
+        dim: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+    ) {
+        for layer in UIRoot::layers(&root_child) {
+            let position = layer.borrow().get_position();
+            let Some(placement) = position.placement() else {
+                continue;
+            };
+            let Some(anchor_id) = &placement.anchor else {
+                continue;
+            };
+            let anchor = roots.iter().find_map(|root| UIRoot::get_by_id(root.clone(), anchor_id));
+            let reference = match anchor {
+                Some(anchor) => {
+                    let anchor = anchor.borrow();
+                    let (x, y) = anchor.get_draw_pos();
+                    let (w, h) = anchor.get_draw_dim();
+                    (x, y, w, h)
+                }
+                None => (0, 0, dim.0, dim.1),
+            };
+            let mut layer = layer.borrow_mut();
+            let pos = placement.resolve(reference, layer.get_draw_dim());
+            layer.measure_positions(pos);
+            layer.measure_overflows(dim, (0, 0), scroll_map, 0);
+        }
+    }
+
+    /// The bubble of the tooltip being shown, laid out next to the pointer. It's drawn over
+    /// everything and takes no input.
+    fn tooltip_bubble(
+        roots: &[Component],
+        dim: (i32, i32),
+        next_id: usize,
+        scroll_map: &mut HashMap<String, i32>,
+    ) -> Option<Component> {
+        let (id, pointer) = tooltip::shown()?;
+        let owner = roots.iter().find_map(|root| UIRoot::get_by_id(root.clone(), &id))?;
+        let tooltip = owner.borrow().get_tooltip()?;
+        let bubble = tooltip::bubble(tooltip);
+        {
+            let mut bubble = bubble.borrow_mut();
+            bubble.inherit_text_style(&TextStyle::default());
+            bubble.measure_dimensions(dim, next_id);
+            let pos = tooltip::place(pointer, bubble.get_draw_dim(), dim);
+            bubble.measure_positions(pos);
+            bubble.measure_overflows(dim, (0, 0), scroll_map, 0);
+        }
+        Some(bubble)
     }
 
     fn handle_key_event(
         root_child: Component,
+        layers: &[Layer],
         root_focused_id: Option<String>,
         key_event: KeyEvent,
     ) -> bool {
         if key_event.ctrl_down && key_event.key.is_some_and(|v| v == KeyboardKey::KEY_D) {
             UIRoot::debug_dims(root_child.clone());
         }
+        if key_event.key == Some(KeyboardKey::KEY_ESCAPE) {
+            for layer in layers.iter().rev() {
+                if layer.close_on_escape {
+                    layer.dismiss();
+                    return true;
+                }
+                if layer.modal {
+                    break;
+                }
+            }
+        }
+        // Focus left below a modal overlay gets its keys back once the overlay closes
         if let Some(focused_id) = root_focused_id {
-            if let Some(focused_child) = UIRoot::get_by_id(root_child.clone(), &focused_id) {
+            let focused_child = UIRoot::active_roots(&root_child, layers)
+                .into_iter()
+                .find_map(|root| UIRoot::get_by_id(root, &focused_id));
+            if let Some(focused_child) = focused_child {
                 let focused_child = focused_child.borrow();
                 focused_child.execute_on_key(key_event);
                 return true;
@@ -135,13 +431,40 @@ impl UIRoot {
         }
         false
     }
+    /// Gives the event to the topmost overlay under the mouse, or the app if none of them
+    /// block it
+    fn handle_mouse_event(
+        root_child: Component,
+        layers: &[Layer],
+        root_focused_id: &mut Option<String>,
+        mouse_event: MouseEvent,
+    ) -> bool {
+        for layer in layers.iter().rev() {
+            if layer.contains(mouse_event.pos) {
+                return UIRoot::get_mouse_event_handlers(layer.root.clone(), root_focused_id, mouse_event);
+            }
+            let pressed = mouse_event.left_button_down || mouse_event.right_button_down;
+            let dismissed = pressed && layer.close_on_backdrop;
+            if dismissed {
+                layer.dismiss();
+            }
+            if layer.modal {
+                return dismissed;
+            }
+        }
+        UIRoot::get_mouse_event_handlers(root_child, root_focused_id, mouse_event)
+    }
     fn get_mouse_event_handlers(
         root_child: Component,
         root_focused_id: &mut Option<String>,
         mouse_event: MouseEvent,
     ) -> bool {
         let child = root_child.clone();
-        let hit_children = child.borrow().get_mouse_event_handlers(mouse_event);
+        let mut hit_children = child.borrow().get_mouse_event_handlers(mouse_event);
+        // Whatever is drawn on top gets the click first
+        let ranks = UIRoot::layer_ranks(&root_child);
+        let root_rank = ranks.get(&root_child.borrow().get_id()).copied().unwrap_or(0);
+        hit_children.sort_by_key(|id| std::cmp::Reverse(ranks.get(id).copied().unwrap_or(root_rank)));
 
         let mut focused_id = None;
 
@@ -163,7 +486,25 @@ impl UIRoot {
             *root_focused_id = focused_id;
             return true;
         }
-        return false;
+        // Right clicks leave focus alone but may open a menu
+        return mouse_event.right_button_down;
+    }
+
+    fn handle_scroll_event(
+        root_child: Component,
+        layers: &[Layer],
+        scroll_map: &mut HashMap<String, i32>,
+        scroll_event: ScrollEvent,
+    ) -> bool {
+        for layer in layers.iter().rev() {
+            if layer.contains(scroll_event.pos) {
+                return UIRoot::get_scroll_event_handler(layer.root.clone(), scroll_map, scroll_event);
+            }
+            if layer.modal {
+                return false;
+            }
+        }
+        UIRoot::get_scroll_event_handler(root_child, scroll_map, scroll_event)
     }
 
     fn get_scroll_event_handler(root_child: Component, scroll_map: &mut HashMap<String, i32>,scroll_event: ScrollEvent) -> bool {
@@ -180,10 +521,12 @@ impl UIRoot {
         false
     }
 
-    fn measure_dimensions(root_child: Component, dim: (i32, i32)) {
+    /// Measures a root with auto ids from `next_id` on, returns the id after the last one
+    fn measure_dimensions(root_child: Component, dim: (i32, i32), next_id: usize) -> usize {
         let mut mut_child = root_child.borrow_mut();
+        mut_child.inherit_text_style(&TextStyle::default());
         mut_child.set_raw_dim(dim);
-        mut_child.measure_dimensions(dim, 0);
+        mut_child.measure_dimensions(dim, next_id)
     }
     fn measure_positions(root_child: Component) {
         root_child.borrow_mut().measure_positions((0, 0));
@@ -0,0 +1,435 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use colored::Colorize;
+use raylib::{
+    color::Color,
+    ffi::{self, KeyboardKey},
+    prelude::RaylibDrawHandle,
+};
+
+use crate::ui::{
+    common::{
+        AbsoluteDraw, Alignment, Base, Component, KeyEvent, Length, MouseEvent, Position,
+        keyboard_key_to_char, tabbed_print,
+    },
+    component_state,
+    icon::{Icon, icons},
+    layout::{Layout, LayoutProps},
+    overlay::OverlayProps,
+    root::focused_id,
+    style::Style,
+    text_layout::TextLayout,
+    theme::{ColorToken, Radius, Space},
+    tooltip::Tooltip,
+};
+
+/// Seconds between typed letters that still add to the same type-ahead search
+const TYPE_AHEAD_RESET: f64 = 1.0;
+
+/// Whether a select's list is open and where its keyboard highlight is
+#[derive(Debug, Clone, Default)]
+struct SelectState {
+    open: bool,
+    highlighted: Option<usize>,
+    typed: String,
+    typed_at: f64,
+    // Last measured width, the list is made as wide
+    width: i32,
+}
+
+fn with_state<R>(id: &str, f: impl FnOnce(&mut SelectState) -> R) -> R {
+    component_state::with_state(id, f)
+}
+
+pub struct SelectProps {
+    pub layout: LayoutProps,
+    pub options: Vec<String>,
+    pub selected: Option<usize>,
+    pub placeholder: String,
+    pub on_change: Rc<RefCell<dyn FnMut(usize)>>,
+    id: String,
+}
+
+impl SelectProps {
+    /// `id` keys the open list across rebuilds and anchors it, it must be unique and stable
+    pub fn new(id: &str) -> Self {
+        let layout = Layout::get_row_builder()
+            .dbg_name(id)
+            .dim((Length::FILL, Length::FIT))
+            .bg_color(ColorToken::Surface)
+            .border_width(1)
+            .border_color(ColorToken::Border)
+            .border_radius(Radius::Sm)
+            .padding(Space::xy(Space::Md, Space::Sm))
+            .gap(Space::Sm)
+            .cross_align(Alignment::Center);
+        Self {
+            layout,
+            options: vec![],
+            selected: None,
+            placeholder: String::new(),
+            on_change: Rc::new(RefCell::new(|_index| {})),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn options(mut self, options: &[&str]) -> Self {
+        self.options = options.iter().map(|option| option.to_string()).collect();
+        self
+    }
+    pub fn option(mut self, option: &str) -> Self {
+        self.options.push(option.to_string());
+        self
+    }
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+    /// Called with the index of the option picked
+    pub fn on_change(mut self, f: Box<dyn FnMut(usize)>) -> Self {
+        self.on_change = Rc::new(RefCell::new(f));
+        self
+    }
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        self.layout = self.layout.dim(dim);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.layout = self.layout.padding(padding);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.layout = self.layout.bg_color(color);
+        self
+    }
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.layout = self.layout.flex(flex);
+        self
+    }
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.layout = self.layout.font_size(size);
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout = self.layout.tooltip(tooltip);
+        self
+    }
+    pub fn style(mut self, style: &Style) -> Self {
+        self.layout = self.layout.style(style);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Select>> {
+        let state = with_state(&self.id, |state| state.clone());
+        let current = self.selected.and_then(|index| self.options.get(index));
+        let mut label = TextLayout::get_builder()
+            .content(current.unwrap_or(&self.placeholder))
+            .wrap(false)
+            .max_lines(1)
+            .dim((Length::FILL, Length::FIT))
+            .padding((0, 0, 0, 0));
+        if current.is_none() {
+            label = label.text_color(ColorToken::TextMuted);
+        }
+        let chevron = Icon::get_builder()
+            .svg(if state.open { icons::CHEVRON_UP } else { icons::CHEVRON_DOWN })
+            .size(16)
+            .color(ColorToken::TextMuted)
+            .build();
+        let mut layout = self.layout.children(vec![label.build(), chevron]);
+        if focused_id().as_deref() == Some(self.id.as_str()) {
+            layout = layout.border_color(ColorToken::Primary);
+        }
+        let select = Select {
+            layout: layout.get_layout(),
+            options: self.options,
+            selected: self.selected,
+            open: state.open,
+            on_change: self.on_change,
+        };
+        if state.open {
+            select.show_list(&state);
+        }
+        Rc::new(RefCell::new(select))
+    }
+}
+
+/// Field showing the selected option, clicking it or pressing Enter opens the list of options
+pub struct Select {
+    pub layout: Layout,
+    pub options: Vec<String>,
+    pub selected: Option<usize>,
+    // Whether the list was open when this was built
+    open: bool,
+    pub on_change: Rc<RefCell<dyn FnMut(usize)>>,
+}
+
+impl Select {
+    pub fn get_builder(id: &str) -> SelectProps {
+        SelectProps::new(id)
+    }
+
+    /// Picks option `index` and closes the list
+    fn choose(id: &str, index: usize, on_change: &Rc<RefCell<dyn FnMut(usize)>>) {
+        with_state(id, |state| {
+            state.open = false;
+            state.highlighted = Some(index);
+        });
+        let mut on_change = on_change.borrow_mut();
+        on_change(index);
+    }
+
+    fn show_list(&self, state: &SelectState) {
+        let id = self.get_id();
+        let rows = self
+            .options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| {
+                let id = id.clone();
+                let on_change = self.on_change.clone();
+                let mut row = TextLayout::get_builder()
+                    .content(option)
+                    .wrap(false)
+                    .dim((Length::FIT, Length::FIT))
+                    .padding(Space::xy(Space::Md, Space::Sm))
+                    .bg_color(if state.highlighted == Some(index) {
+                        ColorToken::Selection
+                    } else {
+                        ColorToken::Surface
+                    })
+                    .on_click(Box::new(move |_| {
+                        Select::choose(&id, index, &on_change);
+                        false
+                    }));
+                if self.selected == Some(index) {
+                    row = row.text_color(ColorToken::Primary);
+                }
+                row.build() as Component
+            })
+            .collect();
+        let list = Layout::get_col_builder()
+            .dim((
+                if state.width > 0 { Length::FIXED(state.width) } else { Length::FIT },
+                Length::FIT,
+            ))
+            // Rows are as wide as the widest one
+            .cross_align(Alignment::Stretch)
+            .bg_color(ColorToken::Surface)
+            .border_width(1)
+            .border_color(ColorToken::Border)
+            .border_radius(Radius::Sm)
+            .children(rows)
+            .build();
+        let close_id = id.clone();
+        OverlayProps::popover(&id)
+            .content(list)
+            .on_dismiss(Box::new(move || with_state(&close_id, |state| state.open = false)))
+            .show();
+    }
+
+    /// Moves the highlight by `step` options, opening the list first if it's closed
+    fn move_highlight(&self, step: i32) {
+        if self.options.is_empty() {
+            return;
+        }
+        let last = self.options.len() as i32 - 1;
+        with_state(&self.get_id(), |state| {
+            if !state.open {
+                state.open = true;
+                state.highlighted = self.selected;
+                return;
+            }
+            let current = state.highlighted.map_or(-1, |index| index as i32);
+            state.highlighted = Some((current + step).clamp(0, last) as usize);
+        });
+    }
+
+    /// Jumps to the first option starting with the letters typed in quick succession
+    fn type_ahead(&self, c: char) {
+        let id = self.get_id();
+        let now = unsafe { ffi::GetTime() };
+        let found = with_state(&id, |state| {
+            if now - state.typed_at > TYPE_AHEAD_RESET {
+                state.typed.clear();
+            }
+            state.typed.extend(c.to_lowercase());
+            state.typed_at = now;
+            let found = self
+                .options
+                .iter()
+                .position(|option| option.to_lowercase().starts_with(&state.typed));
+            if state.open && found.is_some() {
+                state.highlighted = found;
+            }
+            found.filter(|_| !state.open)
+        });
+        // A closed select picks the match straight away
+        if let Some(index) = found {
+            Select::choose(&id, index, &self.on_change);
+        }
+    }
+}
+
+impl Base for Select {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        self.layout.draw(draw_handle)
+    }
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        let (x, y) = self.layout.pos;
+        let (w, h) = self.layout.draw_dim;
+        let pos = mouse_event.pos;
+        if mouse_event.left_button_down && pos.0 >= x && pos.0 <= x + w && pos.1 >= y && pos.1 <= y + h {
+            vec![self.get_id()]
+        } else {
+            vec![]
+        }
+    }
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+    fn execute_on_click(&self, mouse_event: MouseEvent) -> bool {
+        // The click that lands here also dismisses an open list, so this goes by how it was
+        let (open, selected) = (!self.open, self.selected);
+        with_state(&self.get_id(), |state| {
+            state.open = open;
+            state.highlighted = selected;
+        });
+        let mut user_fun = self.layout.on_click.borrow_mut();
+        user_fun(mouse_event)
+    }
+    fn execute_on_key(&self, key_event: KeyEvent) -> bool {
+        {
+            let mut key_fn = self.layout.on_key.borrow_mut();
+            if !key_fn(key_event) {
+                return false;
+            }
+        }
+        let Some(key) = key_event.key else {
+            return true;
+        };
+        match key {
+            KeyboardKey::KEY_DOWN => self.move_highlight(1),
+            KeyboardKey::KEY_UP => self.move_highlight(-1),
+            KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER | KeyboardKey::KEY_SPACE => {
+                let id = self.get_id();
+                let (open, highlighted) = with_state(&id, |state| (state.open, state.highlighted));
+                match highlighted {
+                    Some(index) if open && index < self.options.len() => {
+                        Select::choose(&id, index, &self.on_change)
+                    }
+                    _ => self.move_highlight(0),
+                }
+            }
+            // Shortcuts don't search the options
+            _ if key_event.ctrl_down => {}
+            _ => {
+                if let Some(c) = keyboard_key_to_char(key) {
+                    self.type_ahead(c);
+                }
+            }
+        }
+        true
+    }
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.get_paddings()
+    }
+    fn get_key_event_handlers(&self, _key_event: KeyEvent) -> Vec<String> {
+        vec![self.get_id()]
+    }
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        self.layout.on_key.clone()
+    }
+    fn is_focusable(&self) -> bool {
+        true
+    }
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        self.layout.set_raw_dim(parent_draw_dim);
+    }
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.get_draw_dim()
+    }
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.get_draw_pos()
+    }
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        let id = self.layout.measure_dimensions(parent_draw_dim, id);
+        let width = self.layout.draw_dim.0;
+        with_state(&self.get_id(), |state| state.width = width);
+        id
+    }
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+    }
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        self.layout.measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+    }
+    fn get_overflow(&self) -> (bool, bool) {
+        self.layout.get_overflow()
+    }
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<select width={} height={} x={} y={} bg_color={} options={} selected={:?} open={} name='{}'>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                "███████".truecolor(layout.bg_color.r, layout.bg_color.g, layout.bg_color.b).bold(),
+                self.options.len(),
+                self.selected,
+                self.open,
+                self.get_id(),
+            ),
+            depth,
+        );
+        for child in layout.children.iter() {
+            child.borrow().debug_dims(depth + 1);
+        }
+        tabbed_print("</select>", depth);
+    }
+    fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
+        self.layout.get_children()
+    }
+    fn get_align_self(&self) -> Option<Alignment> {
+        self.layout.get_align_self()
+    }
+    fn get_baseline(&self) -> Option<i32> {
+        self.layout.get_baseline()
+    }
+    fn set_stretch(&mut self, stretch: (Option<i32>, Option<i32>)) {
+        self.layout.set_stretch(stretch);
+    }
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+    fn get_by_id(&self, id: &str) -> Option<Rc<RefCell<dyn Base>>> {
+        self.layout.get_by_id(id)
+    }
+    fn get_position(&self) -> Position {
+        self.layout.get_position()
+    }
+    fn get_z_index(&self) -> i32 {
+        self.layout.get_z_index()
+    }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
+}
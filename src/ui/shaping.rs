@@ -0,0 +1,216 @@
+use std::ops::Range;
+
+use rustybuzz::{
+    Direction, Face, UnicodeBuffer,
+    ttf_parser::{GlyphId, OutlineBuilder},
+};
+use unicode_bidi::BidiInfo;
+
+use crate::ui::{
+    raster::{FillRule, PathBuilder},
+    text_wrap::graphemes,
+};
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32,
+        0x0590..=0x08FF     // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan
+        | 0xFB1D..=0xFDFF   // Hebrew and Arabic presentation forms
+        | 0xFE70..=0xFEFF
+        | 0x10800..=0x10FFF
+        | 0x1E800..=0x1EFFF
+        | 0x200F | 0x202B | 0x202E | 0x2067 // RLM and RTL embeddings
+    )
+}
+
+/// Scripts and marks that only display correctly after OpenType shaping
+fn needs_shaping(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F     // Combining diacritics
+        | 0x0900..=0x0DFF   // Indic scripts
+        | 0x0E00..=0x0EFF   // Thai, Lao
+        | 0x0F00..=0x109F   // Tibetan, Myanmar
+        | 0x1780..=0x17FF   // Khmer
+        | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF
+        | 0xA8E0..=0xA8FF   // Devanagari extended
+    ) || is_rtl_char(c)
+}
+
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl_char)
+}
+
+/// True when `text` needs the BiDi and shaping pipeline rather than per codepoint drawing
+pub fn is_complex(text: &str) -> bool {
+    text.chars().any(needs_shaping)
+}
+
+/// Whether the paragraph direction is right to left, from its first strong character
+pub fn is_rtl_paragraph(text: &str) -> bool {
+    if !has_rtl(text) {
+        return false;
+    }
+    BidiInfo::new(text, None)
+        .paragraphs
+        .first()
+        .is_some_and(|para| para.level.is_rtl())
+}
+
+/// A stretch of a line in one direction
+#[derive(Debug, Clone, PartialEq)]
+pub struct BidiRun {
+    /// Byte range in the line
+    pub range: Range<usize>,
+    pub rtl: bool,
+}
+
+/// Runs of a single line in display order (UAX #9)
+pub fn visual_runs(text: &str) -> Vec<BidiRun> {
+    if !has_rtl(text) {
+        return vec![BidiRun {
+            range: 0..text.len(),
+            rtl: false,
+        }];
+    }
+    let bidi = BidiInfo::new(text, None);
+    let mut runs = vec![];
+    for para in bidi.paragraphs.iter() {
+        let (levels, level_runs) = bidi.visual_runs(para, para.range.clone());
+        for range in level_runs {
+            runs.push(BidiRun {
+                rtl: levels[range.start].is_rtl(),
+                range,
+            });
+        }
+    }
+    runs
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// `text` rearranged into display order for fonts drawn codepoint by codepoint.
+/// Right to left runs are reversed by grapheme so combining marks stay on their base.
+pub fn visual_text(text: &str) -> String {
+    let mut visual = String::with_capacity(text.len());
+    for run in visual_runs(text) {
+        let run_text = &text[run.range];
+        if run.rtl {
+            for grapheme in graphemes(run_text).into_iter().rev() {
+                visual.extend(grapheme.chars().map(mirror));
+            }
+        } else {
+            visual.push_str(run_text);
+        }
+    }
+    visual
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShapedGlyph {
+    pub glyph_id: u16,
+    /// Byte offset in the shaped text of the character the glyph came from
+    pub cluster: usize,
+    pub x_advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+/// Shapes a single direction run at `size` pixels, glyphs come back in visual order
+pub fn shape(face: &Face, text: &str, rtl: bool, size: f32) -> Vec<ShapedGlyph> {
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+    buffer.set_direction(if rtl {
+        Direction::RightToLeft
+    } else {
+        Direction::LeftToRight
+    });
+    let glyphs = rustybuzz::shape(face, &[], buffer);
+    let scale = size / face.units_per_em() as f32;
+    glyphs
+        .glyph_infos()
+        .iter()
+        .zip(glyphs.glyph_positions())
+        .map(|(info, pos)| ShapedGlyph {
+            glyph_id: info.glyph_id as u16,
+            cluster: info.cluster as usize,
+            x_advance: pos.x_advance as f32 * scale,
+            x_offset: pos.x_offset as f32 * scale,
+            y_offset: pos.y_offset as f32 * scale,
+        })
+        .collect()
+}
+
+pub struct GlyphBitmap {
+    pub width: usize,
+    pub height: usize,
+    /// Offset of the top-left corner from the pen position on the baseline
+    pub left: i32,
+    pub top: i32,
+    pub coverage: Vec<u8>,
+}
+
+struct GlyphOutline<'a> {
+    path: &'a mut PathBuilder,
+    scale: f32,
+}
+
+// Font units point up, the bitmap rows go down
+impl OutlineBuilder for GlyphOutline<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.move_to(x * self.scale, -y * self.scale);
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.line_to(x * self.scale, -y * self.scale);
+    }
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.path.quad_to(x1 * s, -y1 * s, x * s, -y * s);
+    }
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.path.cubic_to(x1 * s, -y1 * s, x2 * s, -y2 * s, x * s, -y * s);
+    }
+    fn close(&mut self) {
+        self.path.close();
+    }
+}
+
+/// Renders a glyph by id, which raylib's codepoint based atlases can't do
+pub fn rasterize_glyph(face: &Face, glyph_id: u16, size: f32) -> Option<GlyphBitmap> {
+    let mut path = PathBuilder::new();
+    let scale = size / face.units_per_em() as f32;
+    face.outline_glyph(
+        GlyphId(glyph_id),
+        &mut GlyphOutline {
+            path: &mut path,
+            scale,
+        },
+    )?;
+    let (min_x, min_y, max_x, max_y) = path.bounds()?;
+    let left = min_x.floor() as i32;
+    let top = min_y.floor() as i32;
+    let width = (max_x.ceil() as i32 - left).max(1) as usize;
+    let height = (max_y.ceil() as i32 - top).max(1) as usize;
+    path.transform(|x, y| (x - left as f32, y - top as f32));
+    Some(GlyphBitmap {
+        width,
+        height,
+        left,
+        top,
+        coverage: path.fill(width, height, FillRule::NonZero),
+    })
+}
@@ -0,0 +1,119 @@
+use raylib::color::Color;
+
+use crate::ui::{
+    font::{FontSpec, FontStyle, FontWeight},
+    theme::ColorToken,
+};
+
+pub const MONOSPACE_FAMILY: &str = "monospace";
+
+/// A run of text with its own formatting. Unset fields fall back to the owning component.
+#[derive(Debug, Clone)]
+pub struct TextSpan {
+    pub text: String,
+    pub font_family: Option<String>,
+    pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontStyle>,
+    pub font_size: Option<i32>,
+    pub color: Option<Color>,
+    pub background: Option<Color>,
+    pub underline: bool,
+    pub link: Option<String>,
+}
+
+impl TextSpan {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            font_family: None,
+            font_weight: None,
+            font_style: None,
+            font_size: None,
+            color: None,
+            background: None,
+            underline: false,
+            link: None,
+        }
+    }
+    pub fn font_family(mut self, family: &str) -> Self {
+        self.font_family = Some(family.into());
+        self
+    }
+    pub fn font_weight(mut self, weight: FontWeight) -> Self {
+        self.font_weight = Some(weight);
+        self
+    }
+    pub fn font_style(mut self, style: FontStyle) -> Self {
+        self.font_style = Some(style);
+        self
+    }
+    pub fn bold(self) -> Self {
+        self.font_weight(FontWeight::Bold)
+    }
+    pub fn italic(self) -> Self {
+        self.font_style(FontStyle::Italic)
+    }
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.font_size = Some(size.into());
+        self
+    }
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+    pub fn background(mut self, color: impl Into<Color>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+    /// Clickable span, reported through `on_link_click`
+    pub fn link(mut self, url: &str) -> Self {
+        self.link = Some(url.into());
+        self.underline = true;
+        self
+    }
+    /// Inline code: monospace family on the theme's alternate surface
+    pub fn code(self) -> Self {
+        self.font_family(MONOSPACE_FAMILY).background(ColorToken::SurfaceAlt)
+    }
+
+    pub fn resolve(&self, font: &FontSpec, font_size: i32, color: Color) -> StyledRun {
+        let mut run_font = font.clone();
+        if let Some(family) = &self.font_family {
+            run_font.family = family.clone();
+        }
+        if let Some(weight) = self.font_weight {
+            run_font.weight = weight;
+        }
+        if let Some(style) = self.font_style {
+            run_font.style = style;
+        }
+        StyledRun {
+            text: self.text.clone(),
+            font: run_font,
+            font_size: self.font_size.unwrap_or(font_size),
+            color: self.color.unwrap_or(color),
+            background: self.background,
+            underline: self.underline,
+            link: self.link.clone(),
+            letter_spacing: 0,
+        }
+    }
+}
+
+/// A span with every attribute resolved, ready to be measured and drawn
+#[derive(Debug, Clone)]
+pub struct StyledRun {
+    pub text: String,
+    pub font: FontSpec,
+    pub font_size: i32,
+    pub color: Color,
+    pub background: Option<Color>,
+    pub underline: bool,
+    pub link: Option<String>,
+    /// Extra pixels between characters
+    pub letter_spacing: i32,
+}
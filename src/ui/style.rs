@@ -0,0 +1,184 @@
+use raylib::color::Color;
+
+use crate::ui::{
+    common::{Alignment, Length},
+    font::FontSpec,
+    layout::LayoutProps,
+    paint::{Gradient, Shadow},
+};
+
+/// Text properties a `Layout` passes down to the text inside it. Unset fields come from
+/// further up the tree, text components that set their own keep them.
+#[derive(Debug, Clone, Default)]
+pub struct TextStyle {
+    pub color: Option<Color>,
+    pub font: Option<FontSpec>,
+    pub font_size: Option<i32>,
+}
+
+impl TextStyle {
+    /// `self` with the fields `other` sets replaced
+    pub fn merge(&self, other: &TextStyle) -> TextStyle {
+        TextStyle {
+            color: other.color.or(self.color),
+            font: other.font.clone().or_else(|| self.font.clone()),
+            font_size: other.font_size.or(self.font_size),
+        }
+    }
+}
+
+/// A reusable set of builder settings, applied with `.style(&style)`. Only the fields that
+/// are set are applied, so a style can be layered over a builder's defaults or another style.
+#[derive(Debug, Clone, Default)]
+pub struct Style {
+    pub dim: Option<(Length, Length)>,
+    pub padding: Option<(i32, i32, i32, i32)>,
+    pub gap: Option<i32>,
+    pub main_align: Option<Alignment>,
+    pub cross_align: Option<Alignment>,
+    pub flex: Option<f32>,
+    pub bg_color: Option<Color>,
+    pub gradient: Option<Gradient>,
+    pub border_widths: Option<(i32, i32, i32, i32)>,
+    pub border_colors: Option<(Color, Color, Color, Color)>,
+    pub border_radius: Option<(i32, i32, i32, i32)>,
+    pub shadows: Option<Vec<Shadow>>,
+    pub opacity: Option<f32>,
+    pub text: TextStyle,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        self.dim = Some(dim);
+        self
+    }
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+    pub fn gap(mut self, gap: impl Into<i32>) -> Self {
+        self.gap = Some(gap.into());
+        self
+    }
+    pub fn main_align(mut self, align: Alignment) -> Self {
+        self.main_align = Some(align);
+        self
+    }
+    pub fn cross_align(mut self, align: Alignment) -> Self {
+        self.cross_align = Some(align);
+        self
+    }
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.flex = Some(flex);
+        self
+    }
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        self.bg_color = Some(color.into());
+        self
+    }
+    pub fn gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+    pub fn border_width(mut self, border_width: i32) -> Self {
+        self.border_widths = Some((border_width, border_width, border_width, border_width));
+        self
+    }
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
+        let border_color = border_color.into();
+        self.border_colors = Some((border_color, border_color, border_color, border_color));
+        self
+    }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        let radius = radius.into();
+        self.border_radius = Some((radius, radius, radius, radius));
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.shadows.get_or_insert_with(Vec::new).push(shadow);
+        self
+    }
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.text.color = Some(color.into());
+        self
+    }
+    pub fn font(mut self, font: FontSpec) -> Self {
+        self.text.font = Some(font);
+        self
+    }
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.text.font_size = Some(size.into());
+        self
+    }
+
+    /// `self` with everything `other` sets overriding it
+    pub fn merge(&self, other: &Style) -> Style {
+        Style {
+            dim: other.dim.or(self.dim),
+            padding: other.padding.or(self.padding),
+            gap: other.gap.or(self.gap),
+            main_align: other.main_align.or(self.main_align),
+            cross_align: other.cross_align.or(self.cross_align),
+            flex: other.flex.or(self.flex),
+            bg_color: other.bg_color.or(self.bg_color),
+            gradient: other.gradient.clone().or_else(|| self.gradient.clone()),
+            border_widths: other.border_widths.or(self.border_widths),
+            border_colors: other.border_colors.or(self.border_colors),
+            border_radius: other.border_radius.or(self.border_radius),
+            shadows: other.shadows.clone().or_else(|| self.shadows.clone()),
+            opacity: other.opacity.or(self.opacity),
+            text: self.text.merge(&other.text),
+        }
+    }
+
+    /// Applies the box properties to `layout`, text properties are left to the caller
+    pub fn apply_box(&self, mut layout: LayoutProps) -> LayoutProps {
+        if let Some(dim) = self.dim {
+            layout = layout.dim(dim);
+        }
+        if let Some(padding) = self.padding {
+            layout = layout.padding(padding);
+        }
+        if let Some(gap) = self.gap {
+            layout = layout.gap(gap);
+        }
+        if let Some(align) = self.main_align {
+            layout = layout.main_align(align);
+        }
+        if let Some(align) = self.cross_align {
+            layout = layout.cross_align(align);
+        }
+        if let Some(flex) = self.flex {
+            layout = layout.flex(flex);
+        }
+        if let Some(color) = self.bg_color {
+            layout = layout.bg_color(color);
+        }
+        if let Some(gradient) = &self.gradient {
+            layout = layout.gradient(gradient.clone());
+        }
+        if let Some(widths) = self.border_widths {
+            layout = layout.border_widths(widths);
+        }
+        if let Some(colors) = self.border_colors {
+            layout = layout.border_colors(colors);
+        }
+        if let Some(radii) = self.border_radius {
+            layout = layout.border_radii(radii);
+        }
+        if let Some(shadows) = &self.shadows {
+            layout = layout.shadows(shadows.clone());
+        }
+        if let Some(opacity) = self.opacity {
+            layout = layout.opacity(opacity);
+        }
+        layout
+    }
+}
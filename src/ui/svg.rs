@@ -0,0 +1,457 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::ui::raster::{FillRule, PathBuilder};
+
+/// Absolute path segment in viewBox units, arcs are already converted to cubics
+#[derive(Debug, Clone, Copy)]
+enum PathCommand {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+struct SvgShape {
+    commands: Vec<PathCommand>,
+    rule: FillRule,
+}
+
+/// The filled shapes of an SVG document. Supports `path`, `circle`, `ellipse`, `rect` and
+/// `polygon` with `fill-rule`; strokes, transforms and styles are ignored.
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    /// (x, y, width, height)
+    view_box: (f32, f32, f32, f32),
+    shapes: Vec<SvgShape>,
+}
+
+struct PathLexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PathLexer<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            bytes: data.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace() || *b == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.bytes.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let b = *self.bytes.get(self.pos)?;
+        if b.is_ascii_alphabetic() && b != b'e' && b != b'E' {
+            self.pos += 1;
+            Some(b)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |lexer: &mut Self| {
+            while lexer.bytes.get(lexer.pos).is_some_and(u8::is_ascii_digit) {
+                lexer.pos += 1;
+            }
+        };
+        if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        digits(self);
+        // "0.5.5" is two numbers, the second dot starts the next one
+        if self.bytes.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.bytes.get(self.pos), Some(b'e' | b'E'))
+            && self
+                .bytes
+                .get(self.pos + 1)
+                .is_some_and(|b| b.is_ascii_digit() || *b == b'-' || *b == b'+')
+        {
+            self.pos += 2;
+            digits(self);
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        text.parse().ok().or_else(|| {
+            self.pos = start;
+            None
+        })
+    }
+
+    /// Arc flags may be written without separators, as in "a1 1 0 01.5.5"
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.pos)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(flag)
+    }
+
+    fn point(&mut self) -> Option<(f32, f32)> {
+        Some((self.number()?, self.number()?))
+    }
+}
+
+/// Cubic segments for an SVG elliptical arc (SVG 1.1 appendix F.6)
+fn arc_to_cubics(
+    from: (f32, f32),
+    radii: (f32, f32),
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: (f32, f32),
+) -> Vec<PathCommand> {
+    let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+    if from == to {
+        return vec![];
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return vec![PathCommand::LineTo(to.0, to.1)];
+    }
+    let (sin_phi, cos_phi) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from.0 - to.0) / 2.0, (from.1 - to.1) / 2.0);
+    let x1 = cos_phi * dx + sin_phi * dy;
+    let y1 = -sin_phi * dx + cos_phi * dy;
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coef = sign * (num / den).max(0.0).sqrt();
+    let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+    let cx = cos_phi * cx1 - sin_phi * cy1 + (from.0 + to.0) / 2.0;
+    let cy = sin_phi * cx1 + cos_phi * cy1 + (from.1 + to.1) / 2.0;
+
+    let angle = |u: (f32, f32), v: (f32, f32)| (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1);
+    let u = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let v = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let start = angle((1.0, 0.0), u);
+    let mut sweep_angle = angle(u, v);
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    }
+
+    let point = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        (
+            cx + rx * cos_t * cos_phi - ry * sin_t * sin_phi,
+            cy + rx * cos_t * sin_phi + ry * sin_t * cos_phi,
+        )
+    };
+    let derivative = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        (
+            -rx * sin_t * cos_phi - ry * cos_t * sin_phi,
+            -rx * sin_t * sin_phi + ry * cos_t * cos_phi,
+        )
+    };
+    let segments = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+    let delta = sweep_angle / segments as f32;
+    let k = 4.0 / 3.0 * (delta / 4.0).tan();
+    (0..segments)
+        .map(|i| {
+            let t1 = start + i as f32 * delta;
+            let t2 = t1 + delta;
+            let (p1, d1) = (point(t1), derivative(t1));
+            let d2 = derivative(t2);
+            let p2 = if i + 1 == segments { to } else { point(t2) };
+            PathCommand::CubicTo(
+                p1.0 + k * d1.0,
+                p1.1 + k * d1.1,
+                p2.0 - k * d2.0,
+                p2.1 - k * d2.1,
+                p2.0,
+                p2.1,
+            )
+        })
+        .collect()
+}
+
+/// Parses path data (the `d` attribute), stopping at the first malformed segment
+fn parse_path(data: &str) -> Vec<PathCommand> {
+    let mut lexer = PathLexer::new(data);
+    let mut commands = vec![];
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    // Second control point of the last curve, reflected by S and T
+    let mut last_cubic: Option<(f32, f32)> = None;
+    let mut last_quad: Option<(f32, f32)> = None;
+    let mut command = None;
+    while !lexer.at_end() {
+        if let Some(next) = lexer.command() {
+            command = Some(next);
+        }
+        let Some(cmd) = command else {
+            break;
+        };
+        let relative = cmd.is_ascii_lowercase();
+        let offset = |p: (f32, f32)| if relative { (p.0 + current.0, p.1 + current.1) } else { p };
+        let reflect = |control: Option<(f32, f32)>| {
+            control.map_or(current, |c| (2.0 * current.0 - c.0, 2.0 * current.1 - c.1))
+        };
+        let (mut cubic, mut quad) = (None, None);
+        match cmd.to_ascii_uppercase() {
+            b'M' => {
+                let Some(p) = lexer.point() else { break };
+                current = offset(p);
+                start = current;
+                commands.push(PathCommand::MoveTo(current.0, current.1));
+                // Further pairs after a move are lines
+                command = Some(if relative { b'l' } else { b'L' });
+            }
+            b'L' => {
+                let Some(p) = lexer.point() else { break };
+                current = offset(p);
+                commands.push(PathCommand::LineTo(current.0, current.1));
+            }
+            b'H' => {
+                let Some(x) = lexer.number() else { break };
+                current.0 = if relative { current.0 + x } else { x };
+                commands.push(PathCommand::LineTo(current.0, current.1));
+            }
+            b'V' => {
+                let Some(y) = lexer.number() else { break };
+                current.1 = if relative { current.1 + y } else { y };
+                commands.push(PathCommand::LineTo(current.0, current.1));
+            }
+            b'C' | b'S' => {
+                let c1 = if cmd.eq_ignore_ascii_case(&b'C') {
+                    let Some(p) = lexer.point() else { break };
+                    offset(p)
+                } else {
+                    reflect(last_cubic)
+                };
+                let (Some(c2), Some(p)) = (lexer.point(), lexer.point()) else { break };
+                let (c2, p) = (offset(c2), offset(p));
+                commands.push(PathCommand::CubicTo(c1.0, c1.1, c2.0, c2.1, p.0, p.1));
+                cubic = Some(c2);
+                current = p;
+            }
+            b'Q' | b'T' => {
+                let c = if cmd.eq_ignore_ascii_case(&b'Q') {
+                    let Some(p) = lexer.point() else { break };
+                    offset(p)
+                } else {
+                    reflect(last_quad)
+                };
+                let Some(p) = lexer.point() else { break };
+                let p = offset(p);
+                commands.push(PathCommand::QuadTo(c.0, c.1, p.0, p.1));
+                quad = Some(c);
+                current = p;
+            }
+            b'A' => {
+                let (Some(rx), Some(ry), Some(rotation)) = (lexer.number(), lexer.number(), lexer.number())
+                else {
+                    break;
+                };
+                let (Some(large_arc), Some(sweep), Some(p)) = (lexer.flag(), lexer.flag(), lexer.point())
+                else {
+                    break;
+                };
+                let p = offset(p);
+                commands.extend(arc_to_cubics(current, (rx, ry), rotation, large_arc, sweep, p));
+                current = p;
+            }
+            b'Z' => {
+                commands.push(PathCommand::Close);
+                current = start;
+                command = None;
+            }
+            _ => break,
+        }
+        last_cubic = cubic;
+        last_quad = quad;
+    }
+    commands
+}
+
+/// Attributes of a start tag as (name, value) pairs
+fn attributes(tag: &str) -> Vec<(&str, &str)> {
+    let mut attributes = vec![];
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().rsplit(char::is_whitespace).next().unwrap_or("");
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = after[1..].find(quote) else {
+            break;
+        };
+        attributes.push((name, &after[1..1 + end]));
+        rest = &after[end + 2..];
+    }
+    attributes
+}
+
+fn number_attr(attributes: &[(&str, &str)], name: &str) -> f32 {
+    attributes
+        .iter()
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.trim().trim_end_matches("px").parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn fill_rule(value: &str) -> FillRule {
+    if value.trim() == "evenodd" {
+        FillRule::EvenOdd
+    } else {
+        FillRule::NonZero
+    }
+}
+
+impl SvgDocument {
+    /// `None` when there is no `<svg>` element
+    pub fn parse(source: &str) -> Option<Self> {
+        let mut view_box = None;
+        let mut default_rule = FillRule::NonZero;
+        let mut default_filled = true;
+        let mut shapes = vec![];
+        let mut found_root = false;
+        for chunk in source.split('<').skip(1) {
+            let tag = chunk.split('>').next().unwrap_or("").trim_end_matches('/');
+            let name = tag.split(|c: char| c.is_whitespace()).next().unwrap_or("");
+            let attrs = attributes(&tag[name.len()..]);
+            let attr = |key: &str| attrs.iter().find(|(name, _)| *name == key).map(|(_, value)| *value);
+            let filled = attr("fill").map_or(default_filled, |fill| fill != "none");
+            let rule = attr("fill-rule").map_or(default_rule, fill_rule);
+            let path = match name {
+                "svg" => {
+                    found_root = true;
+                    default_rule = rule;
+                    default_filled = filled;
+                    view_box = attr("viewBox")
+                        .map(|value| {
+                            value
+                                .split(|c: char| c.is_whitespace() || c == ',')
+                                .filter_map(|n| n.parse::<f32>().ok())
+                                .collect::<Vec<_>>()
+                        })
+                        .filter(|values| values.len() == 4)
+                        .map(|v| (v[0], v[1], v[2], v[3]))
+                        .or_else(|| {
+                            let (width, height) = (number_attr(&attrs, "width"), number_attr(&attrs, "height"));
+                            (width > 0.0 && height > 0.0).then_some((0.0, 0.0, width, height))
+                        });
+                    continue;
+                }
+                _ if !filled => continue,
+                "path" => attr("d").unwrap_or("").to_string(),
+                "circle" | "ellipse" => {
+                    let (cx, cy) = (number_attr(&attrs, "cx"), number_attr(&attrs, "cy"));
+                    let (rx, ry) = if name == "circle" {
+                        let r = number_attr(&attrs, "r");
+                        (r, r)
+                    } else {
+                        (number_attr(&attrs, "rx"), number_attr(&attrs, "ry"))
+                    };
+                    format!(
+                        "M{} {}A{rx} {ry} 0 1 0 {} {}A{rx} {ry} 0 1 0 {} {}Z",
+                        cx + rx, cy, cx - rx, cy, cx + rx, cy
+                    )
+                }
+                "rect" => {
+                    let (x, y) = (number_attr(&attrs, "x"), number_attr(&attrs, "y"));
+                    let (w, h) = (number_attr(&attrs, "width"), number_attr(&attrs, "height"));
+                    let r = number_attr(&attrs, "rx").max(number_attr(&attrs, "ry")).min(w / 2.0).min(h / 2.0);
+                    format!(
+                        "M{} {y}H{}A{r} {r} 0 0 1 {} {}V{}A{r} {r} 0 0 1 {} {}H{}A{r} {r} 0 0 1 {x} {}V{}A{r} {r} 0 0 1 {} {y}Z",
+                        x + r, x + w - r, x + w, y + r, y + h - r, x + w - r, y + h, x + r, y + h - r, y + r, x + r
+                    )
+                }
+                "polygon" | "polyline" => format!("M{}Z", attr("points").unwrap_or("")),
+                _ => continue,
+            };
+            let commands = parse_path(&path);
+            if !commands.is_empty() {
+                shapes.push(SvgShape { commands, rule });
+            }
+        }
+        if !found_root {
+            return None;
+        }
+        Some(Self {
+            view_box: view_box.unwrap_or((0.0, 0.0, 24.0, 24.0)),
+            shapes,
+        })
+    }
+
+    /// Width over height of the viewBox
+    pub fn aspect_ratio(&self) -> f32 {
+        self.view_box.2 / self.view_box.3.max(f32::EPSILON)
+    }
+
+    /// Coverage mask of the document scaled into `width` x `height`, centred with its aspect kept
+    pub fn rasterize(&self, width: usize, height: usize) -> Vec<u8> {
+        let (vx, vy, vw, vh) = self.view_box;
+        let scale = (width as f32 / vw).min(height as f32 / vh);
+        let offset = (
+            (width as f32 - vw * scale) / 2.0,
+            (height as f32 - vh * scale) / 2.0,
+        );
+        // Curves are flattened by length, so points are scaled before they reach the builder
+        let map = |x: f32, y: f32| ((x - vx) * scale + offset.0, (y - vy) * scale + offset.1);
+        let mut mask = vec![0u8; width * height];
+        for shape in self.shapes.iter() {
+            let mut path = PathBuilder::new();
+            for command in shape.commands.iter() {
+                match *command {
+                    PathCommand::MoveTo(x, y) => {
+                        let (x, y) = map(x, y);
+                        path.move_to(x, y);
+                    }
+                    PathCommand::LineTo(x, y) => {
+                        let (x, y) = map(x, y);
+                        path.line_to(x, y);
+                    }
+                    PathCommand::QuadTo(x1, y1, x, y) => {
+                        let ((x1, y1), (x, y)) = (map(x1, y1), map(x, y));
+                        path.quad_to(x1, y1, x, y);
+                    }
+                    PathCommand::CubicTo(x1, y1, x2, y2, x, y) => {
+                        let ((x1, y1), (x2, y2), (x, y)) = (map(x1, y1), map(x2, y2), map(x, y));
+                        path.cubic_to(x1, y1, x2, y2, x, y);
+                    }
+                    PathCommand::Close => path.close(),
+                }
+            }
+            path.close();
+            let coverage = path.fill(width, height, shape.rule);
+            // Shapes are painted over each other in document order
+            for (dst, src) in mask.iter_mut().zip(coverage) {
+                *dst = (*dst as u32 + src as u32 * (255 - *dst as u32) / 255) as u8;
+            }
+        }
+        mask
+    }
+}
@@ -0,0 +1,683 @@
+use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
+
+use raylib::{
+    color::Color,
+    ffi::KeyboardKey,
+    prelude::{RaylibDraw, RaylibDrawHandle},
+};
+
+use crate::ui::{
+    common::{
+        AbsoluteDraw, Alignment, Base, Component, KeyEvent, Length, MouseEvent,
+        keyboard_key_to_char, shift_character, tabbed_print,
+    },
+    edit_state::{self, with_edit_state},
+    font::{self, FontSpec},
+    layout::{Layout, LayoutProps},
+    paint::{self, Shadow},
+    raw_text::RawText,
+    root::focused_id,
+    text_wrap::{self, WhiteSpace, graphemes},
+    style::{Style, TextStyle},
+    theme::{self, ColorToken},
+    tooltip::Tooltip,
+};
+
+use colored::Colorize;
+
+pub struct TextAreaProps {
+    pub layout: LayoutProps,
+    pub content: String,
+    pub font: FontSpec,
+    pub font_size: i32,
+    pub text_color: Color,
+    /// Text properties set on this builder, they win over inherited ones
+    pub own_style: TextStyle,
+    pub caret_color: Color,
+    /// Enter submits and Shift+Enter adds a newline.
+    /// Otherwise Enter adds a newline and Ctrl+Enter submits.
+    pub submit_on_enter: bool,
+    /// Height a `FIT` text area grows to before it starts scrolling
+    pub max_height: Option<i32>,
+    pub line_numbers: bool,
+    pub line_number_color: Color,
+    pub on_change: Rc<RefCell<dyn FnMut(String)>>,
+    pub on_submit: Rc<RefCell<dyn FnMut(String)>>,
+}
+
+impl TextAreaProps {
+    /// `id` keys the caret and scroll across rebuilds, it must be unique and stable
+    pub fn new(id: &str) -> Self {
+        let layout = Layout::get_col_builder()
+            .dbg_name(id)
+            .dim((Length::FILL, Length::FIT))
+            .bg_color(ColorToken::Surface)
+            .main_align(Alignment::Start)
+            .cross_align(Alignment::Start)
+            .padding((0, 0, 0, 0))
+            .gap(0)
+            .flex(1.0)
+            .overflow_x(false)
+            .overflow_y(true);
+
+        let theme = theme::current();
+        Self {
+            layout,
+            content: "".to_string(),
+            font: FontSpec::default(),
+            font_size: theme.typography.lg,
+            text_color: theme.palette.text,
+            own_style: TextStyle::default(),
+            caret_color: theme.palette.text,
+            submit_on_enter: false,
+            max_height: None,
+            line_numbers: false,
+            line_number_color: theme.palette.text_muted,
+            on_change: Rc::new(RefCell::new(|_content| {})),
+            on_submit: Rc::new(RefCell::new(|_content| {})),
+        }
+    }
+
+    pub fn border_width(mut self, border_width: i32) -> Self {
+        let layout = self.layout.border_width(border_width);
+        self.layout = layout;
+        self
+    }
+
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
+        let layout = self.layout.border_color(border_color);
+        self.layout = layout;
+        self
+    }
+
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        let layout = self.layout.border_radius(radius);
+        self.layout = layout;
+        self
+    }
+
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        let layout = self.layout.shadow(shadow);
+        self.layout = layout;
+        self
+    }
+
+    pub fn content(mut self, content: &str) -> Self {
+        self.content = content.to_string();
+        self
+    }
+
+    pub fn font(mut self, font: FontSpec) -> Self {
+        self.own_style.font = Some(font.clone());
+        self.font = font;
+        self
+    }
+
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.font_size = size.into();
+        self.own_style.font_size = Some(self.font_size);
+        self
+    }
+
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.text_color = color.into();
+        self.own_style.color = Some(self.text_color);
+        self
+    }
+
+    pub fn caret_color(mut self, color: impl Into<Color>) -> Self {
+        self.caret_color = color.into();
+        self
+    }
+
+    pub fn submit_on_enter(mut self, submit_on_enter: bool) -> Self {
+        self.submit_on_enter = submit_on_enter;
+        self
+    }
+
+    pub fn max_height(mut self, max_height: i32) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    pub fn line_number_color(mut self, color: impl Into<Color>) -> Self {
+        self.line_number_color = color.into();
+        self
+    }
+
+    /// Called with the new content after every edit
+    pub fn on_change(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_change = Rc::new(RefCell::new(f));
+        self
+    }
+
+    pub fn on_submit(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_submit = Rc::new(RefCell::new(f));
+        self
+    }
+
+    pub fn dim(mut self, dim: (Length, Length)) -> Self {
+        let layout = self.layout.dim(dim);
+        self.layout = layout;
+        self
+    }
+
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
+        let layout = self.layout.bg_color(color);
+        self.layout = layout;
+        self
+    }
+
+    pub fn padding(mut self, padding: (i32, i32, i32, i32)) -> Self {
+        let layout = self.layout.padding(padding);
+        self.layout = layout;
+        self
+    }
+
+    /// Also keys the caret, give text areas a name when the tree around them changes
+    pub fn dbg_name(mut self, name: &str) -> Self {
+        let layout = self.layout.dbg_name(name);
+        self.layout = layout;
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout = self.layout.tooltip(tooltip);
+        self
+    }
+
+    pub fn flex(mut self, flex: f32) -> Self {
+        let layout = self.layout.flex(flex);
+        self.layout = layout;
+        self
+    }
+
+    pub fn on_click(mut self, f: Box<dyn FnMut(MouseEvent) -> bool>) -> Self {
+        let layout = self.layout.on_click(f);
+        self.layout = layout;
+        self
+    }
+
+    /// Runs before the built in editing, returning false skips it
+    pub fn on_key(mut self, f: Box<dyn FnMut(KeyEvent) -> bool>) -> Self {
+        let layout = self.layout.on_key(f);
+        self.layout = layout;
+        self
+    }
+
+    pub fn style(mut self, style: &Style) -> Self {
+        if let Some(color) = style.text.color {
+            self = self.text_color(color);
+        }
+        if let Some(font) = &style.text.font {
+            self = self.font(font.clone());
+        }
+        if let Some(size) = style.text.font_size {
+            self = self.font_size(size);
+        }
+        self.layout = style.apply_box(self.layout);
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<TextArea>> {
+        Rc::new(RefCell::new(TextArea {
+            layout: self.layout.get_layout(),
+            content: self.content,
+            font: self.font,
+            font_size: self.font_size,
+            text_color: self.text_color,
+            own_style: self.own_style,
+            caret_color: self.caret_color,
+            submit_on_enter: self.submit_on_enter,
+            max_height: self.max_height,
+            line_numbers: self.line_numbers,
+            line_number_color: self.line_number_color,
+            on_change: self.on_change,
+            on_submit: self.on_submit,
+            lines: vec![],
+            texts: vec![],
+        }))
+    }
+}
+
+/// Multi-line editor. The content is controlled by the app through `on_change`,
+/// the caret is kept by the text area itself.
+pub struct TextArea {
+    layout: Layout,
+    content: String,
+    font: FontSpec,
+    font_size: i32,
+    text_color: Color,
+    own_style: TextStyle,
+    caret_color: Color,
+    submit_on_enter: bool,
+    max_height: Option<i32>,
+    line_numbers: bool,
+    line_number_color: Color,
+    on_change: Rc<RefCell<dyn FnMut(String)>>,
+    on_submit: Rc<RefCell<dyn FnMut(String)>>,
+    /// Byte range of each wrapped row in `content`
+    lines: Vec<Range<usize>>,
+    texts: Vec<Rc<RefCell<RawText>>>,
+}
+
+impl TextArea {
+    pub fn get_builder(id: &str) -> TextAreaProps {
+        TextAreaProps::new(id)
+    }
+
+    /// Row holding `caret`, at a wrap the caret belongs to the start of the next row
+    fn caret_line(&self, caret: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|range| range.start <= caret)
+            .unwrap_or(0)
+    }
+
+    fn line_caret_x(&self, line: usize, caret: usize) -> i32 {
+        let Some(range) = self.lines.get(line) else {
+            return 0;
+        };
+        let index = caret.clamp(range.start, range.end) - range.start;
+        font::caret_x(&self.font, &self.content[range.clone()], self.font_size, index)
+    }
+
+    fn line_index_at_x(&self, line: usize, x: i32) -> usize {
+        let Some(range) = self.lines.get(line) else {
+            return self.content.len();
+        };
+        range.start + font::index_at_x(&self.font, &self.content[range.clone()], self.font_size, x)
+    }
+
+    fn build_rows(&mut self, max_width: Option<i32>) -> Vec<Component> {
+        let lines = text_wrap::wrap_lines(
+            &self.content,
+            &self.font,
+            self.font_size,
+            max_width,
+            WhiteSpace::PreWrap,
+        );
+        self.lines = lines.iter().map(|line| line.range.clone()).collect();
+        self.texts.clear();
+        let gutter_width = self.gutter_width();
+        let mut paragraph = 1;
+        let mut starts_paragraph = true;
+        let mut rows = vec![];
+        for line in lines.iter() {
+            let text = line.pieces.iter().map(|piece| piece.text.as_str()).collect::<String>();
+            let raw_text = RawText::new(&text, &self.font, self.font_size, (0, 0, 0, 0), self.text_color);
+            let mut children: Vec<Component> = vec![];
+            if self.line_numbers {
+                // Wrapped continuations of a line get an empty gutter
+                let number = if starts_paragraph { paragraph.to_string() } else { String::new() };
+                let gutter = Layout::get_row_builder()
+                    .dim((Length::FIXED(gutter_width), Length::FIT))
+                    .main_align(Alignment::End)
+                    .padding((0, 0, self.font_size / 2, 0))
+                    .overflow_y(false)
+                    .children(vec![RawText::new(
+                        &number,
+                        &self.font,
+                        self.font_size,
+                        (0, 0, 0, 0),
+                        self.line_number_color,
+                    )])
+                    .build();
+                gutter.borrow_mut().set_raw_dim((0, 0));
+                children.push(gutter);
+            }
+            children.push(raw_text.clone());
+            self.texts.push(raw_text);
+            let row = Layout::get_row_builder()
+                .dim((Length::FIT, Length::FIT))
+                .overflow_y(false)
+                .children(children)
+                .build();
+            row.borrow_mut().set_raw_dim((0, 0));
+            rows.push(row as Component);
+            if line.hard_end {
+                paragraph += 1;
+            }
+            starts_paragraph = line.hard_end;
+        }
+        rows
+    }
+
+    fn gutter_width(&self) -> i32 {
+        if !self.line_numbers {
+            return 0;
+        }
+        let count = self.content.split('\n').count();
+        let digits = count.to_string().len() as i32;
+        digits * font::measure_text(&self.font, "0", self.font_size) + self.font_size / 2
+    }
+}
+
+impl Base for TextArea {
+    fn set_pos(&mut self, pos: (i32, i32)) {
+        self.layout.set_pos(pos);
+    }
+
+    fn get_draw_pos(&self) -> (i32, i32) {
+        self.layout.get_draw_pos()
+    }
+
+    fn draw(&self, draw_handle: &mut RaylibDrawHandle) -> Vec<AbsoluteDraw> {
+        let abs_draws = self.layout.draw(draw_handle);
+        let id = self.get_id();
+        if focused_id().as_deref() != Some(id.as_str()) {
+            return abs_draws;
+        }
+        let caret = edit_state::caret(&id, &self.content);
+        let line = self.caret_line(caret);
+        if let Some(text) = self.texts.get(line) {
+            let text = text.borrow();
+            // Rows scrolled partly out of view aren't drawn, neither is their caret
+            if !text.overflowed {
+                let x = text.pos.0 + self.line_caret_x(line, caret);
+                draw_handle.draw_rectangle(x, text.pos.1, 2, self.font_size, paint::faded(self.caret_color));
+            }
+        }
+        abs_draws
+    }
+
+    fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
+        self.layout.get_children()
+    }
+
+    fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        let layout = &self.layout;
+        let mouse_pos = mouse_event.pos;
+        let max_x = layout.pos.0 + layout.draw_dim.0;
+        let max_y = layout.pos.1 + layout.draw_dim.1;
+        if mouse_event.left_button_down
+            && mouse_pos.0 as i32 >= layout.pos.0
+            && mouse_pos.0 as i32 <= max_x
+            && mouse_pos.1 as i32 >= layout.pos.1
+            && mouse_pos.1 as i32 <= max_y
+        {
+            return vec![self.get_id()];
+        } else {
+            return vec![];
+        }
+    }
+
+    fn get_on_click(&self) -> Rc<RefCell<dyn FnMut(MouseEvent) -> bool>> {
+        self.layout.get_on_click()
+    }
+
+    fn get_paddings(&self) -> (i32, i32, i32, i32) {
+        self.layout.get_paddings()
+    }
+
+    fn execute_on_click(&self, mouse_event: MouseEvent) -> bool {
+        let hit_line = self.texts.iter().position(|text| {
+            let text = text.borrow();
+            !text.overflowed
+                && mouse_event.pos.1 >= text.pos.1
+                && mouse_event.pos.1 < text.pos.1 + self.font_size
+        });
+        if let Some(line) = hit_line {
+            let x = mouse_event.pos.0 - self.texts[line].borrow().pos.0;
+            let caret = self.line_index_at_x(line, x);
+            with_edit_state(&self.get_id(), |state| state.caret = Some(caret));
+        }
+        let mut user_fun = self.layout.on_click.borrow_mut();
+        user_fun(mouse_event)
+    }
+
+    fn execute_on_key(&self, key_event: KeyEvent) -> bool {
+        {
+            let mut key_fn = self.layout.on_key.borrow_mut();
+            if !key_fn(key_event) {
+                return false;
+            }
+        }
+        let Some(key) = key_event.key else {
+            return true;
+        };
+        let id = self.get_id();
+        let mut content = self.content.clone();
+        let mut caret = edit_state::caret(&id, &content);
+        let line = self.caret_line(caret);
+        match key {
+            KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER => {
+                let submit = if self.submit_on_enter {
+                    !key_event.shift_down
+                } else {
+                    key_event.ctrl_down
+                };
+                if submit {
+                    with_edit_state(&id, |state| state.caret = None);
+                    let mut on_submit = self.on_submit.borrow_mut();
+                    on_submit(content);
+                    return true;
+                }
+                content.insert(caret, '\n');
+                caret += 1;
+            }
+            KeyboardKey::KEY_BACKSPACE => {
+                if let Some(len) = graphemes(&content[..caret]).last().map(|g| g.len()) {
+                    content.replace_range(caret - len..caret, "");
+                    caret -= len;
+                }
+            }
+            KeyboardKey::KEY_DELETE => {
+                if let Some(len) = graphemes(&content[caret..]).first().map(|g| g.len()) {
+                    content.replace_range(caret..caret + len, "");
+                }
+            }
+            KeyboardKey::KEY_LEFT => {
+                caret -= graphemes(&content[..caret]).last().map_or(0, |g| g.len());
+            }
+            KeyboardKey::KEY_RIGHT => {
+                caret += graphemes(&content[caret..]).first().map_or(0, |g| g.len());
+            }
+            KeyboardKey::KEY_UP => {
+                caret = match line.checked_sub(1) {
+                    Some(target) => self.line_index_at_x(target, self.line_caret_x(line, caret)),
+                    None => 0,
+                };
+            }
+            KeyboardKey::KEY_DOWN => {
+                caret = if line + 1 < self.lines.len() {
+                    self.line_index_at_x(line + 1, self.line_caret_x(line, caret))
+                } else {
+                    content.len()
+                };
+            }
+            KeyboardKey::KEY_HOME => {
+                caret = self.lines.get(line).map_or(0, |range| range.start);
+            }
+            KeyboardKey::KEY_END => {
+                caret = self.lines.get(line).map_or(content.len(), |range| range.end);
+            }
+            _ => {
+                if key_event.ctrl_down {
+                    return true;
+                }
+                let Some(mut c) = keyboard_key_to_char(key) else {
+                    return true;
+                };
+                if key_event.shift_down {
+                    c = shift_character(c);
+                }
+                content.insert(caret, c);
+                caret += c.len_utf8();
+            }
+        }
+        with_edit_state(&id, |state| {
+            state.caret = Some(caret);
+            state.follow_caret = true;
+        });
+        if content != self.content {
+            let mut on_change = self.on_change.borrow_mut();
+            on_change(content);
+        }
+        true
+    }
+
+    fn get_key_event_handlers(&self, _key_event: KeyEvent) -> Vec<String> {
+        vec![self.get_id()]
+    }
+
+    fn get_on_key(&self) -> Rc<RefCell<dyn FnMut(KeyEvent) -> bool>> {
+        self.layout.on_key.clone()
+    }
+
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        let text_style = inherited.merge(&self.own_style);
+        if let Some(color) = text_style.color {
+            self.text_color = color;
+        }
+        if let Some(font) = text_style.font {
+            self.font = font;
+        }
+        if let Some(size) = text_style.font_size {
+            self.font_size = size;
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        let padding = self.layout.padding;
+        let (mut draw_width, mut draw_height) = crate::ui::common::get_draw_dim(
+            self.layout.dim,
+            parent_draw_dim,
+            &Vec::new(),
+            self.layout.direction,
+            self.layout.border_widths,
+        );
+        let max_width = if self.layout.dim.0 == Length::FIT {
+            None
+        } else {
+            Some(draw_width - padding.0 - padding.2 - self.gutter_width())
+        };
+        let rows = self.build_rows(max_width);
+        let layout = &mut self.layout;
+        layout.children = rows;
+
+        if layout.dim.0 == Length::FIT {
+            draw_width = layout
+                .children
+                .iter()
+                .map(|child| child.borrow().get_draw_dim().0)
+                .max()
+                .unwrap_or(0)
+                + padding.0
+                + padding.2;
+        }
+        if layout.dim.1 == Length::FIT {
+            draw_height = layout
+                .children
+                .iter()
+                .map(|child| child.borrow().get_draw_dim().1)
+                .sum::<i32>()
+                + padding.1
+                + padding.3;
+            if let Some(max_height) = self.max_height {
+                draw_height = draw_height.min(max_height);
+            }
+        }
+
+        layout.draw_dim = (draw_width, draw_height);
+    }
+
+    fn get_draw_dim(&self) -> (i32, i32) {
+        self.layout.get_draw_dim()
+    }
+
+    fn measure_dimensions(&mut self, parent_draw_dim: (i32, i32), id: usize) -> usize {
+        let draw_dim = self.layout.draw_dim;
+        let id = self.layout.measure_dimensions(parent_draw_dim, id);
+        // The layout re-measures itself from its rows, which knows nothing of max_height
+        self.layout.draw_dim = draw_dim;
+        id
+    }
+
+    fn measure_positions(&mut self, parent_pos: (i32, i32)) {
+        self.layout.measure_positions(parent_pos);
+    }
+
+    fn measure_overflows(
+        &mut self,
+        parent_draw_dim: (i32, i32),
+        parent_pos: (i32, i32),
+        scroll_map: &mut HashMap<String, i32>,
+        y_offset: i32,
+    ) {
+        let id = self.get_id();
+        if with_edit_state(&id, |state| std::mem::take(&mut state.follow_caret)) {
+            let caret = edit_state::caret(&id, &self.content);
+            let caret_top = self.caret_line(caret) as i32 * self.font_size;
+            let caret_bottom = caret_top + self.font_size;
+            let padding = self.layout.padding;
+            let visible_height = self.layout.draw_dim.1 - padding.1 - padding.3;
+            let scroll = scroll_map.entry(id).or_insert(0);
+            if caret_top < *scroll {
+                *scroll = caret_top;
+            } else if caret_bottom > *scroll + visible_height {
+                *scroll = caret_bottom - visible_height;
+            }
+        }
+        self.layout
+            .measure_overflows(parent_draw_dim, parent_pos, scroll_map, y_offset);
+    }
+
+    fn get_overflow(&self) -> (bool, bool) {
+        self.layout.get_overflow()
+    }
+
+    fn get_flex(&self) -> f32 {
+        self.layout.get_flex()
+    }
+
+    fn debug_dims(&self, depth: usize) {
+        let layout = &self.layout;
+        tabbed_print(
+            &format!(
+                "<textarea width={} height={} x={} y={} bg_color={} text_color={} padding=({},{},{},{}) lines={} name='{}' flex={}>",
+                layout.draw_dim.0,
+                layout.draw_dim.1,
+                layout.pos.0,
+                layout.pos.1,
+                "███████".truecolor(layout.bg_color.r, layout.bg_color.g, layout.bg_color.b).bold(),
+                "███████".truecolor(self.text_color.r, self.text_color.g, self.text_color.b).bold(),
+                layout.padding.0,
+                layout.padding.1,
+                layout.padding.2,
+                layout.padding.3,
+                self.lines.len(),
+                self.get_id(),
+                layout.flex
+            ),
+            depth,
+        );
+        for child in layout.children.iter() {
+            child.borrow().debug_dims(depth + 1);
+        }
+        tabbed_print("</textarea>", depth);
+    }
+
+    fn get_id(&self) -> String {
+        self.layout.get_id()
+    }
+
+    fn get_by_id(&self, id: &str) -> Option<Rc<RefCell<dyn Base>>> {
+        self.layout.get_by_id(id)
+    }
+
+    fn get_position(&self) -> super::common::Position {
+        self.layout.get_position()
+    }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
+}
@@ -1,17 +1,25 @@
-use std::{cell::RefCell, collections::HashMap, ffi::CString, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
 
 use raylib::{
     color::Color,
     ffi::KeyboardKey,
-    prelude::RaylibDrawHandle,
+    prelude::{RaylibDraw, RaylibDrawHandle},
 };
 
 use crate::ui::{
     common::{
-        AbsoluteDraw, Alignment, Base, KeyEvent, Length, MouseEvent, keyboard_key_to_char, shift_character, tabbed_print
+        AbsoluteDraw, Alignment, Base, Component, KeyEvent, Length, MouseEvent, keyboard_key_to_char, shift_character, tabbed_print
     },
+    edit_state::{self, with_edit_state},
+    font::{self, FontSpec},
     layout::{self, Layout, LayoutProps},
+    paint::{self, Shadow},
     raw_text::RawText,
+    root::focused_id,
+    text_wrap::{self, WhiteSpace, graphemes},
+    style::{Style, TextStyle},
+    theme::{self, ColorToken},
+    tooltip::Tooltip,
 };
 
 use colored::Colorize;
@@ -19,9 +27,30 @@ use colored::Colorize;
 pub struct TextInputProps {
     pub layout: LayoutProps,
     pub content: String,
+    /// Starting content of an uncontrolled input, `None` keeps it controlled by `content`
+    pub default_content: Option<String>,
+    pub font: FontSpec,
     pub font_size: i32,
     pub wrap: bool,
+    pub white_space: WhiteSpace,
     pub text_color: Color,
+    /// Text properties set on this builder, they win over inherited ones
+    pub own_style: TextStyle,
+    pub caret_color: Color,
+    pub selection_color: Color,
+    pub placeholder: String,
+    pub placeholder_color: Color,
+    /// Maximum number of characters
+    pub max_length: Option<usize>,
+    /// Decides which typed characters are accepted
+    pub filter: Rc<dyn Fn(char) -> bool>,
+    /// Checks the whole content after each insertion, rejected edits are dropped
+    pub validator: Rc<dyn Fn(&str) -> bool>,
+    pub password: bool,
+    pub read_only: bool,
+    pub disabled: bool,
+    pub on_change: Rc<RefCell<dyn FnMut(String)>>,
+    pub on_submit: Rc<RefCell<dyn FnMut(String)>>,
     // pub def_on_key: Rc<RefCell<dyn FnMut(KeyEvent) -> bool>>,
 }
 
@@ -29,7 +58,7 @@ impl TextInputProps {
     pub fn new() -> Self {
         let layout = Layout::get_col_builder()
             .dim((Length::FIT, Length::FIT))
-            .bg_color(Color::WHITE)
+            .bg_color(ColorToken::Surface)
             .main_align(Alignment::Start)
             .cross_align(Alignment::Start)
             .padding((0, 0, 0, 0))
@@ -38,12 +67,29 @@ impl TextInputProps {
             .overflow_x(false)
             .overflow_y(true);
         
+        let theme = theme::current();
         Self {
             layout,
             content: "".to_string(),
-            font_size: 24,
+            default_content: None,
+            font: FontSpec::default(),
+            font_size: theme.typography.lg,
             wrap: true,
-            text_color: Color::BLACK,
+            white_space: WhiteSpace::PreWrap,
+            text_color: theme.palette.text,
+            own_style: TextStyle::default(),
+            caret_color: theme.palette.text,
+            selection_color: theme.palette.selection,
+            placeholder: String::new(),
+            placeholder_color: theme.palette.text_muted,
+            max_length: None,
+            filter: Rc::new(|_c| true),
+            validator: Rc::new(|_content| true),
+            password: false,
+            read_only: false,
+            disabled: false,
+            on_change: Rc::new(RefCell::new(|_content| {})),
+            on_submit: Rc::new(RefCell::new(|_content| {})),
         }
     }
 
@@ -52,19 +98,44 @@ impl TextInputProps {
         self.layout = layout;
         self
     }
-    pub fn border_color(mut self, border_color: Color) -> Self {
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
         let layout = self.layout.border_color(border_color);
         self.layout = layout;
         self
     }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        let layout = self.layout.border_radius(radius);
+        self.layout = layout;
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        let layout = self.layout.shadow(shadow);
+        self.layout = layout;
+        self
+    }
 
     pub fn content(mut self, content: &str) -> Self {
         self.content = content.to_string();
         self
     }
 
-    pub fn font_size(mut self, size: i32) -> Self {
-        self.font_size = size;
+    /// Makes the input uncontrolled, it keeps its own text across rebuilds starting from
+    /// `content`. `key` becomes the input's id, the text is stored under it.
+    pub fn default_content(mut self, key: &str, content: &str) -> Self {
+        self.layout = self.layout.dbg_name(key);
+        self.default_content = Some(content.to_string());
+        self
+    }
+
+    pub fn font(mut self, font: FontSpec) -> Self {
+        self.own_style.font = Some(font.clone());
+        self.font = font;
+        self
+    }
+
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.font_size = size.into();
+        self.own_style.font_size = Some(self.font_size);
         self
     }
 
@@ -73,13 +144,18 @@ impl TextInputProps {
         self
     }
 
+    pub fn white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.white_space = white_space;
+        self
+    }
+
     pub fn dim(mut self, dim: (Length, Length)) -> Self {
         let layout = self.layout.dim(dim);
         self.layout = layout;
         self
     }
 
-    pub fn bg_color(mut self, color: Color) -> Self {
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
         let layout = self.layout.bg_color(color);
         self.layout = layout;
         self
@@ -103,7 +179,7 @@ impl TextInputProps {
         self
     }
 
-    pub fn gap(mut self, gap: i32) -> Self {
+    pub fn gap(mut self, gap: impl Into<i32>) -> Self {
         let layout = self.layout.gap(gap);
         self.layout = layout;
         self
@@ -114,6 +190,10 @@ impl TextInputProps {
         self.layout = layout;
         self
     }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        self.layout = self.layout.tooltip(tooltip);
+        self
+    }
 
     pub fn flex(mut self, flex: f32) -> Self {
         let layout = self.layout.flex(flex);
@@ -133,8 +213,82 @@ impl TextInputProps {
         self
     }
 
-    pub fn text_color(mut self, color: Color) -> Self {
-        self.text_color = color;
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.text_color = color.into();
+        self.own_style.color = Some(self.text_color);
+        self
+    }
+
+    pub fn caret_color(mut self, color: impl Into<Color>) -> Self {
+        self.caret_color = color.into();
+        self
+    }
+
+    /// Drawn over selected text, keep it translucent
+    pub fn selection_color(mut self, color: impl Into<Color>) -> Self {
+        self.selection_color = color.into();
+        self
+    }
+
+    /// Shown in `placeholder_color` while the input is empty
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    pub fn placeholder_color(mut self, color: impl Into<Color>) -> Self {
+        self.placeholder_color = color.into();
+        self
+    }
+
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn filter(mut self, f: Box<dyn Fn(char) -> bool>) -> Self {
+        self.filter = Rc::from(f);
+        self
+    }
+
+    /// Only accepts digits
+    pub fn numeric(self) -> Self {
+        self.filter(Box::new(|c| c.is_ascii_digit()))
+    }
+
+    pub fn validator(mut self, f: Box<dyn Fn(&str) -> bool>) -> Self {
+        self.validator = Rc::from(f);
+        self
+    }
+
+    /// Draws a bullet for every character instead of the content
+    pub fn password(mut self, password: bool) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Can be focused but the built in editing is off
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Greyed out, can't be focused or clicked
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Called with the new content after every accepted edit. A controlled input only shows
+    /// the edit once the app stores the content and passes it back through `content`.
+    pub fn on_change(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_change = Rc::new(RefCell::new(f));
+        self
+    }
+
+    /// Called with the content when Enter is pressed
+    pub fn on_submit(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_submit = Rc::new(RefCell::new(f));
         self
     }
 
@@ -150,14 +304,54 @@ impl TextInputProps {
         self
     }
 
+    pub fn style(mut self, style: &Style) -> Self {
+        if let Some(color) = style.text.color {
+            self = self.text_color(color);
+        }
+        if let Some(font) = &style.text.font {
+            self = self.font(font.clone());
+        }
+        if let Some(size) = style.text.font_size {
+            self = self.font_size(size);
+        }
+        self.layout = style.apply_box(self.layout);
+        self
+    }
+
     pub fn build(self) -> Rc<RefCell<TextInput>> {
-        let layout = self.layout;
+        let layout = self.layout.get_layout();
+        let controlled = self.default_content.is_none();
+        let content = match self.default_content {
+            Some(default_content) => with_edit_state(&layout.get_id(), |state| {
+                state.text.get_or_insert(default_content).clone()
+            }),
+            None => self.content,
+        };
         Rc::new(RefCell::new(TextInput {
-            layout: layout.get_layout(),
-            content: self.content.clone(),
+            layout,
+            content,
+            controlled,
+            font: self.font,
             font_size: self.font_size,
             wrap: self.wrap,
+            white_space: self.white_space,
             text_color: self.text_color,
+            own_style: self.own_style,
+            caret_color: self.caret_color,
+            selection_color: self.selection_color,
+            placeholder: self.placeholder,
+            placeholder_color: self.placeholder_color,
+            max_length: self.max_length,
+            filter: self.filter,
+            validator: self.validator,
+            password: self.password,
+            read_only: self.read_only,
+            disabled: self.disabled,
+            on_change: self.on_change,
+            on_submit: self.on_submit,
+            display: String::new(),
+            lines: vec![],
+            texts: vec![],
         }))
     }
 }
@@ -165,45 +359,219 @@ impl TextInputProps {
 pub struct TextInput {
     layout: Layout,
     content: String,
+    controlled: bool,
+    font: FontSpec,
     font_size: i32,
     wrap: bool,
+    white_space: WhiteSpace,
     text_color: Color,
+    own_style: TextStyle,
+    caret_color: Color,
+    selection_color: Color,
+    placeholder: String,
+    placeholder_color: Color,
+    max_length: Option<usize>,
+    filter: Rc<dyn Fn(char) -> bool>,
+    validator: Rc<dyn Fn(&str) -> bool>,
+    password: bool,
+    read_only: bool,
+    disabled: bool,
+    on_change: Rc<RefCell<dyn FnMut(String)>>,
+    on_submit: Rc<RefCell<dyn FnMut(String)>>,
+    /// Drawn text and the byte range of each row in it
+    display: String,
+    lines: Vec<Range<usize>>,
+    texts: Vec<Rc<RefCell<RawText>>>,
 }
 
 impl TextInput {
     pub fn get_builder() -> TextInputProps {
         TextInputProps::new()
     }
-}
 
-fn get_text_rows(content: &str, max_width: i32, font_size: i32) -> Vec<String> {
-    let mut rows = vec![];
-    let words: Vec<&str> = content.split_whitespace().collect();
-    let mut current_row = String::new();
-    for word in words {
-        let test_row = if current_row.is_empty() {
-            word.to_string()
+    /// Replaces the text of the uncontrolled input with id `id`, e.g. to clear it after a submit
+    pub fn set_content(id: &str, content: &str) {
+        with_edit_state(id, |state| {
+            state.text = Some(content.to_string());
+            state.caret = None;
+            state.anchor = None;
+        });
+    }
+
+    /// Applies `key_event` to the content, caret and selection.
+    /// Returns the new content, `None` when it didn't change or the edit was rejected.
+    fn edit(&self, key_event: KeyEvent) -> Option<String> {
+        let key = key_event.key?;
+        let id = self.get_id();
+        let mut content = self.content.clone();
+        let mut caret = edit_state::caret(&id, &content);
+        let mut anchor = with_edit_state(&id, |state| state.anchor);
+        let selection = edit_state::selection(&id, &content);
+        let mut inserted = None;
+        match key {
+            KeyboardKey::KEY_LEFT | KeyboardKey::KEY_RIGHT | KeyboardKey::KEY_HOME | KeyboardKey::KEY_END => {
+                let target = match (key, &selection) {
+                    (KeyboardKey::KEY_LEFT, Some(selection)) if !key_event.shift_down => selection.start,
+                    (KeyboardKey::KEY_RIGHT, Some(selection)) if !key_event.shift_down => selection.end,
+                    (KeyboardKey::KEY_LEFT, _) => edit_state::prev_boundary(&content, caret),
+                    (KeyboardKey::KEY_RIGHT, _) => edit_state::next_boundary(&content, caret),
+                    (KeyboardKey::KEY_HOME, _) => 0,
+                    _ => content.len(),
+                };
+                anchor = if key_event.shift_down { anchor.or(Some(caret)) } else { None };
+                caret = target;
+            }
+            KeyboardKey::KEY_A if key_event.ctrl_down => {
+                anchor = Some(0);
+                caret = content.len();
+            }
+            KeyboardKey::KEY_C | KeyboardKey::KEY_X if key_event.ctrl_down => {
+                // Passwords stay out of the clipboard
+                let selection = selection.clone().filter(|_| !self.password)?;
+                edit_state::set_clipboard_text(&content[selection.clone()]);
+                if key == KeyboardKey::KEY_C {
+                    return None;
+                }
+                content.replace_range(selection.clone(), "");
+                caret = selection.start;
+                anchor = None;
+            }
+            KeyboardKey::KEY_V if key_event.ctrl_down => {
+                inserted = Some(edit_state::clipboard_text());
+            }
+            KeyboardKey::KEY_BACKSPACE | KeyboardKey::KEY_DELETE => {
+                let range = selection.clone().unwrap_or_else(|| {
+                    if key == KeyboardKey::KEY_BACKSPACE {
+                        edit_state::prev_boundary(&content, caret)..caret
+                    } else {
+                        caret..edit_state::next_boundary(&content, caret)
+                    }
+                });
+                if range.is_empty() {
+                    return None;
+                }
+                content.replace_range(range.clone(), "");
+                caret = range.start;
+                anchor = None;
+            }
+            _ => {
+                if key_event.ctrl_down {
+                    return None;
+                }
+                let mut c = keyboard_key_to_char(key)?;
+                if key_event.shift_down {
+                    c = shift_character(c);
+                }
+                inserted = Some(c.to_string());
+            }
+        }
+        if let Some(text) = inserted {
+            // Typed or pasted text replaces the selection, within the filter and max_length
+            let range = selection.unwrap_or(caret..caret);
+            content.replace_range(range.clone(), "");
+            let room = self
+                .max_length
+                .map_or(usize::MAX, |max_length| max_length.saturating_sub(content.chars().count()));
+            let text = text
+                .chars()
+                .filter(|c| !c.is_control() && (self.filter)(*c))
+                .take(room)
+                .collect::<String>();
+            if text.is_empty() {
+                return None;
+            }
+            content.insert_str(range.start, &text);
+            if !(self.validator)(&content) {
+                return None;
+            }
+            caret = range.start + text.len();
+            anchor = None;
+        }
+        let changed = content != self.content;
+        if changed && self.read_only {
+            return None;
+        }
+        with_edit_state(&id, |state| {
+            state.caret = Some(caret);
+            state.anchor = anchor;
+            if !self.controlled {
+                state.text = Some(content.clone());
+            }
+        });
+        changed.then_some(content)
+    }
+
+    fn bullet(&self) -> &'static str {
+        if font::has_glyph(&self.font, '•', self.font_size) { "•" } else { "*" }
+    }
+
+    /// Text to draw and its colour
+    fn display_text(&self) -> (String, Color) {
+        let (text, color) = if self.content.is_empty() {
+            (self.placeholder.clone(), self.placeholder_color)
+        } else if self.password {
+            (self.bullet().repeat(graphemes(&self.content).len()), self.text_color)
         } else {
-            format!("{} {}", current_row, word)
+            (self.content.clone(), self.text_color)
         };
-        let text_width;
-        unsafe {
-            let c_text = CString::new(test_row.as_str()).unwrap();
-            text_width = raylib::ffi::MeasureText(c_text.as_ptr(), font_size);
+        if self.disabled {
+            (text, color.fade(0.5))
+        } else {
+            (text, color)
         }
-        if text_width <= max_width {
-            current_row = test_row;
+    }
+
+    /// Index in the drawn text of an index in the content
+    fn display_index(&self, index: usize) -> usize {
+        if self.content.is_empty() {
+            0
+        } else if self.password {
+            graphemes(&self.content[..index]).len() * self.bullet().len()
         } else {
-            if !current_row.is_empty() {
-                rows.push(current_row);
-            }
-            current_row = word.to_string();
+            index
         }
     }
-    if !current_row.is_empty() {
-        rows.push(current_row);
+
+    /// Index in the content of an index in the drawn text
+    fn content_index(&self, index: usize) -> usize {
+        if self.content.is_empty() {
+            0
+        } else if self.password {
+            graphemes(&self.content)
+                .iter()
+                .take(index / self.bullet().len())
+                .map(|g| g.len())
+                .sum()
+        } else {
+            index
+        }
+    }
+
+    /// x offset of drawn text index `index` in row `line`
+    fn line_x(&self, line: usize, index: usize) -> i32 {
+        let range = &self.lines[line];
+        let index = index.clamp(range.start, range.end) - range.start;
+        font::caret_x(&self.font, &self.display[range.clone()], self.font_size, index)
+    }
+
+    fn build_rows(&mut self, color: Color, max_width: Option<i32>) -> Vec<Component> {
+        let lines = text_wrap::wrap_lines(
+            &self.display,
+            &self.font,
+            self.font_size,
+            max_width,
+            self.white_space,
+        );
+        self.lines = lines.iter().map(|line| line.range.clone()).collect();
+        self.texts = lines
+            .iter()
+            .map(|line| {
+                let text = line.pieces.iter().map(|piece| piece.text.as_str()).collect::<String>();
+                RawText::new(&text, &self.font, self.font_size, self.layout.padding, color)
+            })
+            .collect();
+        self.texts.iter().map(|text| text.clone() as Component).collect()
     }
-    rows
 }
 
 impl Base for TextInput {
@@ -219,7 +587,41 @@ impl Base for TextInput {
         &self,
         draw_handle: &mut RaylibDrawHandle,
     ) -> Vec<AbsoluteDraw> {
-        return self.layout.draw(draw_handle);
+        let abs_draws = self.layout.draw(draw_handle);
+        let id = self.get_id();
+        if self.disabled || focused_id().as_deref() != Some(id.as_str()) {
+            return abs_draws;
+        }
+        // Rows scrolled partly out of view aren't drawn, neither is their selection or caret
+        if let Some(selection) = edit_state::selection(&id, &self.content) {
+            let start = self.display_index(selection.start);
+            let end = self.display_index(selection.end);
+            for (line, (range, text)) in self.lines.iter().zip(self.texts.iter()).enumerate() {
+                let text = text.borrow();
+                if text.overflowed || range.end < start || range.start > end {
+                    continue;
+                }
+                let (x0, x1) = (self.line_x(line, start), self.line_x(line, end));
+                draw_handle.draw_rectangle(
+                    text.pos.0 + x0.min(x1),
+                    text.pos.1,
+                    (x1 - x0).abs(),
+                    self.font_size,
+                    paint::faded(self.selection_color),
+                );
+            }
+        }
+        if !self.read_only {
+            let caret = self.display_index(edit_state::caret(&id, &self.content));
+            if let Some(line) = self.lines.iter().rposition(|range| range.start <= caret) {
+                let text = self.texts[line].borrow();
+                if !text.overflowed {
+                    let x = text.pos.0 + self.line_x(line, caret);
+                    draw_handle.draw_rectangle(x, text.pos.1, 2, self.font_size, paint::faded(self.caret_color));
+                }
+            }
+        }
+        abs_draws
     }
 
     fn get_children(&self) -> Vec<Rc<RefCell<dyn Base>>> {
@@ -227,6 +629,9 @@ impl Base for TextInput {
     }
 
     fn get_mouse_event_handlers(&self, mouse_event: MouseEvent) -> Vec<String> {
+        if self.disabled {
+            return vec![];
+        }
         let layout = &self.layout;
         let mouse_pos = mouse_event.pos;
         let max_x = layout.pos.0 + layout.draw_dim.0;
@@ -251,13 +656,48 @@ impl Base for TextInput {
     }
 
     fn execute_on_click(&self, mouse_event: MouseEvent) -> bool {
+        let hit_line = self.texts.iter().position(|text| {
+            let text = text.borrow();
+            !text.overflowed
+                && mouse_event.pos.1 >= text.pos.1
+                && mouse_event.pos.1 < text.pos.1 + self.font_size
+        });
+        if let Some(line) = hit_line {
+            let range = self.lines[line].clone();
+            let x = mouse_event.pos.0 - self.texts[line].borrow().pos.0;
+            let index = font::index_at_x(&self.font, &self.display[range.clone()], self.font_size, x);
+            let caret = self.content_index(range.start + index);
+            with_edit_state(&self.get_id(), |state| {
+                state.caret = Some(caret);
+                state.anchor = None;
+            });
+        }
         let mut user_fun = self.layout.on_click.borrow_mut();
         user_fun(mouse_event)
     }
 
     fn execute_on_key(&self, key_event: KeyEvent) -> bool {
-        let mut key_fn = self.layout.on_key.borrow_mut();
-        key_fn(key_event)
+        {
+            let mut key_fn = self.layout.on_key.borrow_mut();
+            if !key_fn(key_event) {
+                return false;
+            }
+        }
+        if self.disabled {
+            return true;
+        }
+        if matches!(key_event.key, Some(KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER)) {
+            if !self.read_only {
+                let mut on_submit = self.on_submit.borrow_mut();
+                on_submit(self.content.clone());
+            }
+            return true;
+        }
+        if let Some(content) = self.edit(key_event) {
+            let mut on_change = self.on_change.borrow_mut();
+            on_change(content);
+        }
+        true
     }
 
     fn get_key_event_handlers(&self, _key_event: KeyEvent) -> Vec<String> {
@@ -268,40 +708,44 @@ impl Base for TextInput {
         self.layout.on_key.clone()
     }
 
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        let text_style = inherited.merge(&self.own_style);
+        if let Some(color) = text_style.color {
+            self.text_color = color;
+        }
+        if let Some(font) = text_style.font {
+            self.font = font;
+        }
+        if let Some(size) = text_style.font_size {
+            self.font_size = size;
+        }
+    }
+
     fn is_focusable(&self) -> bool {
-        true
+        !self.disabled
     }
 
     fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
+        let (text, color) = self.display_text();
+        self.display = text;
+        self.layout.children = self.build_rows(color, None);
         let layout = &mut self.layout;
-        layout.children = vec![RawText::new(
-            &self.content,
-            self.font_size,
-            layout.padding,
-            self.text_color,
-        )];
-        let content_width = unsafe {
-            let c_text = CString::new(self.content.as_str()).unwrap();
-            raylib::ffi::MeasureText(c_text.as_ptr(), self.font_size)
-                + layout.padding.0
-                + layout.padding.2
-        };
+        let content_width = layout
+            .children
+            .iter()
+            .map(|child| child.borrow().get_draw_dim().0)
+            .max()
+            .unwrap_or(0);
         let (mut draw_width, mut draw_height) =
-            crate::ui::common::get_draw_dim(layout.dim, parent_draw_dim, &layout.children, layout.direction,layout.border_width);
+            crate::ui::common::get_draw_dim(layout.dim, parent_draw_dim, &layout.children, layout.direction,layout.border_widths);
 
-        if self.wrap {
+        if self.wrap && self.white_space.wraps() {
             let max_width = draw_width - layout.padding.0 - layout.padding.2;
-            if content_width > max_width {
-                let text_rows = get_text_rows(&self.content, max_width, self.font_size);
-                layout.children = text_rows
-                    .iter()
-                    .map(|row| {
-                        RawText::new(row, self.font_size, layout.padding, self.text_color)
-                            as Rc<RefCell<dyn Base>>
-                    })
-                    .collect();
+            if content_width > draw_width {
+                self.layout.children = self.build_rows(color, Some(max_width));
             }
         }
+        let layout = &mut self.layout;
 
         if layout.dim.0 == Length::FIT {
             draw_width = layout
@@ -388,4 +832,7 @@ impl Base for TextInput {
     fn get_position(&self) -> super::common::Position {
         self.layout.get_position()
     }
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
 }
@@ -1,17 +1,43 @@
-use std::{cell::RefCell, ffi::CString, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 use raylib::{color::Color, prelude::RaylibDraw};
 
-use crate::ui::{common::{Alignment, Base, Length, MouseEvent, tabbed_print}, layout::{self, Layout, LayoutProps}, raw_text::RawText};
+use crate::ui::{
+    common::{
+        Alignment, Base, Component, Length, MouseEvent, Position, TextAlign, TextOverflow,
+        tabbed_print,
+    },
+    font::{self, FontSpec},
+    layout::{self, Layout, LayoutProps},
+    paint::Shadow,
+    raw_text::RawText,
+    shaping,
+    span::{StyledRun, TextSpan},
+    style::{Style, TextStyle},
+    text_wrap::{self, Line, LinePiece, WhiteSpace},
+    theme,
+    tooltip::Tooltip,
+};
 
 use colored::Colorize;
 #[derive(Clone)]
 pub struct TextLayoutProps {
     pub layout: LayoutProps,
+    pub font: FontSpec,
     pub font_size: i32,
     pub wrap: bool,
-    pub content: String,
+    pub white_space: WhiteSpace,
+    pub text_align: TextAlign,
+    /// Multiple of the font size each line takes up
+    pub line_height: f32,
+    pub letter_spacing: i32,
+    pub max_lines: Option<usize>,
+    pub text_overflow: TextOverflow,
+    pub spans: Vec<TextSpan>,
     pub text_color: Color,
+    /// Text properties set on this builder, they win over inherited ones
+    pub own_style: TextStyle,
+    pub on_link_click: Rc<RefCell<dyn FnMut(String)>>,
 }
 
 impl TextLayoutProps {
@@ -26,27 +52,87 @@ impl TextLayoutProps {
         .flex(1.0)
         .overflow_x(false)
         .overflow_y(false);
-        return Self { layout: layout, font_size: 24, wrap: true, content: String::from(""), text_color: Color::BLACK };
+        let theme = theme::current();
+        return Self {
+            layout: layout,
+            font: FontSpec::default(),
+            font_size: theme.typography.lg,
+            wrap: true,
+            white_space: WhiteSpace::PreLine,
+            text_align: TextAlign::Left,
+            line_height: 1.0,
+            letter_spacing: 0,
+            max_lines: None,
+            text_overflow: TextOverflow::Clip,
+            spans: vec![],
+            text_color: theme.palette.text,
+            own_style: TextStyle::default(),
+            on_link_click: Rc::new(RefCell::new(|_url| {})),
+        };
     }
     pub fn content(mut self, content: &str) -> Self {
-        self.content = content.into();
+        self.spans = vec![TextSpan::new(content)];
+        self
+    }
+    pub fn spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = spans;
         self
     }
-    pub fn font_size(mut self, size: i32) -> Self {
-        self.font_size = size;
+    pub fn on_link_click(mut self, f: Box<dyn FnMut(String)>) -> Self {
+        self.on_link_click = Rc::new(RefCell::new(f));
+        self
+    }
+    pub fn font(mut self, font: FontSpec) -> Self {
+        self.own_style.font = Some(font.clone());
+        self.font = font;
+        self
+    }
+    pub fn font_size(mut self, size: impl Into<i32>) -> Self {
+        self.font_size = size.into();
+        self.own_style.font_size = Some(self.font_size);
         self
     }
     pub fn wrap(mut self, wrap: bool) -> Self {
         self.wrap = wrap;
         self
     }
+    pub fn white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.white_space = white_space;
+        self
+    }
+    /// Horizontal placement of each line, this also sets `cross_align`
+    pub fn text_align(mut self, text_align: TextAlign) -> Self {
+        self.text_align = text_align;
+        self.layout = self.layout.cross_align(match text_align {
+            TextAlign::Left | TextAlign::Justify => Alignment::Start,
+            TextAlign::Center => Alignment::Center,
+            TextAlign::Right => Alignment::End,
+        });
+        self
+    }
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+    pub fn letter_spacing(mut self, letter_spacing: i32) -> Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+    pub fn text_overflow(mut self, text_overflow: TextOverflow) -> Self {
+        self.text_overflow = text_overflow;
+        self
+    }
 
     pub fn dim(mut self, dim: (Length, Length)) -> Self {
         let layout = self.layout.dim(dim);
         self.layout = layout;
         return self;
     }
-    pub fn bg_color(mut self, color: Color) -> Self {
+    pub fn bg_color(mut self, color: impl Into<Color>) -> Self {
         let layout = self.layout.bg_color(color);
         self.layout = layout;
         self
@@ -66,7 +152,7 @@ impl TextLayoutProps {
         self.layout = layout;
         self
     }
-    pub fn gap(mut self, gap: i32) -> Self {
+    pub fn gap(mut self, gap: impl Into<i32>) -> Self {
         let layout = self.layout.gap(gap);
         self.layout = layout;
         self
@@ -87,8 +173,9 @@ impl TextLayoutProps {
         self
     }
 
-    pub fn text_color(mut self, color: Color) -> Self {
-        self.text_color = color;
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.text_color = color.into();
+        self.own_style.color = Some(self.text_color);
         self
     }
     pub fn overflow_x(mut self, overflow: bool) -> Self {
@@ -106,20 +193,73 @@ impl TextLayoutProps {
         self.layout = layout;
         self
     }
-    pub fn border_color(mut self, border_color: Color) -> Self {
+    pub fn border_color(mut self, border_color: impl Into<Color>) -> Self {
         let layout = self.layout.border_color(border_color);
         self.layout = layout;
         self
     }
+    pub fn border_radius(mut self, radius: impl Into<i32>) -> Self {
+        let layout = self.layout.border_radius(radius);
+        self.layout = layout;
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        let layout = self.layout.shadow(shadow);
+        self.layout = layout;
+        self
+    }
+    pub fn set_position(mut self, position: Position) -> Self {
+        let layout = self.layout.set_position(position);
+        self.layout = layout;
+        self
+    }
+    pub fn align_self(mut self, align: Alignment) -> Self {
+        let layout = self.layout.align_self(align);
+        self.layout = layout;
+        self
+    }
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        let layout = self.layout.z_index(z_index);
+        self.layout = layout;
+        self
+    }
+    pub fn tooltip(mut self, tooltip: impl Into<Tooltip>) -> Self {
+        let layout = self.layout.tooltip(tooltip);
+        self.layout = layout;
+        self
+    }
+    pub fn style(mut self, style: &Style) -> Self {
+        if let Some(color) = style.text.color {
+            self = self.text_color(color);
+        }
+        if let Some(font) = &style.text.font {
+            self = self.font(font.clone());
+        }
+        if let Some(size) = style.text.font_size {
+            self = self.font_size(size);
+        }
+        self.layout = style.apply_box(self.layout);
+        self
+    }
     
     pub fn build(self) -> Rc<RefCell<TextLayout>> {
         let layout = self.layout;
         return Rc::new(RefCell::new(TextLayout {
             layout: layout.get_layout(),
+            font: self.font,
             font_size: self.font_size,
             wrap: self.wrap,
-            content: self.content,
-            text_color: self.text_color
+            white_space: self.white_space,
+            text_align: self.text_align,
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing,
+            max_lines: self.max_lines,
+            text_overflow: self.text_overflow,
+            spans: self.spans,
+            text_color: self.text_color,
+            own_style: self.own_style,
+            on_link_click: self.on_link_click,
+            links: vec![],
         }))
     }
     
@@ -128,47 +268,178 @@ impl TextLayoutProps {
 
 pub struct TextLayout {
     layout: Layout,
+    font: FontSpec,
     font_size: i32,
     wrap: bool,
-    content: String,
-    text_color: Color
+    white_space: WhiteSpace,
+    text_align: TextAlign,
+    line_height: f32,
+    letter_spacing: i32,
+    max_lines: Option<usize>,
+    text_overflow: TextOverflow,
+    spans: Vec<TextSpan>,
+    text_color: Color,
+    own_style: TextStyle,
+    on_link_click: Rc<RefCell<dyn FnMut(String)>>,
+    /// Drawn link fragments, hit tested on click
+    links: Vec<(Rc<RefCell<RawText>>, String)>,
+}
+
+
+
+fn raw_text_for(
+    run: &StyledRun,
+    text: &str,
+    links: &mut Vec<(Rc<RefCell<RawText>>, String)>,
+) -> Component {
+    let raw_text = RawText::new(text, &run.font, run.font_size, (0, 0, 0, 0), run.color);
+    {
+        let mut raw_text = raw_text.borrow_mut();
+        raw_text.underline = run.underline;
+        raw_text.background = run.background;
+        raw_text.letter_spacing = run.letter_spacing;
+    }
+    if let Some(url) = &run.link {
+        links.push((raw_text.clone(), url.clone()));
+    }
+    raw_text as Component
+}
+
+/// Splits a line at its spaces, dropping them, for justified rows
+fn get_line_words(pieces: &[LinePiece]) -> Vec<Vec<LinePiece>> {
+    let mut words: Vec<Vec<LinePiece>> = vec![vec![]];
+    for piece in pieces {
+        for (idx, part) in piece.text.split(' ').enumerate() {
+            if idx > 0 && !words.last().unwrap().is_empty() {
+                words.push(vec![]);
+            }
+            if !part.is_empty() {
+                words.last_mut().unwrap().push(LinePiece {
+                    run: piece.run,
+                    text: part.to_string(),
+                    source: piece.source.clone(),
+                });
+            }
+        }
+    }
+    words.retain(|word| !word.is_empty());
+    words
 }
 
 impl TextLayout {
     pub fn get_builder()-> TextLayoutProps{
         return TextLayoutProps::new();
     }
-}
 
-
-fn get_text_rows(content: &str, max_width: i32, font_size: i32) -> Vec<String> {
-    let mut rows = vec![];
-    let words: Vec<&str> = content.split_whitespace().collect();
-    let mut current_row = String::new();
-    for word in words {
-        let test_row = if current_row.is_empty() {
-            word.to_string()
-        } else {
-            format!("{} {}", current_row, word)
-        };
-        let text_width;
-        unsafe {
-            let c_text = CString::new(test_row.as_str()).unwrap();
-            text_width = raylib::ffi::MeasureText(c_text.as_ptr(), font_size);
+    fn get_runs(&self) -> Vec<StyledRun> {
+        self.spans
+            .iter()
+            .map(|span| {
+                let mut run = span.resolve(&self.font, self.font_size, self.text_color);
+                run.letter_spacing = self.letter_spacing;
+                run
+            })
+            .collect()
+    }
+
+    /// Wrapped lines with `max_lines` and ellipsis truncation applied
+    fn get_lines(&self, runs: &[StyledRun], max_width: Option<i32>) -> Vec<Line> {
+        let wrap_width = if self.wrap { max_width } else { None };
+        let mut lines = text_wrap::wrap_runs(runs, wrap_width, self.white_space);
+        let truncated = self.max_lines.is_some_and(|max_lines| lines.len() > max_lines);
+        if let Some(max_lines) = self.max_lines {
+            lines.truncate(max_lines);
         }
-        if text_width <= max_width {
-            current_row = test_row;
-        } else {
-            if !current_row.is_empty() {
-                rows.push(current_row);
+        if self.text_overflow == TextOverflow::Ellipsis {
+            let ellipsis = if font::has_glyph(&self.font, '…', self.font_size) { "…" } else { "..." };
+            let count = lines.len();
+            for (idx, line) in lines.iter_mut().enumerate() {
+                let force = truncated && idx == count - 1;
+                line.pieces = text_wrap::ellipsize(
+                    runs,
+                    &line.pieces,
+                    max_width.unwrap_or(i32::MAX),
+                    ellipsis,
+                    force,
+                );
             }
-            current_row = word.to_string();
         }
+        lines
     }
-    if !current_row.is_empty() {
-        rows.push(current_row);
+
+    /// One row layout per line, each piece becoming a `RawText`
+    fn build_rows(
+        &self,
+        runs: &[StyledRun],
+        lines: Vec<Line>,
+        max_width: Option<i32>,
+        links: &mut Vec<(Rc<RefCell<RawText>>, String)>,
+    ) -> Vec<Component> {
+        lines
+            .into_iter()
+            .map(|line| {
+                let line_font_size = line
+                    .pieces
+                    .iter()
+                    .map(|piece| runs[piece.run].font_size)
+                    .max()
+                    .unwrap_or(self.font_size);
+                let height = if self.line_height == 1.0 {
+                    Length::FIT
+                } else {
+                    Length::FIXED((line_font_size as f32 * self.line_height).round() as i32)
+                };
+                let mut row = Layout::get_row_builder()
+                    .dim((Length::FIT, height))
+                    .cross_align(Alignment::Center)
+                    .overflow_y(false);
+
+                let words = get_line_words(&line.pieces);
+                let justify_width = max_width.filter(|_| {
+                    self.text_align == TextAlign::Justify && !line.hard_end && words.len() > 1
+                });
+                let mut children = if let Some(max_width) = justify_width {
+                    let words = words
+                        .iter()
+                        .map(|word| {
+                            let children = word
+                                .iter()
+                                .map(|piece| raw_text_for(&runs[piece.run], &piece.text, links))
+                                .collect::<Vec<_>>();
+                            let word = Layout::get_row_builder()
+                                .dim((Length::FIT, Length::FIT))
+                                .overflow_y(false)
+                                .children(children)
+                                .build();
+                            word.borrow_mut().set_raw_dim((0, 0));
+                            word as Component
+                        })
+                        .collect::<Vec<_>>();
+                    let words_width = words
+                        .iter()
+                        .map(|word| word.borrow().get_draw_dim().0)
+                        .sum::<i32>();
+                    let gap = (max_width - words_width) / (words.len() as i32 - 1);
+                    row = row.dim((Length::FIXED(max_width), height)).gap(gap.max(0));
+                    words
+                } else {
+                    line.pieces
+                        .iter()
+                        .map(|piece| raw_text_for(&runs[piece.run], &piece.text, links))
+                        .collect::<Vec<_>>()
+                };
+                // Each piece reorders its own text, the pieces of a right to left line run backwards
+                let line_text = line.pieces.iter().map(|piece| piece.text.as_str()).collect::<String>();
+                if shaping::is_rtl_paragraph(&line_text) {
+                    children.reverse();
+                }
+                let row = row.children(children).build();
+                // Rows are rebuilt after the measure pass, so size them right away
+                row.borrow_mut().set_raw_dim((0, 0));
+                row as Component
+            })
+            .collect()
     }
-    rows
 }
 
 
@@ -205,6 +476,22 @@ impl Base for TextLayout{
         self.layout.on_click.clone()
     }
 
+    fn execute_on_click(&self, mouse_event: MouseEvent) -> bool {
+        let mouse_pos = mouse_event.pos;
+        for (raw_text, url) in self.links.iter() {
+            let raw_text = raw_text.borrow();
+            let (x, y) = raw_text.get_draw_pos();
+            let (w, h) = raw_text.get_draw_dim();
+            if mouse_pos.0 >= x && mouse_pos.0 <= x + w && mouse_pos.1 >= y && mouse_pos.1 <= y + h {
+                let mut f = self.on_link_click.borrow_mut();
+                f(url.clone());
+                return false;
+            }
+        }
+        let mut f = self.layout.on_click.borrow_mut();
+        f(mouse_event)
+    }
+
     fn get_key_event_handlers(&self, key_event: super::common::KeyEvent) -> Vec<String> {
         vec![]
     }
@@ -213,6 +500,18 @@ impl Base for TextLayout{
         Rc::new(RefCell::new(|_key_event| true))
     }
 
+    fn inherit_text_style(&mut self, inherited: &TextStyle) {
+        let text_style = inherited.merge(&self.own_style);
+        if let Some(color) = text_style.color {
+            self.text_color = color;
+        }
+        if let Some(font) = text_style.font {
+            self.font = font;
+        }
+        if let Some(size) = text_style.font_size {
+            self.font_size = size;
+        }
+    }
     fn get_paddings(&self) -> (i32,i32,i32,i32) {
         self.layout.get_paddings()
     }
@@ -220,39 +519,39 @@ impl Base for TextLayout{
     fn set_raw_dim(&mut self, parent_draw_dim: (i32, i32)) {
         let layout_paddings = self.layout.padding;
 
+        let runs = self.get_runs();
+        let mut links = vec![];
+
+        let lines = self.get_lines(&runs, None);
+        let children = self.build_rows(&runs, lines, None, &mut links);
         let layout = &mut self.layout;
-        layout.children = vec![RawText::new(
-            &self.content,
-            self.font_size,
-            (0,0,0,0),
-            // layout.padding,
-            self.text_color,
-        )];
-        let content_width = unsafe {
-            let c_text = CString::new(self.content.as_str()).unwrap();
-            raylib::ffi::MeasureText(c_text.as_ptr(), self.font_size)
-                // + layout.padding.0
-                // + layout.padding.2
-        };
+        layout.children = children;
+        if layout.children.is_empty() {
+            // Empty text still takes up a line
+            layout.children = vec![RawText::new("", &self.font, self.font_size, (0, 0, 0, 0), self.text_color)];
+        }
+        let content_width = layout
+            .children
+            .iter()
+            .map(|child| child.borrow().get_draw_dim().0)
+            .max()
+            .unwrap_or(0);
         let (mut draw_width, mut draw_height) =
-            crate::ui::common::get_draw_dim(layout.dim, parent_draw_dim, &layout.children, layout.direction, layout.border_width);
-
-        if self.wrap {
-            let max_width = draw_width - layout.padding.0 - layout.padding.2;
-            if content_width > max_width {
-                let text_rows = get_text_rows(&self.content, max_width, self.font_size);
-                layout.children = text_rows
-                    .iter()
-                    .map(|row| {
-                        RawText::new(row, self.font_size, 
-                            (0,0,0,0)
-                            // layout.padding
-                            , self.text_color)
-                            as Rc<RefCell<dyn Base>>
-                    })
-                    .collect();
+            crate::ui::common::get_draw_dim(layout.dim, parent_draw_dim, &layout.children, layout.direction, layout.border_widths);
+
+        let max_width = draw_width - layout.padding.0 - layout.padding.2;
+        let wraps = self.wrap && self.white_space.wraps();
+        let ellipsizes = self.text_overflow == TextOverflow::Ellipsis;
+        if content_width > max_width && (wraps || ellipsizes) {
+            links.clear();
+            let lines = self.get_lines(&runs, Some(max_width));
+            let children = self.build_rows(&runs, lines, Some(max_width), &mut links);
+            if !children.is_empty() {
+                self.layout.children = children;
             }
         }
+        self.links = links;
+        let layout = &mut self.layout;
 
         if layout.dim.0 == Length::FIT {
             draw_width = layout
@@ -260,7 +559,7 @@ impl Base for TextLayout{
                 .iter()
                 .map(|child| child.borrow().get_draw_dim().0)
                 .max()
-                .unwrap()
+                .unwrap_or(0)
         }
         if let Length::FIT_PER(p) = layout.dim.0 {
             draw_width = layout
@@ -268,7 +567,7 @@ impl Base for TextLayout{
                 .iter()
                 .map(|child| child.borrow().get_draw_dim().0)
                 .max()
-                .unwrap();
+                .unwrap_or(0);
             draw_width = (draw_width * p)/100
         }
         if layout.dim.1 == Length::FIT {
@@ -285,7 +584,7 @@ impl Base for TextLayout{
                 .iter()
                 .map(|child| child.borrow().get_draw_dim().1)
                 .max()
-                .unwrap();
+                .unwrap_or(0);
             draw_height = (draw_height * p)/100
         }
 
@@ -293,6 +592,7 @@ impl Base for TextLayout{
             draw_width + layout_paddings.0 + layout_paddings.2,
             draw_height + layout_paddings.1 + layout_paddings.3
         );
+        layout.apply_stretch();
     }
 
     fn get_draw_dim(&self) -> (i32, i32) {
@@ -367,4 +667,24 @@ impl Base for TextLayout{
     fn get_position(&self) -> super::common::Position {
         self.layout.get_position()
     }
+
+    fn get_align_self(&self) -> Option<Alignment> {
+        self.layout.get_align_self()
+    }
+
+    fn get_baseline(&self) -> Option<i32> {
+        self.layout.get_baseline()
+    }
+
+    fn set_stretch(&mut self, stretch: (Option<i32>, Option<i32>)) {
+        self.layout.set_stretch(stretch);
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.layout.get_z_index()
+    }
+
+    fn get_tooltip(&self) -> Option<Tooltip> {
+        self.layout.get_tooltip()
+    }
 }
\ No newline at end of file
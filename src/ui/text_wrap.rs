@@ -0,0 +1,727 @@
+use std::ops::Range;
+
+use raylib::color::Color;
+
+use crate::ui::{
+    font::{self, FontSpec},
+    span::StyledRun,
+};
+
+/// How spaces and newlines are treated, after CSS `white-space`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteSpace {
+    /// Collapse spaces and newlines, wrap
+    Normal,
+    /// Collapse spaces and newlines, never wrap
+    NoWrap,
+    /// Keep spaces and newlines, never wrap
+    Pre,
+    /// Keep spaces and newlines, wrap
+    PreWrap,
+    /// Collapse spaces, keep newlines, wrap
+    PreLine,
+}
+
+impl WhiteSpace {
+    pub fn collapses_spaces(&self) -> bool {
+        matches!(self, WhiteSpace::Normal | WhiteSpace::NoWrap | WhiteSpace::PreLine)
+    }
+    pub fn keeps_newlines(&self) -> bool {
+        matches!(self, WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::PreLine)
+    }
+    pub fn wraps(&self) -> bool {
+        matches!(self, WhiteSpace::Normal | WhiteSpace::PreWrap | WhiteSpace::PreLine)
+    }
+}
+
+/// Part of a wrapped line drawn with a single run's style
+#[derive(Debug, Clone)]
+pub struct LinePiece {
+    pub run: usize,
+    pub text: String,
+    /// Byte range the text came from in the runs' text joined together
+    pub source: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub pieces: Vec<LinePiece>,
+    /// Byte range of the line in the runs' text joined together. Spaces dropped at a wrap
+    /// and newlines belong to no line.
+    pub range: Range<usize>,
+    /// Ends at a newline or the end of the text rather than at a wrap
+    pub hard_end: bool,
+}
+
+const TAB_WIDTH: usize = 4;
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+const SOFT_HYPHEN: char = '\u{AD}';
+const ZWJ: char = '\u{200D}';
+
+/// Whitespace that must not be broken or collapsed (NBSP, narrow NBSP, figure space, word joiner)
+fn is_glue(c: char) -> bool {
+    matches!(c, '\u{A0}' | '\u{202F}' | '\u{2007}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Marks and modifiers that attach to the previous character
+fn is_extending(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F     // Combining diacritics
+        | 0x0483..=0x0489   // Cyrillic
+        | 0x0591..=0x05BD | 0x05BF | 0x05C1..=0x05C2 | 0x05C4..=0x05C5 | 0x05C7 // Hebrew points
+        | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 // Arabic marks
+        | 0x0900..=0x0903 | 0x093A..=0x094F | 0x0951..=0x0957 | 0x0962..=0x0963 // Devanagari signs
+        | 0x0981..=0x0983 | 0x09BC | 0x09BE..=0x09CD | 0x09D7 // Bengali signs
+        | 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E // Thai
+        | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F   // Variation selectors
+        | 0xFE20..=0xFE2F
+        | 0x1F3FB..=0x1F3FF // Emoji skin tones
+        | 0xE0020..=0xE007F // Emoji tags
+        | 0x200C..=0x200D
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Approximate extended grapheme clusters: a base character followed by combining marks,
+/// ZWJ sequences and flag pairs
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut clusters = vec![];
+    let mut start = 0;
+    let mut prev: Option<char> = None;
+    let mut regional_count = 0;
+    for (idx, c) in text.char_indices() {
+        if let Some(prev) = prev {
+            let joins = is_extending(c)
+                || prev == ZWJ
+                || (prev == '\r' && c == '\n')
+                || (is_regional_indicator(c) && regional_count % 2 == 1);
+            if !joins {
+                clusters.push(&text[start..idx]);
+                start = idx;
+                regional_count = 0;
+            }
+        }
+        if is_regional_indicator(c) {
+            regional_count += 1;
+        }
+        prev = Some(c);
+    }
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+/// Simplified UAX #14 line breaking classes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BreakClass {
+    Space,
+    Glue,
+    Ideographic,
+    Open,
+    Close,
+    Hyphen,
+    ZeroWidth,
+    SoftHyphen,
+    Other,
+}
+
+fn break_class(c: char) -> BreakClass {
+    match c {
+        ' ' => BreakClass::Space,
+        ZERO_WIDTH_SPACE => BreakClass::ZeroWidth,
+        SOFT_HYPHEN => BreakClass::SoftHyphen,
+        c if is_glue(c) => BreakClass::Glue,
+        '-' | '\u{2010}' | '\u{2013}' | '\u{2014}' | '/' => BreakClass::Hyphen,
+        '(' | '[' | '{' | '\u{3008}' | '\u{300A}' | '\u{300C}' | '\u{300E}' | '\u{3010}'
+        | '\u{FF08}' | '\u{201C}' | '\u{2018}' => BreakClass::Open,
+        ')' | ']' | '}' | ',' | '.' | ':' | ';' | '!' | '?' | '%' | '\u{3001}' | '\u{3002}'
+        | '\u{3009}' | '\u{300B}' | '\u{300D}' | '\u{300F}' | '\u{3011}' | '\u{FF09}'
+        | '\u{FF0C}' | '\u{FF0E}' | '\u{FF01}' | '\u{FF1F}' | '\u{201D}' | '\u{2019}'
+        | '\u{30FC}' | '\u{3005}' => BreakClass::Close,
+        c if matches!(c as u32,
+            0x1100..=0x115F     // Hangul Jamo
+            | 0x2E80..=0x303E   // CJK radicals, punctuation
+            | 0x3041..=0x33FF   // Kana, CJK compatibility
+            | 0x3400..=0x4DBF   // CJK extension A
+            | 0x4E00..=0x9FFF   // CJK unified ideographs
+            | 0xAC00..=0xD7A3   // Hangul syllables
+            | 0xF900..=0xFAFF
+            | 0xFF01..=0xFF60   // Fullwidth forms
+            | 0x1F300..=0x1FAFF // Emoji
+            | 0x20000..=0x3FFFD
+        ) => BreakClass::Ideographic,
+        _ => BreakClass::Other,
+    }
+}
+
+/// Whether a line may break between clusters starting with `before` and `after`
+fn can_break_between(before: char, after: char) -> bool {
+    let (b, a) = (break_class(before), break_class(after));
+    match (b, a) {
+        (_, BreakClass::Space) | (BreakClass::Glue, _) | (_, BreakClass::Glue) => false,
+        (BreakClass::ZeroWidth, _) | (BreakClass::SoftHyphen, _) => true,
+        (_, BreakClass::ZeroWidth) | (_, BreakClass::Close) | (BreakClass::Open, _) => false,
+        // "well-known" breaks after the hyphen, "-5" keeps the sign with the number
+        (BreakClass::Hyphen, _) => after.is_alphabetic(),
+        (BreakClass::Ideographic, _) | (_, BreakClass::Ideographic) => true,
+        _ => false,
+    }
+}
+
+/// Width and leading spacing of text in a run's style, see [`font::measure_piece`]
+type Measure<'a> = &'a dyn Fn(&StyledRun, &str) -> (f32, f32);
+
+fn measure_font(run: &StyledRun, text: &str) -> (f32, f32) {
+    font::measure_piece(&run.font, text, run.font_size, run.letter_spacing)
+}
+
+#[derive(Debug, Clone)]
+struct Cluster {
+    run: usize,
+    text: String,
+    source: Range<usize>,
+    width: f32,
+    /// Put before the cluster when it follows another one on the line
+    spacing: f32,
+}
+
+impl Cluster {
+    fn first(&self) -> char {
+        self.text.chars().next().unwrap_or(' ')
+    }
+}
+
+/// Unbreakable content followed by the spaces after it
+#[derive(Debug, Default)]
+struct Segment {
+    clusters: Vec<Cluster>,
+    spaces: Vec<Cluster>,
+    /// Run of a soft hyphen to show if the line breaks here
+    hyphen: Option<usize>,
+}
+
+/// Character left by the `white_space` rules, with the run and bytes it came from
+#[derive(Debug, Clone)]
+struct SourceChar {
+    run: usize,
+    c: char,
+    source: Range<usize>,
+}
+
+/// A paragraph is the text between forced line breaks, `run` and `start` are used for
+/// empty lines
+struct Paragraph {
+    chars: Vec<SourceChar>,
+    run: usize,
+    start: usize,
+}
+
+/// Applies the `white_space` rules and splits the text at forced line breaks
+fn get_paragraphs(runs: &[StyledRun], white_space: WhiteSpace) -> Vec<Paragraph> {
+    let mut paragraphs = vec![Paragraph {
+        chars: vec![],
+        run: 0,
+        start: 0,
+    }];
+    let mut prev_cr = false;
+    let mut offset = 0;
+    for (run_idx, run) in runs.iter().enumerate() {
+        for (idx, c) in run.text.char_indices() {
+            let source = offset + idx..offset + idx + c.len_utf8();
+            let paragraph = paragraphs.last_mut().unwrap();
+            if c == '\n' && prev_cr {
+                prev_cr = false;
+                if paragraph.chars.is_empty() && paragraph.start == source.start {
+                    paragraph.start = source.end;
+                }
+                continue;
+            }
+            prev_cr = c == '\r';
+            let c = if c == '\r' { '\n' } else { c };
+            if c == '\n' && white_space.keeps_newlines() {
+                if white_space.collapses_spaces() {
+                    while paragraph.chars.last().is_some_and(|char| char.c == ' ') {
+                        paragraph.chars.pop();
+                    }
+                }
+                paragraphs.push(Paragraph {
+                    chars: vec![],
+                    run: run_idx,
+                    start: source.end,
+                });
+                continue;
+            }
+            let space = SourceChar {
+                run: run_idx,
+                c: ' ',
+                source: source.clone(),
+            };
+            if c.is_whitespace() && !is_glue(c) {
+                if white_space.collapses_spaces() {
+                    let at_start = paragraph.chars.is_empty();
+                    let after_space = paragraph.chars.last().is_some_and(|char| char.c == ' ');
+                    if !at_start && !after_space {
+                        paragraph.chars.push(space);
+                    }
+                } else if c == '\t' {
+                    paragraph.chars.extend(std::iter::repeat_n(space, TAB_WIDTH));
+                } else {
+                    paragraph.chars.push(space);
+                }
+                continue;
+            }
+            paragraph.chars.push(SourceChar { run: run_idx, c, source });
+        }
+        offset += run.text.len();
+    }
+    if white_space.collapses_spaces() {
+        for paragraph in paragraphs.iter_mut() {
+            while paragraph.chars.last().is_some_and(|char| char.c == ' ') {
+                paragraph.chars.pop();
+            }
+        }
+    }
+    paragraphs
+}
+
+fn get_clusters(runs: &[StyledRun], chars: &[SourceChar], measure: Measure) -> Vec<Cluster> {
+    let mut clusters = vec![];
+    let mut start = 0;
+    // Clusters never cross a style change
+    while start < chars.len() {
+        let run = chars[start].run;
+        let end = start + chars[start..].iter().take_while(|char| char.run == run).count();
+        let text = chars[start..end].iter().map(|char| char.c).collect::<String>();
+        let mut first = start;
+        for g in graphemes(&text) {
+            let last = first + g.chars().count() - 1;
+            let (width, spacing) = measure(&runs[run], g);
+            clusters.push(Cluster {
+                run,
+                text: g.to_string(),
+                source: chars[first].source.start..chars[last].source.end,
+                width,
+                spacing,
+            });
+            first = last + 1;
+        }
+        start = end;
+    }
+    clusters
+}
+
+fn get_segments(clusters: Vec<Cluster>) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = vec![Segment::default()];
+    let mut prev: Option<char> = None;
+    for cluster in clusters {
+        let c = cluster.first();
+        let segment = segments.last_mut().unwrap();
+        if c == ' ' {
+            segment.spaces.push(cluster);
+            prev = Some(c);
+            continue;
+        }
+        let starts_new = match prev {
+            Some(' ') => true,
+            Some(prev) => can_break_between(prev, c),
+            None => false,
+        };
+        let segment = if starts_new {
+            segments.push(Segment::default());
+            segments.last_mut().unwrap()
+        } else {
+            segment
+        };
+        match c {
+            SOFT_HYPHEN => segment.hyphen = Some(cluster.run),
+            ZERO_WIDTH_SPACE => {}
+            _ => segment.clusters.push(cluster),
+        }
+        prev = Some(c);
+    }
+    segments
+}
+
+fn push_piece(pieces: &mut Vec<LinePiece>, run: usize, text: &str, source: Range<usize>) {
+    match pieces.last_mut() {
+        Some(last) if last.run == run => {
+            last.text.push_str(text);
+            last.source.end = last.source.end.max(source.end);
+        }
+        _ => pieces.push(LinePiece {
+            run,
+            text: text.to_string(),
+            source,
+        }),
+    }
+}
+
+/// Pushes text that isn't in the source, like a hyphen or an ellipsis, after `pieces`
+fn push_added(pieces: &mut Vec<LinePiece>, run: usize, text: &str) {
+    let end = pieces.last().map_or(0, |piece| piece.source.end);
+    push_piece(pieces, run, text, end..end);
+}
+
+/// Width of a line `width` wide, or empty, once `clusters` are added to it
+fn joined_width(mut width: f32, mut empty: bool, clusters: &[Cluster]) -> f32 {
+    for cluster in clusters {
+        if !empty {
+            width += cluster.spacing;
+        }
+        width += cluster.width;
+        empty = false;
+    }
+    width
+}
+
+fn with_clusters(pieces: &[LinePiece], clusters: &[Cluster]) -> Vec<LinePiece> {
+    let mut pieces = pieces.to_vec();
+    for cluster in clusters {
+        push_piece(&mut pieces, cluster.run, &cluster.text, cluster.source.clone());
+    }
+    pieces
+}
+
+struct LineBuilder {
+    max_width: i32,
+    lines: Vec<Vec<LinePiece>>,
+    line: Vec<LinePiece>,
+    /// Width of `line`, kept up to date as clusters are added instead of measuring it again
+    width: f32,
+    /// Spaces after the last segment, only drawn if more content follows on the line
+    pending_spaces: Vec<Cluster>,
+    pending_hyphen: Option<usize>,
+}
+
+impl LineBuilder {
+    fn fits(&self, width: f32) -> bool {
+        width.ceil() as i32 <= self.max_width
+    }
+
+    /// Width of the line once `clusters` are added
+    fn width_with(&self, clusters: &[Cluster]) -> f32 {
+        joined_width(self.width, self.line.is_empty(), clusters)
+    }
+
+    fn push_clusters(&mut self, clusters: &[Cluster]) {
+        self.width = self.width_with(clusters);
+        for cluster in clusters {
+            push_piece(&mut self.line, cluster.run, &cluster.text, cluster.source.clone());
+        }
+    }
+
+    fn break_line(&mut self) {
+        if let Some(run) = self.pending_hyphen.take() {
+            push_added(&mut self.line, run, "-");
+        }
+        self.pending_spaces.clear();
+        self.lines.push(std::mem::take(&mut self.line));
+        self.width = 0.0;
+    }
+
+    fn add_segment(&mut self, segment: Segment) {
+        let with_spaces = self.width_with(&self.pending_spaces);
+        let empty = self.line.is_empty() && self.pending_spaces.is_empty();
+        let candidate = joined_width(with_spaces, empty, &segment.clusters);
+        if !self.line.is_empty() && !self.fits(candidate) {
+            self.break_line();
+            return self.add_segment(segment);
+        }
+        let spaces = std::mem::take(&mut self.pending_spaces);
+        self.push_clusters(&spaces);
+        if self.fits(candidate) {
+            self.push_clusters(&segment.clusters);
+        } else {
+            // Alone on the line and still too wide, break between graphemes
+            for cluster in segment.clusters.iter() {
+                let cluster = std::slice::from_ref(cluster);
+                if !self.line.is_empty() && !self.fits(self.width_with(cluster)) {
+                    self.pending_hyphen = None;
+                    self.break_line();
+                }
+                self.push_clusters(cluster);
+            }
+        }
+        self.pending_spaces = segment.spaces;
+        self.pending_hyphen = segment.hyphen;
+    }
+}
+
+/// Wraps styled runs into lines no wider than `max_width`, `None` only breaks at newlines.
+/// Empty lines hold a single empty piece so they keep their height.
+pub fn wrap_runs(runs: &[StyledRun], max_width: Option<i32>, white_space: WhiteSpace) -> Vec<Line> {
+    wrap_runs_with(runs, max_width, white_space, &measure_font)
+}
+
+fn wrap_runs_with(
+    runs: &[StyledRun],
+    max_width: Option<i32>,
+    white_space: WhiteSpace,
+    measure: Measure,
+) -> Vec<Line> {
+    if runs.is_empty() {
+        return vec![];
+    }
+    let max_width = match max_width {
+        Some(max_width) if white_space.wraps() => max_width,
+        _ => i32::MAX,
+    };
+    let mut lines = vec![];
+    for paragraph in get_paragraphs(runs, white_space) {
+        let mut builder = LineBuilder {
+            max_width,
+            lines: vec![],
+            line: vec![],
+            width: 0.0,
+            pending_spaces: vec![],
+            pending_hyphen: None,
+        };
+        for segment in get_segments(get_clusters(runs, &paragraph.chars, measure)) {
+            builder.add_segment(segment);
+        }
+        // Spaces at the very end are only kept when they are preserved
+        if !white_space.collapses_spaces() {
+            let spaces = std::mem::take(&mut builder.pending_spaces);
+            builder.push_clusters(&spaces);
+        }
+        builder.pending_hyphen = None;
+        builder.break_line();
+        let count = builder.lines.len();
+        let mut end = paragraph.start;
+        for (idx, mut pieces) in builder.lines.into_iter().enumerate() {
+            if pieces.is_empty() {
+                pieces.push(LinePiece {
+                    run: paragraph.run,
+                    text: String::new(),
+                    source: end..end,
+                });
+            }
+            let range = pieces[0].source.start..pieces[pieces.len() - 1].source.end;
+            end = range.end;
+            lines.push(Line {
+                pieces,
+                range,
+                hard_end: idx == count - 1,
+            });
+        }
+    }
+    lines
+}
+
+/// Cuts `pieces` down to fit `max_width` with `ellipsis` appended.
+/// With `force` the ellipsis is added even when the line already fits.
+pub fn ellipsize(
+    runs: &[StyledRun],
+    pieces: &[LinePiece],
+    max_width: i32,
+    ellipsis: &str,
+    force: bool,
+) -> Vec<LinePiece> {
+    ellipsize_with(runs, pieces, max_width, ellipsis, force, &measure_font)
+}
+
+fn ellipsize_with(
+    runs: &[StyledRun],
+    pieces: &[LinePiece],
+    max_width: i32,
+    ellipsis: &str,
+    force: bool,
+    measure: Measure,
+) -> Vec<LinePiece> {
+    let Some(last_run) = pieces.last().map(|piece| piece.run) else {
+        return vec![];
+    };
+    let clusters = pieces
+        .iter()
+        .flat_map(|piece| {
+            graphemes(&piece.text).into_iter().map(|text| {
+                let (width, spacing) = measure(&runs[piece.run], text);
+                Cluster {
+                    run: piece.run,
+                    text: text.to_string(),
+                    source: piece.source.clone(),
+                    width,
+                    spacing,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    // Width of the first `n` clusters at index `n`
+    let mut widths = vec![0.0];
+    for (idx, cluster) in clusters.iter().enumerate() {
+        widths.push(joined_width(widths[idx], idx == 0, std::slice::from_ref(cluster)));
+    }
+    if !force && widths[clusters.len()].ceil() as i32 <= max_width {
+        return pieces.to_vec();
+    }
+    let mut count = clusters.len();
+    loop {
+        while count > 0 && clusters[count - 1].first() == ' ' {
+            count -= 1;
+        }
+        let run = if count > 0 { clusters[count - 1].run } else { last_run };
+        let (ellipsis_width, spacing) = measure(&runs[run], ellipsis);
+        let gap = if count > 0 { spacing } else { 0.0 };
+        if count == 0 || (widths[count] + gap + ellipsis_width).ceil() as i32 <= max_width {
+            let mut candidate = with_clusters(&[], &clusters[..count]);
+            push_added(&mut candidate, run, ellipsis);
+            return candidate;
+        }
+        count -= 1;
+    }
+}
+
+/// Plain text version of [`wrap_runs`] in a single font
+pub fn wrap_lines(
+    text: &str,
+    font: &FontSpec,
+    font_size: i32,
+    max_width: Option<i32>,
+    white_space: WhiteSpace,
+) -> Vec<Line> {
+    let run = StyledRun {
+        text: text.to_string(),
+        font: font.clone(),
+        font_size,
+        color: Color::BLACK,
+        background: None,
+        underline: false,
+        link: None,
+        letter_spacing: 0,
+    };
+    wrap_runs(&[run], max_width, white_space)
+}
+
+pub fn wrap_text(
+    text: &str,
+    font: &FontSpec,
+    font_size: i32,
+    max_width: Option<i32>,
+    white_space: WhiteSpace,
+) -> Vec<String> {
+    wrap_lines(text, font, font_size, max_width, white_space)
+        .into_iter()
+        .map(|line| line.pieces.into_iter().map(|piece| piece.text).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every grapheme is 10 wide with no spacing, so widths count graphemes
+    fn measure(_run: &StyledRun, text: &str) -> (f32, f32) {
+        (graphemes(text).len() as f32 * 10.0, 0.0)
+    }
+
+    fn run(text: &str) -> StyledRun {
+        StyledRun {
+            text: text.to_string(),
+            font: FontSpec::default(),
+            font_size: 10,
+            color: Color::BLACK,
+            background: None,
+            underline: false,
+            link: None,
+            letter_spacing: 0,
+        }
+    }
+
+    fn wrap(text: &str, max_width: Option<i32>, white_space: WhiteSpace) -> Vec<Line> {
+        wrap_runs_with(&[run(text)], max_width, white_space, &measure)
+    }
+
+    fn joined(pieces: &[LinePiece]) -> String {
+        pieces.iter().map(|piece| piece.text.as_str()).collect()
+    }
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| joined(&line.pieces)).collect()
+    }
+
+    #[test]
+    fn graphemes_keep_marks_joiners_and_flags_together() {
+        assert_eq!(graphemes("e\u{301}a"), vec!["e\u{301}", "a"]);
+        assert_eq!(graphemes("👩\u{200D}👧!"), vec!["👩\u{200D}👧", "!"]);
+        assert_eq!(graphemes("🇫🇷🇩🇪"), vec!["🇫🇷", "🇩🇪"]);
+        assert_eq!(graphemes("a\r\nb"), vec!["a", "\r\n", "b"]);
+    }
+
+    #[test]
+    fn break_classes() {
+        assert!(!can_break_between('a', 'b'));
+        assert!(can_break_between('-', 'a'));
+        assert!(!can_break_between('-', '5'));
+        assert!(can_break_between('中', '文'));
+        assert!(!can_break_between('中', '。'));
+        assert!(!can_break_between('(', '中'));
+        assert!(!can_break_between('a', '\u{A0}'));
+        assert!(can_break_between(ZERO_WIDTH_SPACE, 'a'));
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        let lines = wrap("aaa bbb ccc", Some(70), WhiteSpace::Normal);
+        assert_eq!(texts(&lines), vec!["aaa bbb", "ccc"]);
+        assert!(!lines[0].hard_end);
+        assert!(lines[1].hard_end);
+    }
+
+    #[test]
+    fn breaks_long_words_between_graphemes() {
+        let lines = wrap("ab abcdefgh", Some(30), WhiteSpace::Normal);
+        assert_eq!(texts(&lines), vec!["ab", "abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn soft_hyphen_shows_only_at_a_break() {
+        let lines = wrap("ab\u{AD}cd", Some(30), WhiteSpace::Normal);
+        assert_eq!(texts(&lines), vec!["ab-", "cd"]);
+        let lines = wrap("ab\u{AD}cd", Some(100), WhiteSpace::Normal);
+        assert_eq!(texts(&lines), vec!["abcd"]);
+    }
+
+    #[test]
+    fn newlines_follow_white_space() {
+        assert_eq!(texts(&wrap("ab\ncd", None, WhiteSpace::Normal)), vec!["ab cd"]);
+        assert_eq!(texts(&wrap("ab\n\ncd", None, WhiteSpace::PreWrap)), vec!["ab", "", "cd"]);
+        assert_eq!(texts(&wrap("a\r\nb", None, WhiteSpace::Pre)), vec!["a", "b"]);
+        assert_eq!(texts(&wrap("ab  \n  cd", None, WhiteSpace::PreLine)), vec!["ab", "cd"]);
+    }
+
+    #[test]
+    fn spaces_follow_white_space() {
+        assert_eq!(texts(&wrap(" a   b ", None, WhiteSpace::NoWrap)), vec!["a b"]);
+        assert_eq!(texts(&wrap("a\tb ", None, WhiteSpace::Pre)), vec!["a    b "]);
+        assert_eq!(texts(&wrap("aaa bbb", Some(30), WhiteSpace::Pre)), vec!["aaa bbb"]);
+        assert_eq!(texts(&wrap("aaa bbb", Some(30), WhiteSpace::NoWrap)), vec!["aaa bbb"]);
+    }
+
+    #[test]
+    fn lines_carry_their_source_ranges() {
+        let lines = wrap("ab cd\n\nef", Some(20), WhiteSpace::PreWrap);
+        let ranges = lines.iter().map(|line| line.range.clone()).collect::<Vec<_>>();
+        assert_eq!(ranges, vec![0..2, 3..5, 6..6, 7..9]);
+        // Repeated text isn't mistaken for an earlier line
+        let lines = wrap("ab ab\nab", Some(20), WhiteSpace::PreWrap);
+        let ranges = lines.iter().map(|line| line.range.clone()).collect::<Vec<_>>();
+        assert_eq!(ranges, vec![0..2, 3..5, 6..8]);
+    }
+
+    #[test]
+    fn ellipsize_cuts_to_fit() {
+        let runs = [run("hello world")];
+        let pieces = &wrap_runs_with(&runs, None, WhiteSpace::Normal, &measure)[0].pieces;
+        let cut = ellipsize_with(&runs, pieces, 70, "…", false, &measure);
+        assert_eq!(joined(&cut), "hello…");
+        let kept = ellipsize_with(&runs, pieces, 200, "…", false, &measure);
+        assert_eq!(joined(&kept), "hello world");
+        let forced = ellipsize_with(&runs, pieces, 200, "…", true, &measure);
+        assert_eq!(joined(&forced), "hello world…");
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use raylib::color::Color;
+
+/// Named colours components are styled with
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// Window background
+    pub background: Color,
+    /// Inputs, cards and other raised areas
+    pub surface: Color,
+    /// Sidebars, list rows and code blocks
+    pub surface_alt: Color,
+    pub primary: Color,
+    pub on_primary: Color,
+    pub secondary: Color,
+    pub on_secondary: Color,
+    pub text: Color,
+    pub text_muted: Color,
+    pub border: Color,
+    pub link: Color,
+    pub selection: Color,
+    pub danger: Color,
+    pub on_danger: Color,
+    pub warning: Color,
+    pub on_warning: Color,
+}
+
+/// Font sizes from smallest to largest
+#[derive(Debug, Clone, Copy)]
+pub struct TypeScale {
+    pub xs: i32,
+    pub sm: i32,
+    pub md: i32,
+    pub lg: i32,
+    pub xl: i32,
+}
+
+/// Paddings and gaps from smallest to largest
+#[derive(Debug, Clone, Copy)]
+pub struct SpacingScale {
+    pub xs: i32,
+    pub sm: i32,
+    pub md: i32,
+    pub lg: i32,
+    pub xl: i32,
+}
+
+/// Corner radii, `full` rounds the short side completely
+#[derive(Debug, Clone, Copy)]
+pub struct Radii {
+    pub sm: i32,
+    pub md: i32,
+    pub lg: i32,
+    pub full: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub dark: bool,
+    pub palette: Palette,
+    pub typography: TypeScale,
+    pub spacing: SpacingScale,
+    pub radii: Radii,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            dark: false,
+            palette: Palette {
+                background: Color::new(245, 240, 230, 255),
+                surface: Color::WHITE,
+                surface_alt: Color::new(228, 224, 216, 255),
+                primary: Color::new(72, 92, 230, 255),
+                on_primary: Color::WHITE,
+                secondary: Color::new(150, 222, 156, 255),
+                on_secondary: Color::new(20, 40, 24, 255),
+                text: Color::new(30, 30, 34, 255),
+                text_muted: Color::new(120, 120, 128, 255),
+                border: Color::new(196, 196, 204, 255),
+                link: Color::new(30, 60, 180, 255),
+                selection: Color::new(0, 120, 215, 90),
+                danger: Color::new(220, 53, 69, 255),
+                on_danger: Color::WHITE,
+                warning: Color::new(255, 170, 60, 255),
+                on_warning: Color::new(30, 30, 34, 255),
+            },
+            typography: TypeScale::default(),
+            spacing: SpacingScale::default(),
+            radii: Radii::default(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            dark: true,
+            palette: Palette {
+                background: Color::new(24, 24, 28, 255),
+                surface: Color::new(38, 38, 44, 255),
+                surface_alt: Color::new(50, 50, 58, 255),
+                primary: Color::new(110, 130, 255, 255),
+                on_primary: Color::WHITE,
+                secondary: Color::new(46, 125, 80, 255),
+                on_secondary: Color::new(230, 255, 236, 255),
+                text: Color::new(230, 230, 236, 255),
+                text_muted: Color::new(150, 150, 160, 255),
+                border: Color::new(72, 72, 82, 255),
+                link: Color::new(140, 170, 255, 255),
+                selection: Color::new(90, 140, 255, 110),
+                danger: Color::new(230, 80, 90, 255),
+                on_danger: Color::WHITE,
+                warning: Color::new(200, 130, 40, 255),
+                on_warning: Color::new(20, 20, 24, 255),
+            },
+            typography: TypeScale::default(),
+            spacing: SpacingScale::default(),
+            radii: Radii::default(),
+        }
+    }
+}
+
+impl Default for TypeScale {
+    fn default() -> Self {
+        Self {
+            xs: 12,
+            sm: 16,
+            md: 20,
+            lg: 24,
+            xl: 32,
+        }
+    }
+}
+
+impl Default for SpacingScale {
+    fn default() -> Self {
+        Self {
+            xs: 2,
+            sm: 5,
+            md: 10,
+            lg: 16,
+            xl: 24,
+        }
+    }
+}
+
+impl Default for Radii {
+    fn default() -> Self {
+        Self {
+            sm: 4,
+            md: 8,
+            lg: 12,
+            full: 9999,
+        }
+    }
+}
+
+thread_local! {
+    static THEME: RefCell<Rc<Theme>> = RefCell::new(Rc::new(Theme::light()));
+    static CHANGED: Cell<bool> = Cell::new(false);
+}
+
+/// Theme components built right now are styled with
+pub fn current() -> Rc<Theme> {
+    THEME.with(|theme| theme.borrow().clone())
+}
+
+/// Switches the app's theme, the UI is rebuilt on the next frame
+pub fn set_theme(theme: Theme) {
+    THEME.with(|current| *current.borrow_mut() = Rc::new(theme));
+    CHANGED.with(|changed| changed.set(true));
+}
+
+/// Builds `f` with `theme` in place of the current one, e.g. for a dark sidebar in a light app
+pub fn provide<R>(theme: Theme, f: impl FnOnce() -> R) -> R {
+    let previous = THEME.with(|current| current.replace(Rc::new(theme)));
+    let result = f();
+    THEME.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Whether `set_theme` was called since the last check
+pub fn take_changed() -> bool {
+    CHANGED.with(|changed| changed.replace(false))
+}
+
+/// Palette entry resolved against the current theme, accepted wherever a `Color` is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorToken {
+    Background,
+    Surface,
+    SurfaceAlt,
+    Primary,
+    OnPrimary,
+    Secondary,
+    OnSecondary,
+    Text,
+    TextMuted,
+    Border,
+    Link,
+    Selection,
+    Danger,
+    OnDanger,
+    Warning,
+    OnWarning,
+}
+
+impl From<ColorToken> for Color {
+    fn from(token: ColorToken) -> Self {
+        let palette = current().palette;
+        match token {
+            ColorToken::Background => palette.background,
+            ColorToken::Surface => palette.surface,
+            ColorToken::SurfaceAlt => palette.surface_alt,
+            ColorToken::Primary => palette.primary,
+            ColorToken::OnPrimary => palette.on_primary,
+            ColorToken::Secondary => palette.secondary,
+            ColorToken::OnSecondary => palette.on_secondary,
+            ColorToken::Text => palette.text,
+            ColorToken::TextMuted => palette.text_muted,
+            ColorToken::Border => palette.border,
+            ColorToken::Link => palette.link,
+            ColorToken::Selection => palette.selection,
+            ColorToken::Danger => palette.danger,
+            ColorToken::OnDanger => palette.on_danger,
+            ColorToken::Warning => palette.warning,
+            ColorToken::OnWarning => palette.on_warning,
+        }
+    }
+}
+
+/// Step of the type scale, accepted by `font_size`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextSize {
+    Xs,
+    Sm,
+    Md,
+    Lg,
+    Xl,
+}
+
+impl From<TextSize> for i32 {
+    fn from(size: TextSize) -> Self {
+        let scale = current().typography;
+        match size {
+            TextSize::Xs => scale.xs,
+            TextSize::Sm => scale.sm,
+            TextSize::Md => scale.md,
+            TextSize::Lg => scale.lg,
+            TextSize::Xl => scale.xl,
+        }
+    }
+}
+
+/// Step of the spacing scale, accepted by `gap`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Space {
+    Xs,
+    Sm,
+    Md,
+    Lg,
+    Xl,
+}
+
+impl Space {
+    /// The same space on every side, for `padding`
+    pub fn all(self) -> (i32, i32, i32, i32) {
+        let space = self.into();
+        (space, space, space, space)
+    }
+
+    /// `x` on the left and right, `y` on the top and bottom, for `padding`
+    pub fn xy(x: Space, y: Space) -> (i32, i32, i32, i32) {
+        let (x, y) = (x.into(), y.into());
+        (x, y, x, y)
+    }
+}
+
+impl From<Space> for i32 {
+    fn from(space: Space) -> Self {
+        let scale = current().spacing;
+        match space {
+            Space::Xs => scale.xs,
+            Space::Sm => scale.sm,
+            Space::Md => scale.md,
+            Space::Lg => scale.lg,
+            Space::Xl => scale.xl,
+        }
+    }
+}
+
+/// Entry of the theme's radii, accepted by `border_radius`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radius {
+    Sm,
+    Md,
+    Lg,
+    Full,
+}
+
+impl From<Radius> for i32 {
+    fn from(radius: Radius) -> Self {
+        let radii = current().radii;
+        match radius {
+            Radius::Sm => radii.sm,
+            Radius::Md => radii.md,
+            Radius::Lg => radii.lg,
+            Radius::Full => radii.full,
+        }
+    }
+}
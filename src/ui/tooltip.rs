@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+
+use crate::ui::{
+    common::{Component, Length},
+    layout::Layout,
+    text_layout::TextLayout,
+    theme::{ColorToken, Radius, Space, TextSize},
+};
+
+/// Seconds the pointer rests on a component before its tooltip shows
+const DELAY: f64 = 0.5;
+/// Distance from the pointer to the bubble's corner
+const CURSOR_GAP: (i32, i32) = (12, 16);
+
+thread_local! {
+    static HOVER: RefCell<Hover> = RefCell::new(Hover::default());
+}
+
+/// Shown when the pointer rests on a component, set with `tooltip` on its builder
+#[derive(Clone)]
+pub enum Tooltip {
+    Text(String),
+    Component(Component),
+}
+
+impl From<&str> for Tooltip {
+    fn from(text: &str) -> Self {
+        Tooltip::Text(text.to_string())
+    }
+}
+
+impl From<String> for Tooltip {
+    fn from(text: String) -> Self {
+        Tooltip::Text(text)
+    }
+}
+
+impl From<Component> for Tooltip {
+    fn from(component: Component) -> Self {
+        Tooltip::Component(component)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Hover {
+    id: Option<String>,
+    since: f64,
+    // Pointer position when the tooltip came up
+    shown_at: Option<(i32, i32)>,
+    // Clicked since the pointer arrived, nothing shows until it leaves
+    clicked: bool,
+}
+
+/// Topmost component under `pos` with a tooltip, `roots` go from top to bottom
+pub(crate) fn hovered(roots: &[Component], pos: (i32, i32)) -> Option<String> {
+    fn find(component: &Component, pos: (i32, i32)) -> Option<String> {
+        let component = component.borrow();
+        // Later children are drawn over earlier ones
+        for child in component.get_children().iter().rev() {
+            if let Some(id) = find(child, pos) {
+                return Some(id);
+            }
+        }
+        let (x, y) = component.get_draw_pos();
+        let (w, h) = component.get_draw_dim();
+        let inside = pos.0 >= x && pos.0 <= x + w && pos.1 >= y && pos.1 <= y + h;
+        (inside && component.get_tooltip().is_some()).then(|| component.get_id())
+    }
+    roots.iter().find_map(|root| find(root, pos))
+}
+
+/// Moves the hover state on for this frame, returns whether a tooltip appeared or went away
+pub(crate) fn update(hovered: Option<String>, pos: (i32, i32), clicked: bool, time: f64) -> bool {
+    HOVER.with(|hover| {
+        let mut hover = hover.borrow_mut();
+        let was_shown = hover.shown_at.is_some();
+        if hover.id != hovered {
+            *hover = Hover {
+                id: hovered,
+                since: time,
+                ..Hover::default()
+            };
+        }
+        if clicked {
+            hover.clicked = true;
+            hover.shown_at = None;
+        }
+        if hover.id.is_some() && !hover.clicked && hover.shown_at.is_none() && time - hover.since >= DELAY {
+            hover.shown_at = Some(pos);
+        }
+        was_shown != hover.shown_at.is_some()
+    })
+}
+
+/// Id of the component whose tooltip is up and the pointer position it came up at
+pub(crate) fn shown() -> Option<(String, (i32, i32))> {
+    HOVER.with(|hover| {
+        let hover = hover.borrow();
+        Some((hover.id.clone()?, hover.shown_at?))
+    })
+}
+
+/// Box the tooltip is drawn in, text ones get the theme's tooltip styling
+pub(crate) fn bubble(tooltip: Tooltip) -> Component {
+    let content = match tooltip {
+        Tooltip::Text(text) => TextLayout::get_builder()
+            .content(&text)
+            .dim((Length::FIT, Length::FIT))
+            .font_size(TextSize::Xs)
+            .build(),
+        Tooltip::Component(component) => component,
+    };
+    Layout::get_row_builder()
+        .dim((Length::FIT, Length::FIT))
+        .padding(Space::xy(Space::Sm, Space::Xs))
+        .bg_color(ColorToken::Text)
+        .text_color(ColorToken::Background)
+        .border_radius(Radius::Sm)
+        .children(vec![content])
+        .build()
+}
+
+/// Top left of a bubble of `size` next to the pointer at `pos`, on the other side of the
+/// pointer on axes it would leave the window
+pub(crate) fn place(pos: (i32, i32), size: (i32, i32), window: (i32, i32)) -> (i32, i32) {
+    let x = if pos.0 + CURSOR_GAP.0 + size.0 > window.0 {
+        pos.0 - size.0 - CURSOR_GAP.0 / 2
+    } else {
+        pos.0 + CURSOR_GAP.0
+    };
+    let y = if pos.1 + CURSOR_GAP.1 + size.1 > window.1 {
+        pos.1 - size.1 - CURSOR_GAP.1 / 2
+    } else {
+        pos.1 + CURSOR_GAP.1
+    };
+    (x.max(0), y.max(0))
+}
@@ -0,0 +1,183 @@
+use std::{cell::RefCell, rc::Rc};
+
+use raylib::{
+    RaylibHandle,
+    color::Color,
+    consts::{ConfigFlags, KeyboardKey},
+    prelude::Image,
+};
+
+use crate::ui::{common::ResizeEvent, font};
+
+#[derive(Debug, Clone)]
+pub enum WindowIcon {
+    Path(String),
+    /// File extension (".png") and the encoded bytes
+    Bytes(String, Vec<u8>),
+}
+
+pub struct WindowConfig {
+    pub dim: (i32, i32),
+    pub title: String,
+    pub min_size: Option<(i32, i32)>,
+    pub max_size: Option<(i32, i32)>,
+    pub resizable: bool,
+    pub fullscreen: bool,
+    pub high_dpi: bool,
+    pub vsync: bool,
+    pub msaa: bool,
+    pub target_fps: u32,
+    pub icon: Option<WindowIcon>,
+    /// Background behind the UI, `None` uses the theme's background
+    pub clear_color: Option<Color>,
+    /// Fixed UI scale, `None` follows the monitor's DPI
+    pub scale_factor: Option<f32>,
+    /// Key that quits, off by default so Escape can close overlays
+    pub exit_key: Option<KeyboardKey>,
+    pub on_close_requested: Option<Rc<RefCell<dyn FnMut() -> bool>>>,
+    pub on_resize: Option<Rc<RefCell<dyn FnMut(ResizeEvent)>>>,
+}
+
+impl WindowConfig {
+    pub fn new() -> Self {
+        Self {
+            dim: (800, 600),
+            title: String::from(""),
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            fullscreen: false,
+            high_dpi: false,
+            vsync: false,
+            msaa: false,
+            target_fps: 60,
+            icon: None,
+            clear_color: None,
+            scale_factor: None,
+            exit_key: None,
+            on_close_requested: None,
+            on_resize: None,
+        }
+    }
+    pub fn dim(mut self, dim: (i32, i32)) -> Self {
+        self.dim = dim;
+        self
+    }
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+    pub fn min_size(mut self, min_size: (i32, i32)) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
+    pub fn max_size(mut self, max_size: (i32, i32)) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+    pub fn high_dpi(mut self, high_dpi: bool) -> Self {
+        self.high_dpi = high_dpi;
+        self
+    }
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+    pub fn msaa(mut self, msaa: bool) -> Self {
+        self.msaa = msaa;
+        self
+    }
+    pub fn target_fps(mut self, fps: u32) -> Self {
+        self.target_fps = fps;
+        self
+    }
+    pub fn icon(mut self, icon: WindowIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+    pub fn clear_color(mut self, color: impl Into<Color>) -> Self {
+        self.clear_color = Some(color.into());
+        self
+    }
+    pub fn scale_factor(mut self, scale: f32) -> Self {
+        self.scale_factor = Some(scale);
+        self
+    }
+    /// Key that requests closing the window, `None` (the default) disables it
+    pub fn exit_key(mut self, key: Option<KeyboardKey>) -> Self {
+        self.exit_key = key;
+        self
+    }
+    /// Called when the user tries to close the window. Returning `false` keeps it open.
+    pub fn on_close_requested(mut self, f: Box<dyn FnMut() -> bool>) -> Self {
+        self.on_close_requested = Some(Rc::new(RefCell::new(f)));
+        self
+    }
+    pub fn on_resize(mut self, f: Box<dyn FnMut(ResizeEvent)>) -> Self {
+        self.on_resize = Some(Rc::new(RefCell::new(f)));
+        self
+    }
+
+    pub(crate) fn open(&self) -> (RaylibHandle, raylib::RaylibThread) {
+        if self.high_dpi {
+            // SetConfigFlags accumulates, so this survives the builder setting its own flags
+            unsafe {
+                raylib::ffi::SetConfigFlags(ConfigFlags::FLAG_WINDOW_HIGHDPI as u32);
+            }
+        }
+        let mut builder = raylib::init();
+        builder.size(self.dim.0, self.dim.1).title(&self.title);
+        if self.resizable {
+            builder.resizable();
+        }
+        if self.fullscreen {
+            builder.fullscreen();
+        }
+        if self.vsync {
+            builder.vsync();
+        }
+        if self.msaa {
+            builder.msaa_4x();
+        }
+        let (mut rl, thread) = builder.build();
+
+        rl.set_target_fps(self.target_fps);
+        rl.set_exit_key(self.exit_key);
+        if let Some((w, h)) = self.min_size {
+            rl.set_window_min_size(w, h);
+        }
+        if let Some((w, h)) = self.max_size {
+            rl.set_window_max_size(w, h);
+        }
+        if let Some(icon) = &self.icon {
+            let image = match icon {
+                WindowIcon::Path(path) => Image::load_image(path),
+                WindowIcon::Bytes(ext, bytes) => Image::load_image_from_mem(ext, bytes),
+            };
+            match image {
+                Ok(image) => rl.set_window_icon(&image),
+                Err(err) => font::log_warning(&format!("Could not load window icon: {}", err)),
+            }
+        }
+        (rl, thread)
+    }
+
+    /// Whether a close request should actually close the window
+    pub(crate) fn allows_close(&self) -> bool {
+        match &self.on_close_requested {
+            Some(f) => {
+                let mut f = f.borrow_mut();
+                f()
+            }
+            None => true,
+        }
+    }
+}